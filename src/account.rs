@@ -0,0 +1,493 @@
+use std::fmt::Display;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use clap::Parser;
+use directories::BaseDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Client, Config, Process,
+    atproto::{
+        identity::{self, GetRecommendedDidCredentials, RequestPlcOperationSignature},
+        repo,
+        server::DescribeServer,
+        sync::{GetBlob, GetRepo},
+    },
+    auth::LoginResponse,
+    format,
+    xrpc::check,
+};
+
+/// Manage accounts across PDSes
+#[derive(Parser)]
+pub enum Account {
+    /// Move an account to another PDS, keeping its DID
+    Migrate(Migrate),
+}
+
+#[derive(Parser)]
+pub struct Migrate {
+    /// Stored account to move (defaults to the active account, also when
+    /// given without a value)
+    #[arg(long, value_name = "ACCOUNT", num_args = 0..=1)]
+    pub from_session: Option<String>,
+    /// URL of the PDS to move to
+    #[arg(long)]
+    pub to: String,
+    /// Handle on the new PDS (defaults to the current handle)
+    #[arg(long)]
+    pub handle: Option<String>,
+    /// Email for the new account (defaults to the current email)
+    #[arg(long)]
+    pub email: Option<String>,
+    /// Password for the new account
+    #[arg(long)]
+    pub password: Option<String>,
+    /// Invite code, if the new PDS requires one
+    #[arg(long)]
+    pub invite_code: Option<String>,
+    /// Code emailed after the PLC signature request; without it the
+    /// migration stops once the request is sent
+    #[arg(long)]
+    pub plc_token: Option<String>,
+    /// Name to store the new account under (defaults to `<account>-migrated`)
+    #[arg(long)]
+    pub name: Option<String>,
+    /// File recording progress, so an interrupted migration can be resumed
+    #[arg(long, default_value = "atp-migration.json")]
+    pub state: String,
+}
+
+/// The stages of a migration, in the order they run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Step {
+    CreateAccount,
+    ImportRepo,
+    ImportBlobs,
+    ImportPreferences,
+    RequestPlcSignature,
+    SignPlcOperation,
+    SubmitPlcOperation,
+    ActivateAccount,
+    DeactivateAccount,
+}
+
+impl Step {
+    const ALL: [Step; 9] = [
+        Step::CreateAccount,
+        Step::ImportRepo,
+        Step::ImportBlobs,
+        Step::ImportPreferences,
+        Step::RequestPlcSignature,
+        Step::SignPlcOperation,
+        Step::SubmitPlcOperation,
+        Step::ActivateAccount,
+        Step::DeactivateAccount,
+    ];
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Step::CreateAccount => "Created the account on the new PDS",
+            Step::ImportRepo => "Imported the repository",
+            Step::ImportBlobs => "Copied missing blobs",
+            Step::ImportPreferences => "Copied preferences",
+            Step::RequestPlcSignature => "Requested a PLC operation signature",
+            Step::SignPlcOperation => "Signed the PLC operation",
+            Step::SubmitPlcOperation => "Submitted the PLC operation",
+            Step::ActivateAccount => "Activated the new account",
+            Step::DeactivateAccount => "Deactivated the old account",
+        })
+    }
+}
+
+/// Progress of a migration, saved after every step.
+#[derive(Debug, Deserialize, Serialize)]
+struct MigrationState {
+    did: String,
+    /// Stored account being moved
+    source: String,
+    /// Stored account holding the session on the new PDS
+    target: String,
+    pds: String,
+    completed: Vec<Step>,
+    /// Kept so a failed submit can be retried without a new PLC token
+    operation: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MigrateResponse {
+    pub did: String,
+    pub pds: String,
+    pub account: String,
+    /// Steps run by this invocation
+    pub completed: Vec<Step>,
+    pub remaining: Vec<Step>,
+    pub state: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ServiceAuthResponse {
+    token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MissingBlobsResponse {
+    blobs: Vec<MissingBlob>,
+    cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MissingBlob {
+    cid: String,
+}
+
+#[async_trait]
+impl Process for Account {
+    type Output = String;
+
+    async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
+        match self {
+            Account::Migrate(cmd) => {
+                let response = cmd.process(client, config).await?;
+                format::render(config.output, &response, |response| {
+                    let mut output = format!("Migrating {} to {}\n", response.did, response.pds);
+                    for step in &response.completed {
+                        output.push_str(&format!("  ✓ {}\n", step));
+                    }
+                    if response.remaining.is_empty() {
+                        output.push_str(&format!(
+                            "Migration complete; now using account: {}",
+                            response.account
+                        ));
+                    } else if response.remaining.first() == Some(&Step::SignPlcOperation) {
+                        output.push_str(
+                            "Check the account's email for the PLC confirmation code, then \
+                             re-run with --plc-token <code> to finish",
+                        );
+                    }
+                    output
+                })
+            }
+        }
+    }
+}
+
+impl Migrate {
+    async fn read_state(&self) -> anyhow::Result<Option<MigrationState>> {
+        match tokio::fs::read(&self.state).await {
+            Ok(bytes) => {
+                Ok(Some(serde_json::from_slice(&bytes).with_context(|| {
+                    format!("Invalid migration state {}", self.state)
+                })?))
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error).with_context(|| format!("Failed to read {}", self.state)),
+        }
+    }
+
+    async fn write_state(&self, state: &MigrationState) -> anyhow::Result<()> {
+        tokio::fs::write(&self.state, serde_json::to_vec_pretty(state)?)
+            .await
+            .with_context(|| format!("Failed to write {}", self.state))
+    }
+}
+
+/// The stored config with the named account selected. Read fresh from disk
+/// so sessions created or refreshed earlier in the migration are current.
+async fn stored_config(name: &str, output: format::OutputFormat) -> anyhow::Result<Config> {
+    let base_dirs = BaseDirs::new().context("Unable to find home directory")?;
+    let mut config = Config::load(&base_dirs).await?;
+    config.select(name)?;
+    config.output = output;
+    Ok(config)
+}
+
+#[async_trait]
+impl Process for Migrate {
+    type Output = MigrateResponse;
+
+    async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
+        let source_name = match self.from_session.as_deref() {
+            Some("") => anyhow::bail!("--from-session needs an account name"),
+            Some(name) => name.to_string(),
+            None => config.account_name().context("Not logged in")?.to_string(),
+        };
+        let mut source_config = config.clone();
+        source_config.select(&source_name)?;
+        let source_session = source_config.session().cloned().context("Not logged in")?;
+        let source = Client::for_config(&source_config);
+        let target =
+            Client::with_service(self.to.as_str()).with_plc_directory(client.plc_directory());
+
+        let mut state = match self.read_state().await? {
+            Some(state) if state.did != source_session.did => anyhow::bail!(
+                "{} records a migration of {}, not {}",
+                self.state,
+                state.did,
+                source_session.did
+            ),
+            Some(state) if state.pds != target.service() => anyhow::bail!(
+                "{} records a migration to {}, not {}",
+                self.state,
+                state.pds,
+                target.service()
+            ),
+            Some(state) => state,
+            None => MigrationState {
+                did: source_session.did.clone(),
+                source: source_name.clone(),
+                target: self
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("{}-migrated", source_name)),
+                pds: target.service().to_string(),
+                completed: Vec::new(),
+                operation: None,
+            },
+        };
+
+        let mut completed = Vec::new();
+        if !state.completed.contains(&Step::CreateAccount) {
+            let session = self
+                .create_account(&source, &source_config, &target, &source_session)
+                .await
+                .context("Failed to create account")?;
+            let base_dirs = BaseDirs::new().context("Unable to find home directory")?;
+            let mut stored = Config::load(&base_dirs).await?;
+            stored.accounts.insert(
+                state.target.clone(),
+                crate::Account {
+                    pds: target.service().to_string(),
+                    session,
+                },
+            );
+            stored.write(&base_dirs).await?;
+            state.completed.push(Step::CreateAccount);
+            completed.push(Step::CreateAccount);
+            self.write_state(&state).await?;
+        }
+
+        for step in Step::ALL {
+            if state.completed.contains(&step) {
+                continue;
+            }
+            // A token given up front was already emailed; asking again sends
+            // a new one and may invalidate it
+            if step == Step::RequestPlcSignature && self.plc_token.is_some() {
+                state.completed.push(step);
+                self.write_state(&state).await?;
+                continue;
+            }
+            // Either session may have been refreshed by an earlier step
+            let source_config = stored_config(&state.source, config.output).await?;
+            let target_config = &stored_config(&state.target, config.output).await?;
+
+            match step {
+                Step::CreateAccount => unreachable!("the account is created first"),
+                Step::ImportRepo => {
+                    let dir = tempfile::tempdir()?;
+                    let out = dir.path().join("repo.car");
+                    GetRepo {
+                        did: state.did.parse()?,
+                        out: out.to_string_lossy().into_owned(),
+                        since: None,
+                    }
+                    .download(&source)
+                    .await
+                    .context("Failed to export repo")?;
+                    let car = tokio::fs::read(&out).await?;
+                    repo::import_repo(&target, target_config, car).await?;
+                }
+                Step::ImportBlobs => {
+                    copy_missing_blobs(&source, &target, target_config, &state.did).await?;
+                }
+                Step::ImportPreferences => {
+                    let preferences: serde_json::Value = source
+                        .authenticated_get(&source_config, "app.bsky.actor.getPreferences", &[])
+                        .await
+                        .context("Failed to get preferences")?;
+                    let url = target.xrpc("app.bsky.actor.putPreferences");
+                    target
+                        .send_authenticated(target_config, |http| {
+                            http.post(&url).json(&preferences)
+                        })
+                        .await
+                        .context("Failed to copy preferences")?;
+                }
+                Step::RequestPlcSignature => {
+                    RequestPlcOperationSignature
+                        .process(&source, &source_config)
+                        .await?;
+                }
+                Step::SignPlcOperation => {
+                    let Some(token) = &self.plc_token else {
+                        break;
+                    };
+                    let credentials = GetRecommendedDidCredentials
+                        .process(&target, target_config)
+                        .await?;
+                    let signed = identity::sign_plc_operation(
+                        &source,
+                        &source_config,
+                        Some(token),
+                        &credentials,
+                    )
+                    .await?;
+                    state.operation = Some(signed.operation);
+                }
+                Step::SubmitPlcOperation => {
+                    let operation = state
+                        .operation
+                        .as_ref()
+                        .context("Migration state has no signed PLC operation")?;
+                    identity::submit_plc_operation(&target, target_config, operation).await?;
+                }
+                Step::ActivateAccount => {
+                    let url = target.xrpc("com.atproto.server.activateAccount");
+                    target
+                        .send_authenticated(target_config, |http| http.post(&url))
+                        .await
+                        .context("Failed to activate the new account")?;
+                }
+                Step::DeactivateAccount => {
+                    let url = source.xrpc("com.atproto.server.deactivateAccount");
+                    source
+                        .send_authenticated(&source_config, |http| {
+                            http.post(&url).json(&serde_json::json!({}))
+                        })
+                        .await
+                        .context("Failed to deactivate the old account")?;
+                }
+            }
+
+            state.completed.push(step);
+            completed.push(step);
+            self.write_state(&state).await?;
+        }
+
+        let remaining: Vec<_> = Step::ALL
+            .into_iter()
+            .filter(|step| !state.completed.contains(step))
+            .collect();
+        if remaining.is_empty() {
+            let base_dirs = BaseDirs::new().context("Unable to find home directory")?;
+            let mut stored = Config::load(&base_dirs).await?;
+            stored.use_account(&state.target)?;
+            stored.write(&base_dirs).await?;
+        }
+
+        Ok(MigrateResponse {
+            did: state.did,
+            pds: state.pds,
+            account: state.target,
+            completed,
+            remaining,
+            state: self.state.clone(),
+        })
+    }
+}
+
+impl Migrate {
+    /// Create the account on the new PDS under the existing DID, authorized
+    /// by a service token from the old PDS.
+    async fn create_account(
+        &self,
+        source: &Client,
+        source_config: &Config,
+        target: &Client,
+        session: &LoginResponse,
+    ) -> anyhow::Result<LoginResponse> {
+        let password = self
+            .password
+            .as_deref()
+            .context("--password is required to create the new account")?;
+        // No account exists on the new PDS yet, and the old one's session
+        // must not be offered to it
+        let server = DescribeServer.process(target, &Config::default()).await?;
+        let auth: ServiceAuthResponse = source
+            .authenticated_get(
+                source_config,
+                "com.atproto.server.getServiceAuth",
+                &[
+                    ("aud", server.did),
+                    ("lxm", "com.atproto.server.createAccount".to_string()),
+                ],
+            )
+            .await
+            .context("Failed to get service auth")?;
+
+        let mut body = serde_json::json!({
+            "did": session.did,
+            "handle": self.handle.as_ref().unwrap_or(&session.handle),
+            "password": password,
+        });
+        if let Some(email) = self.email.as_ref().or(session.email.as_ref()) {
+            body["email"] = serde_json::Value::String(email.clone());
+        }
+        if let Some(invite_code) = &self.invite_code {
+            body["inviteCode"] = serde_json::Value::String(invite_code.clone());
+        }
+        let response = target
+            .inner()
+            .post(target.xrpc("com.atproto.server.createAccount"))
+            .bearer_auth(&auth.token)
+            .json(&body)
+            .send()
+            .await?;
+        Ok(check(response).await?.json().await?)
+    }
+}
+
+/// Upload every blob the new PDS reports missing, fetched from the old one.
+async fn copy_missing_blobs(
+    source: &Client,
+    target: &Client,
+    target_config: &Config,
+    did: &str,
+) -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let mut cursor = None;
+    loop {
+        let mut query = vec![("limit", "500".to_string())];
+        if let Some(cursor) = cursor {
+            query.push(("cursor", cursor));
+        }
+        let page: MissingBlobsResponse = target
+            .authenticated_get(target_config, "com.atproto.repo.listMissingBlobs", &query)
+            .await
+            .context("Failed to list missing blobs")?;
+
+        for blob in &page.blobs {
+            let out = dir.path().join("blob");
+            let fetched = GetBlob {
+                did: did.parse()?,
+                cid: blob.cid.parse()?,
+                out: Some(out.to_string_lossy().into_owned()),
+            }
+            .download(source)
+            .await
+            .with_context(|| format!("Failed to get blob {}", blob.cid))?;
+            let bytes = tokio::fs::read(&out).await?;
+            repo::upload_blob(
+                target,
+                target_config,
+                bytes,
+                fetched
+                    .mime_type
+                    .as_deref()
+                    .unwrap_or("application/octet-stream"),
+            )
+            .await
+            .with_context(|| format!("Failed to upload blob {}", blob.cid))?;
+        }
+
+        if page.blobs.is_empty() || page.cursor.is_none() {
+            return Ok(());
+        }
+        cursor = page.cursor;
+    }
+}
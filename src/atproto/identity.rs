@@ -1,58 +1,208 @@
+use std::time::Duration;
+
+use anyhow::Context;
 use async_trait::async_trait;
 use clap::Parser;
 use serde::{Deserialize, Serialize};
 
-use crate::{BASE_URL, Client, Config, Process};
+use crate::{
+    Client, Config, Process, format, input, plc,
+    resolve::{self, DidDocument, HandleMethod, HandleResolution, HandleResolver},
+    syntax::{Did, Handle},
+};
 
 #[derive(Parser)]
 pub enum Identity {
-    /// Resolve a handle to a DID
+    /// Resolve a handle to a DID through DNS or HTTPS, and check that the
+    /// DID claims the handle back
     ResolveHandle(ResolveHandle),
-    /// Resolve a DID to its DID document
+    /// Resolve a did:plc or did:web to its DID document, without a PDS
     ResolveDid(ResolveDid),
     /// Update the handle for an account
     UpdateHandle(UpdateHandle),
+    /// Walk through publishing a custom-domain handle, then switch to it
+    SetupHandle(SetupHandle),
+    /// Get the DID credentials the current PDS recommends for the account
+    GetRecommendedDidCredentials(GetRecommendedDidCredentials),
+    /// Ask the PDS to email a token for signing a PLC operation
+    RequestPlcOperationSignature(RequestPlcOperationSignature),
+    /// Have the PDS sign a PLC operation updating the account's DID document
+    SignPlcOperation(SignPlcOperation),
+    /// Submit a signed PLC operation through the PDS
+    SubmitPlcOperation(SubmitPlcOperation),
+    /// Show and verify the history of a did:plc from the PLC directory
+    PlcLog(PlcLog),
 }
 
 #[derive(Parser)]
 pub struct ResolveHandle {
     /// Handle to resolve (e.g., alice.bsky.social)
     #[arg(long)]
-    pub handle: String,
+    pub handle: Handle,
+    #[command(flatten)]
+    pub resolver: HandleResolver,
 }
 
 #[derive(Parser)]
 pub struct ResolveDid {
     /// DID to resolve (e.g., did:plc:...)
     #[arg(long)]
-    pub did: String,
+    pub did: Did,
 }
 
 #[derive(Parser)]
 pub struct UpdateHandle {
     /// New handle to set
     #[arg(long)]
-    pub handle: String,
+    pub handle: Handle,
+}
+
+#[derive(Parser)]
+pub struct SetupHandle {
+    /// Handle to switch the account to (e.g., alice.example.com)
+    #[arg(long)]
+    pub handle: Handle,
+    /// Seconds to wait between checks
+    #[arg(long, default_value = "10")]
+    pub interval: u64,
+    /// Seconds to keep checking before giving up
+    #[arg(long, default_value = "900")]
+    pub timeout: u64,
+    #[command(flatten)]
+    pub resolver: HandleResolver,
+}
+
+#[derive(Parser)]
+pub struct GetRecommendedDidCredentials;
+
+#[derive(Parser)]
+pub struct RequestPlcOperationSignature;
+
+#[derive(Parser)]
+pub struct SignPlcOperation {
+    /// Token emailed by request-plc-operation-signature
+    #[arg(long)]
+    pub token: Option<String>,
+    /// Credentials to start from, as output by get-recommended-did-credentials
+    /// with --output json: inline JSON, @path to read a file, or - for stdin
+    #[arg(long)]
+    pub credentials: Option<String>,
+    /// Rotation key as a did:key, highest priority first; may be repeated
+    #[arg(long = "rotation-key", value_name = "DID_KEY")]
+    pub rotation_keys: Vec<String>,
+    /// Identity alias such as at://alice.example.com; may be repeated
+    #[arg(long = "also-known-as", value_name = "URI")]
+    pub also_known_as: Vec<String>,
+    /// Verification method as NAME=DID_KEY (e.g. atproto=did:key:z...); may be repeated
+    #[arg(long = "verification-method", value_name = "NAME=DID_KEY")]
+    pub verification_methods: Vec<String>,
+    /// Service as NAME=ENDPOINT or NAME=TYPE=ENDPOINT (e.g.
+    /// atproto_pds=https://pds.example.com); may be repeated
+    #[arg(long = "service", value_name = "NAME=ENDPOINT")]
+    pub services: Vec<String>,
+}
+
+#[derive(Parser)]
+pub struct SubmitPlcOperation {
+    /// Signed operation, or the output of sign-plc-operation with --output
+    /// json: inline JSON, @path to read a file, or - for stdin
+    #[arg(long)]
+    pub operation: String,
+}
+
+#[derive(Parser)]
+pub struct PlcLog {
+    /// DID to show the history of (e.g., did:plc:...)
+    #[arg(long)]
+    pub did: Did,
+}
+
+/// The DID document fields a PLC operation sets. Each is left unchanged
+/// when absent.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct DidCredentials {
+    #[serde(
+        rename = "rotationKeys",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub rotation_keys: Option<Vec<String>>,
+    #[serde(
+        rename = "alsoKnownAs",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub also_known_as: Option<Vec<String>>,
+    #[serde(
+        rename = "verificationMethods",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub verification_methods: Option<serde_json::Map<String, serde_json::Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub services: Option<serde_json::Map<String, serde_json::Value>>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
-pub struct ResolveHandleResponse {
+pub struct SignPlcOperationResponse {
+    pub operation: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SetupHandleResponse {
+    pub handle: String,
     pub did: String,
+    /// Whether the `_atproto` TXT record points at the account
+    pub dns: bool,
+    /// Whether the `atproto-did` file points at the account
+    pub http: bool,
+    /// Whether the handle resolved back and forth once updated
+    pub valid: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlcLogResponse {
+    pub did: String,
+    pub operations: Vec<PlcLogOperation>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlcLogOperation {
+    pub cid: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub nullified: bool,
+    /// Rotation key that signed the operation
+    #[serde(rename = "signedBy")]
+    pub signed_by: String,
+    /// Human-readable changes from the operation this one follows
+    pub changes: Vec<String>,
+    /// Identity after the operation; absent for a tombstone
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<plc::State>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ResolveDidResponse {
     pub did: String,
     #[serde(rename = "didDoc")]
-    pub did_doc: serde_json::Value,
+    pub did_doc: DidDocument,
 }
 
 impl Identity {
     pub fn needs_authentication(&self) -> bool {
         match self {
             Identity::ResolveHandle(_) => false, // Public endpoint
-            Identity::ResolveDid(_) => true,     // Requires auth (returns 401)
+            Identity::ResolveDid(_) => false,    // Resolved locally
             Identity::UpdateHandle(_) => true,   // Requires auth
+            Identity::SetupHandle(_) => true,    // Requires auth
+            Identity::GetRecommendedDidCredentials(_) => true, // Requires auth
+            Identity::RequestPlcOperationSignature(_) => true, // Requires auth
+            Identity::SignPlcOperation(_) => true, // Requires auth
+            Identity::SubmitPlcOperation(_) => true, // Requires auth
+            Identity::PlcLog(_) => false,        // Public PLC directory
         }
     }
 }
@@ -65,19 +215,92 @@ impl Process for Identity {
         match self {
             Identity::ResolveHandle(cmd) => {
                 let response = cmd.process(client, config).await?;
-                Ok(format!("DID: {}", response.did))
+                format::render(config.output, &response, |response| {
+                    format!(
+                        "DID: {}\nMethod: {}\nValid: {}",
+                        response.did,
+                        response.method,
+                        if response.valid {
+                            "yes".to_string()
+                        } else {
+                            format!(
+                                "no (the DID document does not list at://{})",
+                                response.handle
+                            )
+                        }
+                    )
+                })
             }
             Identity::ResolveDid(cmd) => {
                 let response = cmd.process(client, config).await?;
-                Ok(format!(
-                    "DID: {}\nDocument: {}",
-                    response.did,
-                    serde_json::to_string_pretty(&response.did_doc)?
-                ))
+                format::render(config.output, &response, |response| {
+                    let doc = &response.did_doc;
+                    let signing_key = doc
+                        .verification_method("atproto")
+                        .and_then(|method| method.public_key_multibase.as_deref());
+                    format!(
+                        "DID: {}\nHandle: {}\nPDS: {}\nSigning key: {}\nDocument: {}",
+                        response.did,
+                        doc.handle().unwrap_or("(none)"),
+                        doc.pds_endpoint().as_deref().unwrap_or("(none)"),
+                        signing_key.unwrap_or("(none)"),
+                        format::format_json(doc)
+                    )
+                })
             }
             Identity::UpdateHandle(cmd) => {
                 cmd.process(client, config).await?;
-                Ok("Handle updated successfully".to_string())
+                format::render_success(config.output, "Handle updated successfully")
+            }
+            Identity::SetupHandle(cmd) => {
+                let response = cmd.process(client, config).await?;
+                format::render(config.output, &response, |response| {
+                    let methods: Vec<_> = [
+                        (response.dns, HandleMethod::Dns),
+                        (response.http, HandleMethod::Http),
+                    ]
+                    .into_iter()
+                    .filter(|(ok, _)| *ok)
+                    .map(|(_, method)| method.to_string())
+                    .collect();
+                    let mut output = format!(
+                        "Handle updated to {}\nVerified through: {}",
+                        response.handle,
+                        methods.join(", ")
+                    );
+                    if !response.valid {
+                        output.push_str(
+                            "\nThe DID document does not list the handle yet; it may take a \
+                             few minutes to update",
+                        );
+                    }
+                    output
+                })
+            }
+            Identity::GetRecommendedDidCredentials(cmd) => {
+                let response = cmd.process(client, config).await?;
+                format::render(config.output, &response, format_credentials)
+            }
+            Identity::RequestPlcOperationSignature(cmd) => {
+                cmd.process(client, config).await?;
+                format::render_success(
+                    config.output,
+                    "Signature requested; check the account's email for the token",
+                )
+            }
+            Identity::SignPlcOperation(cmd) => {
+                let response = cmd.process(client, config).await?;
+                format::render(config.output, &response, |response| {
+                    format::format_json(&response.operation)
+                })
+            }
+            Identity::SubmitPlcOperation(cmd) => {
+                cmd.process(client, config).await?;
+                format::render_success(config.output, "PLC operation submitted successfully")
+            }
+            Identity::PlcLog(cmd) => {
+                let response = cmd.process(client, config).await?;
+                format::render(config.output, &response, format_plc_log)
             }
         }
     }
@@ -85,23 +308,10 @@ impl Process for Identity {
 
 #[async_trait]
 impl Process for ResolveHandle {
-    type Output = ResolveHandleResponse;
+    type Output = HandleResolution;
 
     async fn process(&self, client: &Client, _config: &Config) -> anyhow::Result<Self::Output> {
-        let url = format!("{}/com.atproto.identity.resolveHandle", BASE_URL);
-        let response = client
-            .inner()
-            .get(&url)
-            .query(&[("handle", &self.handle)])
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            anyhow::bail!("Failed to resolve handle: {}", response.status());
-        }
-
-        let response: ResolveHandleResponse = response.json().await?;
-        Ok(response)
+        self.resolver.resolve(client, self.handle.as_str()).await
     }
 }
 
@@ -109,57 +319,416 @@ impl Process for ResolveHandle {
 impl Process for ResolveDid {
     type Output = ResolveDidResponse;
 
+    async fn process(&self, client: &Client, _config: &Config) -> anyhow::Result<Self::Output> {
+        let did_doc = resolve::resolve_did_document(client, self.did.as_str())
+            .await
+            .context("Failed to resolve DID")?;
+        Ok(ResolveDidResponse {
+            did: self.did.to_string(),
+            did_doc,
+        })
+    }
+}
+
+#[async_trait]
+impl Process for UpdateHandle {
+    type Output = ();
+
+    async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
+        let url = client.xrpc("com.atproto.identity.updateHandle");
+        let body = serde_json::json!({
+            "handle": self.handle
+        });
+
+        client
+            .send_authenticated(config, |http| http.post(&url).json(&body))
+            .await
+            .context("Failed to update handle")?;
+
+        Ok(())
+    }
+}
+
+/// What one method currently says about a handle, for progress output.
+fn describe_check(result: &anyhow::Result<String>, did: &str) -> String {
+    match result {
+        Ok(found) if found == did => "ok".to_string(),
+        Ok(found) => format!("points at {} instead", found),
+        Err(error) => format!("{:#}", error),
+    }
+}
+
+#[async_trait]
+impl Process for SetupHandle {
+    type Output = SetupHandleResponse;
+
     async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
         let session = config
-            .session
-            .as_ref()
+            .session()
             .ok_or_else(|| anyhow::anyhow!("Not logged in"))?;
+        let handle = self.handle.as_str();
+        let did = &session.did;
 
-        let url = format!("{}/com.atproto.identity.resolveDid", BASE_URL);
-        let response = client
-            .inner()
-            .get(&url)
-            .query(&[("did", &self.did)])
-            .header("Authorization", format!("Bearer {}", session.access_jwt))
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            anyhow::bail!("Failed to resolve DID: {}", response.status());
+        // Guidance goes to stderr so --output keeps stdout machine-readable
+        eprintln!(
+            "To use {handle} as the handle of {did}, publish either:\n\n  \
+             a DNS TXT record\n    Name:  _atproto.{handle}\n    Value: did={did}\n\n  \
+             or a file at https://{handle}/.well-known/atproto-did containing only\n    \
+             {did}\n"
+        );
+        eprintln!(
+            "Waiting for {} to resolve (checking every {}s)...",
+            handle, self.interval
+        );
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(self.timeout);
+        let (dns, http) = loop {
+            let dns = self.resolver.dns(handle).await;
+            let http = self.resolver.http(client, handle).await;
+            let matches =
+                |result: &anyhow::Result<String>| result.as_ref().is_ok_and(|found| found == did);
+            // A method pointing elsewhere would win over, or contradict, the
+            // one that is right, so both have to agree
+            let conflicting = [&dns, &http]
+                .iter()
+                .any(|result| result.as_ref().is_ok_and(|found| found != did));
+            if (matches(&dns) || matches(&http)) && !conflicting {
+                break (matches(&dns), matches(&http));
+            }
+
+            let status = format!(
+                "  DNS: {}\n  HTTPS: {}",
+                describe_check(&dns, did),
+                describe_check(&http, did)
+            );
+            if tokio::time::Instant::now() >= deadline {
+                anyhow::bail!(
+                    "Timed out waiting for {} to resolve to {}\n{}",
+                    handle,
+                    did,
+                    status
+                );
+            }
+            eprintln!("{}", status);
+            tokio::time::sleep(Duration::from_secs(self.interval)).await;
+        };
+
+        UpdateHandle {
+            handle: self.handle.clone(),
+        }
+        .process(client, config)
+        .await?;
+
+        let valid = self
+            .resolver
+            .resolve(client, handle)
+            .await
+            .is_ok_and(|resolution| resolution.did == *did && resolution.valid);
+        Ok(SetupHandleResponse {
+            handle: handle.to_string(),
+            did: did.clone(),
+            dns,
+            http,
+            valid,
+        })
+    }
+}
+
+fn format_plc_log(response: &PlcLogResponse) -> String {
+    let mut output = format!(
+        "PLC log for {} ({} operations, all signatures verified)\n",
+        response.did,
+        response.operations.len()
+    );
+    for operation in &response.operations {
+        output.push_str(&format!(
+            "\n{}  {}  {}{}\n",
+            operation.created_at,
+            operation.type_,
+            operation.cid,
+            if operation.nullified {
+                " (nullified)"
+            } else {
+                ""
+            }
+        ));
+        for change in &operation.changes {
+            output.push_str(&format!("  {}\n", change));
+        }
+        output.push_str(&format!("  signed by {}\n", operation.signed_by));
+    }
+    output.trim_end().to_string()
+}
+
+fn format_credentials(credentials: &DidCredentials) -> String {
+    let mut output = String::new();
+    if let Some(keys) = &credentials.rotation_keys {
+        output.push_str("Rotation keys:\n");
+        for key in keys {
+            output.push_str(&format!("  {}\n", key));
+        }
+    }
+    if let Some(aliases) = &credentials.also_known_as {
+        output.push_str("Also known as:\n");
+        for alias in aliases {
+            output.push_str(&format!("  {}\n", alias));
+        }
+    }
+    if let Some(methods) = &credentials.verification_methods {
+        output.push_str("Verification methods:\n");
+        for (name, key) in methods {
+            output.push_str(&format!(
+                "  {}: {}\n",
+                name,
+                key.as_str().unwrap_or_default()
+            ));
+        }
+    }
+    if let Some(services) = &credentials.services {
+        output.push_str("Services:\n");
+        for (name, service) in services {
+            output.push_str(&format!(
+                "  {}: {} {}\n",
+                name,
+                service["type"].as_str().unwrap_or_default(),
+                service["endpoint"].as_str().unwrap_or_default()
+            ));
         }
+    }
+    output.trim_end().to_string()
+}
 
-        let response: ResolveDidResponse = response.json().await?;
-        Ok(response)
+#[async_trait]
+impl Process for GetRecommendedDidCredentials {
+    type Output = DidCredentials;
+
+    async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
+        client
+            .authenticated_get(
+                config,
+                "com.atproto.identity.getRecommendedDidCredentials",
+                &[],
+            )
+            .await
+            .context("Failed to get recommended DID credentials")
     }
 }
 
 #[async_trait]
-impl Process for UpdateHandle {
+impl Process for RequestPlcOperationSignature {
     type Output = ();
 
     async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
-        let session = config
-            .session
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Not logged in"))?;
+        let url = client.xrpc("com.atproto.identity.requestPlcOperationSignature");
+        client
+            .send_authenticated(config, |http| http.post(&url))
+            .await
+            .context("Failed to request PLC operation signature")?;
+        Ok(())
+    }
+}
 
-        let url = format!("{}/com.atproto.identity.updateHandle", BASE_URL);
-        let body = serde_json::json!({
-            "handle": self.handle
-        });
+/// Split a `NAME=VALUE` argument.
+fn split_pair<'a>(arg: &'a str, flag: &str) -> anyhow::Result<(&'a str, &'a str)> {
+    arg.split_once('=')
+        .filter(|(name, value)| !name.is_empty() && !value.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("Invalid {} '{}': expected NAME=VALUE", flag, arg))
+}
 
-        let response = client
-            .inner()
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", session.access_jwt))
-            .json(&body)
-            .send()
-            .await?;
+impl SignPlcOperation {
+    /// The credentials to sign: `--credentials`, with each field given by a
+    /// flag replacing it.
+    async fn credentials(&self) -> anyhow::Result<DidCredentials> {
+        let mut credentials = match &self.credentials {
+            Some(arg) => serde_json::from_str(&input::read_arg(arg).await?)
+                .context("Invalid credentials JSON")?,
+            None => DidCredentials::default(),
+        };
+        if !self.rotation_keys.is_empty() {
+            credentials.rotation_keys = Some(self.rotation_keys.clone());
+        }
+        if !self.also_known_as.is_empty() {
+            credentials.also_known_as = Some(self.also_known_as.clone());
+        }
+        if !self.verification_methods.is_empty() {
+            let mut methods = serde_json::Map::new();
+            for arg in &self.verification_methods {
+                let (name, key) = split_pair(arg, "--verification-method")?;
+                methods.insert(name.to_string(), key.into());
+            }
+            credentials.verification_methods = Some(methods);
+        }
+        if !self.services.is_empty() {
+            let mut services = serde_json::Map::new();
+            for arg in &self.services {
+                let (name, rest) = split_pair(arg, "--service")?;
+                let (kind, endpoint) = match rest.split_once('=') {
+                    Some((kind, endpoint)) => (kind, endpoint),
+                    None => match name {
+                        "atproto_pds" => ("AtprotoPersonalDataServer", rest),
+                        "atproto_labeler" => ("AtprotoLabeler", rest),
+                        _ => anyhow::bail!(
+                            "Invalid --service '{}': expected NAME=TYPE=ENDPOINT",
+                            arg
+                        ),
+                    },
+                };
+                services.insert(
+                    name.to_string(),
+                    serde_json::json!({ "type": kind, "endpoint": endpoint }),
+                );
+            }
+            credentials.services = Some(services);
+        }
+        Ok(credentials)
+    }
+}
+
+/// Ask the PDS to sign a PLC operation setting `credentials`, authorized by
+/// an emailed `token`.
+pub(crate) async fn sign_plc_operation(
+    client: &Client,
+    config: &Config,
+    token: Option<&str>,
+    credentials: &DidCredentials,
+) -> anyhow::Result<SignPlcOperationResponse> {
+    let mut body = serde_json::to_value(credentials)?;
+    if let Some(token) = token {
+        body["token"] = token.into();
+    }
+    client
+        .authenticated_post(config, "com.atproto.identity.signPlcOperation", &body)
+        .await
+        .context("Failed to sign PLC operation")
+}
+
+#[async_trait]
+impl Process for SignPlcOperation {
+    type Output = SignPlcOperationResponse;
+
+    async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
+        let credentials = self.credentials().await?;
+        sign_plc_operation(client, config, self.token.as_deref(), &credentials).await
+    }
+}
+
+/// Submit a signed PLC operation through the PDS, which forwards it to the
+/// PLC directory.
+pub(crate) async fn submit_plc_operation(
+    client: &Client,
+    config: &Config,
+    operation: &serde_json::Value,
+) -> anyhow::Result<()> {
+    let url = client.xrpc("com.atproto.identity.submitPlcOperation");
+    let body = serde_json::json!({ "operation": operation });
+    client
+        .send_authenticated(config, |http| http.post(&url).json(&body))
+        .await
+        .context("Failed to submit PLC operation")?;
+    Ok(())
+}
+
+#[async_trait]
+impl Process for SubmitPlcOperation {
+    type Output = ();
+
+    async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
+        let operation: serde_json::Value =
+            serde_json::from_str(&input::read_arg(&self.operation).await?)
+                .context("Invalid operation JSON")?;
+        // Accept sign-plc-operation's output as well as the bare operation
+        let operation = match operation.get("operation") {
+            Some(inner) if operation.get("sig").is_none() => inner.clone(),
+            _ => operation,
+        };
+        submit_plc_operation(client, config, &operation).await
+    }
+}
+
+/// Describe what changed between two identity states. A missing `before`
+/// is the genesis operation, a missing `after` a tombstone.
+fn plc_changes(before: Option<&plc::State>, after: Option<&plc::State>) -> Vec<String> {
+    let Some(after) = after else {
+        return vec!["DID deactivated (tombstone)".to_string()];
+    };
+    let handles = |state: &plc::State| {
+        state
+            .also_known_as
+            .iter()
+            .map(|alias| alias.strip_prefix("at://").unwrap_or(alias))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let fields = |state: &plc::State| {
+        [
+            ("handle", handles(state)),
+            ("pds", state.pds.clone().unwrap_or_default()),
+            ("signing key", state.signing_key.clone().unwrap_or_default()),
+            ("rotation keys", state.rotation_keys.join(", ")),
+        ]
+    };
 
-        if !response.status().is_success() {
-            anyhow::bail!("Failed to update handle: {}", response.status());
+    let new = fields(after);
+    let old = before.map(fields);
+    let mut changes = Vec::new();
+    for (index, (label, value)) in new.iter().enumerate() {
+        let show = |value: &str| {
+            if value.is_empty() {
+                "(none)".to_string()
+            } else {
+                value.to_string()
+            }
+        };
+        match &old {
+            None => changes.push(format!("{}: {}", label, show(value))),
+            Some(old) if old[index].1 != *value => changes.push(format!(
+                "{}: {} → {}",
+                label,
+                show(&old[index].1),
+                show(value)
+            )),
+            Some(_) => {}
         }
+    }
+    changes
+}
 
-        Ok(())
+#[async_trait]
+impl Process for PlcLog {
+    type Output = PlcLogResponse;
+
+    async fn process(&self, client: &Client, _config: &Config) -> anyhow::Result<Self::Output> {
+        if !self.did.as_str().starts_with("did:plc:") {
+            anyhow::bail!("Only did:plc identities have a PLC log, not {}", self.did);
+        }
+        let log = plc::audit_log(client, self.did.as_str()).await?;
+        let verified = plc::verify_log(self.did.as_str(), log)?;
+
+        let operations = verified
+            .iter()
+            .map(|operation| {
+                let before = operation
+                    .prev
+                    .and_then(|index| verified[index].state.as_ref());
+                let type_ = match (&operation.state, operation.prev) {
+                    (None, _) => "tombstone",
+                    (Some(_), None) => "create",
+                    (Some(_), Some(_)) => "update",
+                };
+                PlcLogOperation {
+                    cid: operation.entry.cid.clone(),
+                    created_at: operation.entry.created_at.clone(),
+                    type_: type_.to_string(),
+                    nullified: operation.entry.nullified,
+                    signed_by: operation.signed_by.clone(),
+                    changes: plc_changes(before, operation.state.as_ref()),
+                    state: operation.state.clone(),
+                }
+            })
+            .collect();
+        Ok(PlcLogResponse {
+            did: self.did.to_string(),
+            operations,
+        })
     }
 }
@@ -1,8 +1,18 @@
+use std::str::FromStr;
+
+use anyhow::Context;
 use async_trait::async_trait;
 use clap::Parser;
 use serde::{Deserialize, Serialize};
 
-use crate::{BASE_URL, Client, Config, Process};
+use crate::{
+    Client, Config, Process, format, input,
+    resolve::HandleResolver,
+    syntax::{AtIdentifier, AtUri, Cid, Nsid, RecordKey},
+};
+
+/// The most writes a PDS accepts in a single `applyWrites` call.
+const MAX_WRITES_PER_CALL: usize = 200;
 
 #[derive(Parser)]
 pub enum Repo {
@@ -12,8 +22,12 @@ pub enum Repo {
     GetRecord(GetRecord),
     /// List records in a collection
     ListRecords(ListRecords),
+    /// Create or update a record in place
+    PutRecord(PutRecord),
     /// Delete a record from a repository
     DeleteRecord(DeleteRecord),
+    /// Apply a batch of creates, updates and deletes from a JSONL file
+    ApplyWrites(ApplyWrites),
     /// Upload a blob to the repository
     UploadBlob(UploadBlob),
     /// Describe a repository
@@ -24,58 +38,125 @@ pub enum Repo {
 pub struct CreateRecord {
     /// Repository DID or handle
     #[arg(long)]
-    pub repo: String,
+    pub repo: AtIdentifier,
     /// Collection name (e.g., app.bsky.feed.post)
     #[arg(long)]
-    pub collection: String,
-    /// Record data as JSON
+    pub collection: Nsid,
+    /// Record data as JSON, @path to read it from a file, or - for stdin.
+    /// JSONL input creates one record per line. `$type` and `createdAt` are
+    /// filled in when missing
     #[arg(long)]
     pub record: String,
-    /// Optional record key
+    /// Optional record key (single record only)
     #[arg(long)]
-    pub rkey: Option<String>,
+    pub rkey: Option<RecordKey>,
 }
 
-#[derive(Parser)]
-pub struct GetRecord {
+/// The record a command acts on, given as a URI or as its three parts.
+#[derive(clap::Args)]
+pub struct RecordRef {
+    /// Record as an at:// URI or a https://bsky.app/profile/<handle>/post/<rkey>
+    /// link, instead of --repo, --collection and --rkey
+    #[arg(value_name = "URI", conflicts_with_all = ["repo", "collection", "rkey"])]
+    pub uri: Option<RecordUri>,
     /// Repository DID or handle
-    #[arg(long)]
-    pub repo: String,
+    #[arg(long, required_unless_present = "uri")]
+    pub repo: Option<AtIdentifier>,
     /// Collection name
-    #[arg(long)]
-    pub collection: String,
+    #[arg(long, required_unless_present = "uri")]
+    pub collection: Option<Nsid>,
     /// Record key
-    #[arg(long)]
-    pub rkey: String,
+    #[arg(long, required_unless_present = "uri")]
+    pub rkey: Option<RecordKey>,
+    #[command(flatten)]
+    pub resolver: HandleResolver,
+}
+
+/// An AT-URI naming a single record.
+#[derive(Clone, Debug)]
+pub struct RecordUri {
+    pub repo: AtIdentifier,
+    pub collection: Nsid,
+    pub rkey: RecordKey,
+}
+
+#[derive(Parser)]
+pub struct GetRecord {
+    #[command(flatten)]
+    pub target: RecordRef,
 }
 
 #[derive(Parser)]
 pub struct ListRecords {
     /// Repository DID or handle
     #[arg(long)]
-    pub repo: String,
+    pub repo: AtIdentifier,
     /// Collection name
     #[arg(long)]
-    pub collection: String,
-    /// Maximum number of records to return
+    pub collection: Nsid,
+    /// Maximum number of records to return (per page with --all or --max)
     #[arg(long, default_value = "50")]
     pub limit: u32,
     /// Cursor for pagination
     #[arg(long)]
     pub cursor: Option<String>,
+    /// Follow the cursor through every page, streaming records as JSONL
+    #[arg(long)]
+    pub all: bool,
+    /// Like --all, but stop after this many records
+    #[arg(long)]
+    pub max: Option<usize>,
+    /// List records in reverse order
+    #[arg(long)]
+    pub reverse: bool,
+}
+
+#[derive(Parser)]
+pub struct PutRecord {
+    #[command(flatten)]
+    pub target: RecordRef,
+    /// Record data as JSON, @path to read it from a file, or - for stdin
+    #[arg(long)]
+    pub record: String,
+    /// Only update if the record's current CID matches
+    #[arg(long)]
+    pub swap_record: Option<Cid>,
+    /// Only update if the repository's current commit CID matches
+    #[arg(long)]
+    pub swap_commit: Option<Cid>,
+    /// Require (true) or skip (false) lexicon validation; by default the
+    /// server validates records of known types only
+    #[arg(long)]
+    pub validate: Option<bool>,
 }
 
 #[derive(Parser)]
 pub struct DeleteRecord {
+    #[command(flatten)]
+    pub target: RecordRef,
+}
+
+#[derive(Parser)]
+pub struct ApplyWrites {
     /// Repository DID or handle
     #[arg(long)]
-    pub repo: String,
-    /// Collection name
+    pub repo: AtIdentifier,
+    /// JSONL file of writes, one per line, or - for stdin. Each line looks
+    /// like {"action": "create", "collection": "...", "rkey": "...", "value": {...}}
+    /// where action is create, update or delete
     #[arg(long)]
-    pub collection: String,
-    /// Record key
+    pub file: String,
+    /// Only apply if the repository's current commit CID matches; later
+    /// batches are chained to the commit returned by the previous one
     #[arg(long)]
-    pub rkey: String,
+    pub swap_commit: Option<Cid>,
+    /// Require (true) or skip (false) lexicon validation; by default the
+    /// server validates records of known types only
+    #[arg(long)]
+    pub validate: Option<bool>,
+    /// Number of writes sent per applyWrites call
+    #[arg(long, default_value_t = MAX_WRITES_PER_CALL, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=MAX_WRITES_PER_CALL as u64))]
+    pub batch_size: usize,
 }
 
 #[derive(Parser)]
@@ -89,7 +170,7 @@ pub struct UploadBlob {
 pub struct DescribeRepo {
     /// Repository DID or handle
     #[arg(long)]
-    pub repo: String,
+    pub repo: AtIdentifier,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -105,6 +186,67 @@ pub struct GetRecordResponse {
     pub value: serde_json::Value,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PutRecordResponse {
+    pub uri: String,
+    pub cid: String,
+    #[serde(rename = "validationStatus")]
+    pub validation_status: Option<String>,
+}
+
+/// One line of an `apply-writes` input file.
+#[derive(Debug, Deserialize)]
+pub struct WriteOp {
+    pub action: WriteAction,
+    pub collection: Nsid,
+    pub rkey: Option<RecordKey>,
+    pub value: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WriteAction {
+    Create,
+    Update,
+    Delete,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ApplyWritesResponse {
+    pub commit: Option<CommitMeta>,
+    pub results: Vec<WriteResult>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CommitMeta {
+    pub cid: String,
+    pub rev: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WriteResult {
+    pub action: WriteAction,
+    pub uri: String,
+    pub cid: Option<String>,
+    #[serde(rename = "validationStatus")]
+    pub validation_status: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApplyWritesBatchResponse {
+    commit: Option<CommitMeta>,
+    #[serde(default)]
+    results: Vec<ApplyWritesBatchResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApplyWritesBatchResult {
+    uri: Option<String>,
+    cid: Option<String>,
+    #[serde(rename = "validationStatus")]
+    validation_status: Option<String>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ListRecordsResponse {
     pub records: Vec<RecordItem>,
@@ -151,7 +293,9 @@ impl Repo {
             Repo::CreateRecord(_) => true,  // Requires auth
             Repo::GetRecord(_) => false,    // Public endpoint
             Repo::ListRecords(_) => false,  // Public endpoint
+            Repo::PutRecord(_) => true,     // Requires auth
             Repo::DeleteRecord(_) => true,  // Requires auth
+            Repo::ApplyWrites(_) => true,   // Requires auth
             Repo::UploadBlob(_) => true,    // Requires auth
             Repo::DescribeRepo(_) => false, // Public endpoint
         }
@@ -165,94 +309,245 @@ impl Process for Repo {
     async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
         match self {
             Repo::CreateRecord(cmd) => {
-                let response = cmd.process(client, config).await?;
-                Ok(format!(
-                    "Created record: {}\nCID: {}",
-                    response.uri, response.cid
-                ))
+                let mut responses = cmd.process(client, config).await?;
+                if responses.len() == 1 {
+                    let response = responses.remove(0);
+                    return format::render(config.output, &response, |response| {
+                        format!("Created record: {}\nCID: {}", response.uri, response.cid)
+                    });
+                }
+                format::render(config.output, &responses, |responses| {
+                    let mut output = format!("Created {} records:\n", responses.len());
+                    for response in responses {
+                        output.push_str(&format!("  {}: {}\n", response.uri, response.cid));
+                    }
+                    output
+                })
             }
             Repo::GetRecord(cmd) => {
                 let response = cmd.process(client, config).await?;
-                Ok(format!(
-                    "URI: {}\nCID: {}\nValue: {}",
-                    response.uri,
-                    response.cid,
-                    serde_json::to_string_pretty(&response.value)?
-                ))
+                format::render(config.output, &response, |response| {
+                    format!(
+                        "URI: {}\nCID: {}\nValue: {}",
+                        response.uri,
+                        response.cid,
+                        format::format_json(&response.value)
+                    )
+                })
+            }
+            Repo::ListRecords(cmd) if cmd.all || cmd.max.is_some() => {
+                cmd.stream(client).await?;
+                Ok(String::new())
             }
             Repo::ListRecords(cmd) => {
                 let response = cmd.process(client, config).await?;
-                let mut output = format!("Found {} records:\n", response.records.len());
-                for record in response.records {
-                    output.push_str(&format!("  {}: {}\n", record.uri, record.cid));
-                }
-                if let Some(cursor) = response.cursor {
-                    output.push_str(&format!("Cursor: {}\n", cursor));
-                }
-                Ok(output)
+                format::render(config.output, &response, |response| {
+                    let mut output = format!("Found {} records:\n", response.records.len());
+                    for record in &response.records {
+                        output.push_str(&format!("  {}: {}\n", record.uri, record.cid));
+                    }
+                    if let Some(cursor) = &response.cursor {
+                        output.push_str(&format!("Cursor: {}\n", cursor));
+                    }
+                    output
+                })
+            }
+            Repo::PutRecord(cmd) => {
+                let response = cmd.process(client, config).await?;
+                format::render(config.output, &response, |response| {
+                    format!("Updated record: {}\nCID: {}", response.uri, response.cid)
+                })
             }
             Repo::DeleteRecord(cmd) => {
                 cmd.process(client, config).await?;
-                Ok("Record deleted successfully".to_string())
+                format::render_success(config.output, "Record deleted successfully")
+            }
+            Repo::ApplyWrites(cmd) => {
+                let response = cmd.process(client, config).await?;
+                format::render(config.output, &response, |response| {
+                    let mut output = format!("Applied {} writes:\n", response.results.len());
+                    for result in &response.results {
+                        let action = format!("{:?}", result.action).to_lowercase();
+                        output.push_str(&format!("  {action} {}", result.uri));
+                        if let Some(cid) = &result.cid {
+                            output.push_str(&format!(": {cid}"));
+                        }
+                        output.push('\n');
+                    }
+                    if let Some(commit) = &response.commit {
+                        output.push_str(&format!("Commit: {} (rev {})\n", commit.cid, commit.rev));
+                    }
+                    output
+                })
             }
             Repo::UploadBlob(cmd) => {
                 let response = cmd.process(client, config).await?;
-                Ok(format!(
-                    "Uploaded blob: {} bytes, type: {}",
-                    response.blob.size, response.blob.mime_type
-                ))
+                format::render(config.output, &response, |response| {
+                    format!(
+                        "Uploaded blob: {} bytes, type: {}",
+                        response.blob.size, response.blob.mime_type
+                    )
+                })
             }
             Repo::DescribeRepo(cmd) => {
                 let response = cmd.process(client, config).await?;
-                Ok(format!(
-                    "Handle: {}\nDID: {}\nCollections: {}\nHandle correct: {}",
-                    response.handle,
-                    response.did,
-                    response.collections.join(", "),
-                    response.handle_is_correct
-                ))
+                format::render(config.output, &response, |response| {
+                    format!(
+                        "Handle: {}\nDID: {}\nCollections: {}\nHandle correct: {}",
+                        response.handle,
+                        response.did,
+                        response.collections.join(", "),
+                        response.handle_is_correct
+                    )
+                })
             }
         }
     }
 }
 
+impl CreateRecord {
+    /// Parse the record input as a single JSON document, falling back to one
+    /// record per line for JSONL.
+    fn parse(input: &str) -> anyhow::Result<Vec<serde_json::Value>> {
+        let error = match serde_json::from_str(input) {
+            Ok(record) => return Ok(vec![record]),
+            Err(error) => error,
+        };
+        let lines: Vec<(usize, &str)> = input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .collect();
+        if lines.len() < 2 {
+            return Err(error).context("Invalid record JSON");
+        }
+        lines
+            .into_iter()
+            .map(|(index, line)| {
+                serde_json::from_str(line)
+                    .with_context(|| format!("Invalid record JSON on line {}", index + 1))
+            })
+            .collect()
+    }
+
+    /// Fill in `$type` from the collection and `createdAt` with the current
+    /// time when the record doesn't set them.
+    fn complete(&self, mut record: serde_json::Value) -> anyhow::Result<serde_json::Value> {
+        let fields = record
+            .as_object_mut()
+            .ok_or_else(|| anyhow::anyhow!("Record must be a JSON object"))?;
+        fields
+            .entry("$type")
+            .or_insert_with(|| serde_json::Value::String(self.collection.to_string()));
+        fields.entry("createdAt").or_insert_with(|| {
+            serde_json::Value::String(
+                chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            )
+        });
+        Ok(record)
+    }
+}
+
 #[async_trait]
 impl Process for CreateRecord {
-    type Output = CreateRecordResponse;
+    type Output = Vec<CreateRecordResponse>;
 
     async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
-        let session = config
-            .session
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Not logged in"))?;
-
-        let record: serde_json::Value = serde_json::from_str(&self.record)?;
+        let records = Self::parse(&input::read_arg(&self.record).await?)?;
+        if records.len() > 1 && self.rkey.is_some() {
+            anyhow::bail!("--rkey can only be used when creating a single record");
+        }
 
-        let mut body = serde_json::json!({
-            "repo": self.repo,
-            "collection": self.collection,
-            "record": record
-        });
+        let total = records.len();
+        let mut responses = Vec::with_capacity(total);
+        for record in records {
+            let mut body = serde_json::json!({
+                "repo": self.repo,
+                "collection": self.collection,
+                "record": self.complete(record)?
+            });
+
+            if let Some(rkey) = &self.rkey {
+                body["rkey"] = serde_json::Value::String(rkey.to_string());
+            }
 
-        if let Some(rkey) = &self.rkey {
-            body["rkey"] = serde_json::Value::String(rkey.clone());
+            let response = client
+                .authenticated_post(config, "com.atproto.repo.createRecord", &body)
+                .await
+                .with_context(|| match total {
+                    1 => "Failed to create record".to_string(),
+                    _ => format!(
+                        "Failed to create record {} of {}",
+                        responses.len() + 1,
+                        total
+                    ),
+                })?;
+            responses.push(response);
         }
+        Ok(responses)
+    }
+}
 
-        let url = format!("{}/com.atproto.repo.createRecord", BASE_URL);
-        let response = client
-            .inner()
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", session.access_jwt))
-            .json(&body)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            anyhow::bail!("Failed to create record: {}", response.status());
+impl FromStr for RecordUri {
+    type Err = anyhow::Error;
+
+    /// Parse an `at://` URI, or a bsky.app post link as copied from the
+    /// browser, which names the post's author by handle or DID.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let uri: AtUri = match s.strip_prefix("https://bsky.app/profile/") {
+            Some(path) => {
+                let path = path.split(['?', '#']).next().unwrap_or_default();
+                let (actor, rkey) =
+                    path.trim_end_matches('/')
+                        .split_once("/post/")
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("Invalid record URL {}: not a link to a post", s)
+                        })?;
+                format!("at://{}/app.bsky.feed.post/{}", actor, rkey).parse()?
+            }
+            None => s.parse()?,
+        };
+        match uri {
+            AtUri {
+                authority,
+                collection: Some(collection),
+                rkey: Some(rkey),
+            } => Ok(Self {
+                repo: authority,
+                collection,
+                rkey,
+            }),
+            _ => anyhow::bail!(
+                "Invalid record URI {}: expected at://<repo>/<collection>/<rkey>",
+                s
+            ),
         }
+    }
+}
 
-        let response: CreateRecordResponse = response.json().await?;
-        Ok(response)
+impl RecordRef {
+    /// The repository, collection and record key. A handle in a URI is
+    /// resolved to its DID on the client, since links from bsky.app name the
+    /// author by handle and the handle may since have changed hands.
+    pub async fn resolve(&self, client: &Client) -> anyhow::Result<(String, Nsid, RecordKey)> {
+        if let Some(uri) = &self.uri {
+            let repo = match &uri.repo {
+                AtIdentifier::Did(did) => did.to_string(),
+                AtIdentifier::Handle(handle) => {
+                    self.resolver.verified(client, handle.as_str()).await?.0
+                }
+            };
+            return Ok((repo, uri.collection.clone(), uri.rkey.clone()));
+        }
+        // clap requires all three when there is no URI
+        match (&self.repo, &self.collection, &self.rkey) {
+            (Some(repo), Some(collection), Some(rkey)) => {
+                Ok((repo.to_string(), collection.clone(), rkey.clone()))
+            }
+            _ => {
+                anyhow::bail!("Either a record URI or --repo, --collection and --rkey is required")
+            }
+        }
     }
 }
 
@@ -261,24 +556,80 @@ impl Process for GetRecord {
     type Output = GetRecordResponse;
 
     async fn process(&self, client: &Client, _config: &Config) -> anyhow::Result<Self::Output> {
-        let url = format!("{}/com.atproto.repo.getRecord", BASE_URL);
-        let response = client
-            .inner()
-            .get(&url)
-            .query(&[
-                ("repo", &self.repo),
-                ("collection", &self.collection),
-                ("rkey", &self.rkey),
-            ])
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            anyhow::bail!("Failed to get record: {}", response.status());
+        let (repo, collection, rkey) = self
+            .target
+            .resolve(client)
+            .await
+            .context("Failed to get record")?;
+        let client = client
+            .for_repo(&repo)
+            .await
+            .context("Failed to get record")?;
+        client
+            .get(
+                "com.atproto.repo.getRecord",
+                &[
+                    ("repo", repo),
+                    ("collection", collection.to_string()),
+                    ("rkey", rkey.to_string()),
+                ],
+            )
+            .await
+            .context("Failed to get record")
+    }
+}
+
+impl ListRecords {
+    async fn page(
+        &self,
+        client: &Client,
+        cursor: Option<&str>,
+        limit: u32,
+    ) -> anyhow::Result<ListRecordsResponse> {
+        let mut query = vec![
+            ("repo", self.repo.to_string()),
+            ("collection", self.collection.to_string()),
+            ("limit", limit.to_string()),
+        ];
+
+        if let Some(cursor) = cursor {
+            query.push(("cursor", cursor.to_string()));
+        }
+        if self.reverse {
+            query.push(("reverse", "true".to_string()));
         }
 
-        let response: GetRecordResponse = response.json().await?;
-        Ok(response)
+        client
+            .get("com.atproto.repo.listRecords", &query)
+            .await
+            .context("Failed to list records")
+    }
+
+    /// Follow the cursor until the collection (or `--max`) is exhausted,
+    /// printing each record as a JSONL line as its page arrives.
+    pub async fn stream(&self, client: &Client) -> anyhow::Result<()> {
+        let client = client
+            .for_repo(self.repo.as_str())
+            .await
+            .context("Failed to list records")?;
+        let mut remaining = self.max.unwrap_or(usize::MAX);
+        let mut cursor = self.cursor.clone();
+        while remaining > 0 {
+            let limit = self.limit.min(u32::try_from(remaining).unwrap_or(u32::MAX));
+            let page = self.page(&client, cursor.as_deref(), limit).await?;
+            let exhausted = page.records.is_empty() || page.cursor.is_none();
+            for record in page.records.iter().take(remaining) {
+                if !format::print_jsonl(record)? {
+                    return Ok(());
+                }
+                remaining -= 1;
+            }
+            if exhausted {
+                break;
+            }
+            cursor = page.cursor;
+        }
+        Ok(())
     }
 }
 
@@ -287,26 +638,48 @@ impl Process for ListRecords {
     type Output = ListRecordsResponse;
 
     async fn process(&self, client: &Client, _config: &Config) -> anyhow::Result<Self::Output> {
-        let url = format!("{}/com.atproto.repo.listRecords", BASE_URL);
-        let limit_str = self.limit.to_string();
-        let mut query = vec![
-            ("repo", self.repo.as_str()),
-            ("collection", self.collection.as_str()),
-            ("limit", limit_str.as_str()),
-        ];
+        let client = client
+            .for_repo(self.repo.as_str())
+            .await
+            .context("Failed to list records")?;
+        self.page(&client, self.cursor.as_deref(), self.limit).await
+    }
+}
 
-        if let Some(cursor) = &self.cursor {
-            query.push(("cursor", cursor));
-        }
+#[async_trait]
+impl Process for PutRecord {
+    type Output = PutRecordResponse;
 
-        let response = client.inner().get(&url).query(&query).send().await?;
+    async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
+        let record: serde_json::Value = serde_json::from_str(&input::read_arg(&self.record).await?)
+            .context("Invalid record JSON")?;
+        let (repo, collection, rkey) = self
+            .target
+            .resolve(client)
+            .await
+            .context("Failed to put record")?;
+
+        let mut body = serde_json::json!({
+            "repo": repo,
+            "collection": collection,
+            "rkey": rkey,
+            "record": record
+        });
 
-        if !response.status().is_success() {
-            anyhow::bail!("Failed to list records: {}", response.status());
+        if let Some(swap_record) = &self.swap_record {
+            body["swapRecord"] = serde_json::Value::String(swap_record.to_string());
+        }
+        if let Some(swap_commit) = &self.swap_commit {
+            body["swapCommit"] = serde_json::Value::String(swap_commit.to_string());
+        }
+        if let Some(validate) = self.validate {
+            body["validate"] = serde_json::Value::Bool(validate);
         }
 
-        let response: ListRecordsResponse = response.json().await?;
-        Ok(response)
+        client
+            .authenticated_post(config, "com.atproto.repo.putRecord", &body)
+            .await
+            .context("Failed to put record")
     }
 }
 
@@ -315,44 +688,147 @@ impl Process for DeleteRecord {
     type Output = ();
 
     async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
-        let session = config
-            .session
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Not logged in"))?;
-
+        let (repo, collection, rkey) = self
+            .target
+            .resolve(client)
+            .await
+            .context("Failed to delete record")?;
         let body = serde_json::json!({
-            "repo": self.repo,
-            "collection": self.collection,
-            "rkey": self.rkey
+            "repo": repo,
+            "collection": collection,
+            "rkey": rkey
         });
 
-        let url = format!("{}/com.atproto.repo.deleteRecord", BASE_URL);
-        let response = client
-            .inner()
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", session.access_jwt))
-            .json(&body)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            anyhow::bail!("Failed to delete record: {}", response.status());
-        }
+        let url = client.xrpc("com.atproto.repo.deleteRecord");
+        client
+            .send_authenticated(config, |http| http.post(&url).json(&body))
+            .await
+            .context("Failed to delete record")?;
 
         Ok(())
     }
 }
 
+impl ApplyWrites {
+    /// Parse the JSONL input, skipping blank lines.
+    fn parse(input: &str) -> anyhow::Result<Vec<WriteOp>> {
+        let mut ops = Vec::new();
+        for (index, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let op: WriteOp = serde_json::from_str(line)
+                .with_context(|| format!("Invalid write on line {}", index + 1))?;
+            match op.action {
+                WriteAction::Create | WriteAction::Update if op.value.is_none() => {
+                    anyhow::bail!("Write on line {} is missing a value", index + 1)
+                }
+                WriteAction::Update | WriteAction::Delete if op.rkey.is_none() => {
+                    anyhow::bail!("Write on line {} is missing an rkey", index + 1)
+                }
+                _ => ops.push(op),
+            }
+        }
+        Ok(ops)
+    }
+
+    fn write_json(op: &WriteOp) -> serde_json::Value {
+        let kind = match op.action {
+            WriteAction::Create => "create",
+            WriteAction::Update => "update",
+            WriteAction::Delete => "delete",
+        };
+        let mut write = serde_json::json!({
+            "$type": format!("com.atproto.repo.applyWrites#{kind}"),
+            "collection": op.collection,
+        });
+        if let Some(rkey) = &op.rkey {
+            write["rkey"] = serde_json::Value::String(rkey.to_string());
+        }
+        if let Some(value) = &op.value {
+            write["value"] = value.clone();
+        }
+        write
+    }
+}
+
+#[async_trait]
+impl Process for ApplyWrites {
+    type Output = ApplyWritesResponse;
+
+    async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
+        let source = if self.file == "-" {
+            self.file.clone()
+        } else {
+            format!("@{}", self.file)
+        };
+        let ops = Self::parse(&input::read_arg(&source).await?)?;
+
+        let mut swap_commit = self.swap_commit.as_ref().map(Cid::to_string);
+        let mut commit = None;
+        let mut results = Vec::with_capacity(ops.len());
+        for (batch_index, batch) in ops.chunks(self.batch_size).enumerate() {
+            let mut body = serde_json::json!({
+                "repo": self.repo,
+                "writes": batch.iter().map(Self::write_json).collect::<Vec<_>>(),
+            });
+            if let Some(cid) = &swap_commit {
+                body["swapCommit"] = serde_json::Value::String(cid.clone());
+            }
+            if let Some(validate) = self.validate {
+                body["validate"] = serde_json::Value::Bool(validate);
+            }
+
+            let response: ApplyWritesBatchResponse = client
+                .authenticated_post(config, "com.atproto.repo.applyWrites", &body)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to apply writes (batch {}, {} writes already applied)",
+                        batch_index + 1,
+                        results.len()
+                    )
+                })?;
+
+            for (index, op) in batch.iter().enumerate() {
+                let result = response.results.get(index);
+                let uri = result
+                    .and_then(|result| result.uri.clone())
+                    .unwrap_or_else(|| {
+                        // Delete results carry no URI, so rebuild it from the write.
+                        format!(
+                            "at://{}/{}/{}",
+                            self.repo,
+                            op.collection,
+                            op.rkey.as_ref().map(RecordKey::as_str).unwrap_or_default()
+                        )
+                    });
+                results.push(WriteResult {
+                    action: op.action,
+                    uri,
+                    cid: result.and_then(|result| result.cid.clone()),
+                    validation_status: result.and_then(|result| result.validation_status.clone()),
+                });
+            }
+
+            if let Some(meta) = response.commit {
+                if swap_commit.is_some() {
+                    swap_commit = Some(meta.cid.clone());
+                }
+                commit = Some(meta);
+            }
+        }
+
+        Ok(ApplyWritesResponse { commit, results })
+    }
+}
+
 #[async_trait]
 impl Process for UploadBlob {
     type Output = UploadBlobResponse;
 
     async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
-        let session = config
-            .session
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Not logged in"))?;
-
         let file_data = tokio::fs::read(&self.file).await?;
 
         // Try to determine MIME type from file extension
@@ -369,43 +845,64 @@ impl Process for UploadBlob {
             _ => "application/octet-stream",
         };
 
-        let url = format!("{}/com.atproto.repo.uploadBlob", BASE_URL);
-        let response = client
-            .inner()
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", session.access_jwt))
-            .header("Content-Type", mime_type)
-            .body(file_data)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            anyhow::bail!("Failed to upload blob: {}", response.status());
-        }
-
-        let response: UploadBlobResponse = response.json().await?;
-        Ok(response)
+        upload_blob(client, config, file_data, mime_type)
+            .await
+            .context("Failed to upload blob")
     }
 }
 
+/// Upload blob bytes to the active account's PDS.
+pub(crate) async fn upload_blob(
+    client: &Client,
+    config: &Config,
+    bytes: Vec<u8>,
+    mime_type: &str,
+) -> anyhow::Result<UploadBlobResponse> {
+    let url = client.xrpc("com.atproto.repo.uploadBlob");
+    let response = client
+        .send_authenticated(config, |http| {
+            http.post(&url)
+                .header("Content-Type", mime_type)
+                .body(bytes.clone())
+        })
+        .await?;
+    Ok(response.json().await?)
+}
+
+/// Replace the active account's repository with a repository CAR, as when
+/// moving it to a new PDS.
+pub(crate) async fn import_repo(
+    client: &Client,
+    config: &Config,
+    car: Vec<u8>,
+) -> anyhow::Result<()> {
+    let url = client.xrpc("com.atproto.repo.importRepo");
+    client
+        .send_authenticated(config, |http| {
+            http.post(&url)
+                .header("Content-Type", "application/vnd.ipld.car")
+                .body(car.clone())
+        })
+        .await
+        .context("Failed to import repo")?;
+    Ok(())
+}
+
 #[async_trait]
 impl Process for DescribeRepo {
     type Output = DescribeRepoResponse;
 
     async fn process(&self, client: &Client, _config: &Config) -> anyhow::Result<Self::Output> {
-        let url = format!("{}/com.atproto.repo.describeRepo", BASE_URL);
-        let response = client
-            .inner()
-            .get(&url)
-            .query(&[("repo", &self.repo)])
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            anyhow::bail!("Failed to describe repo: {}", response.status());
-        }
-
-        let response: DescribeRepoResponse = response.json().await?;
-        Ok(response)
+        let client = client
+            .for_repo(self.repo.as_str())
+            .await
+            .context("Failed to describe repo")?;
+        client
+            .get(
+                "com.atproto.repo.describeRepo",
+                &[("repo", self.repo.to_string())],
+            )
+            .await
+            .context("Failed to describe repo")
     }
 }
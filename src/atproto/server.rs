@@ -1,8 +1,9 @@
+use anyhow::Context;
 use async_trait::async_trait;
 use clap::Parser;
 use serde::{Deserialize, Serialize};
 
-use crate::{BASE_URL, Client, Config, Process};
+use crate::{Client, Config, Process, format, xrpc::check};
 
 #[derive(Parser)]
 pub enum Server {
@@ -68,6 +69,7 @@ pub struct RefreshSessionResponse {
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct DescribeServerResponse {
+    pub did: String,
     #[serde(rename = "availableUserDomains")]
     pub available_user_domains: Vec<String>,
     #[serde(rename = "inviteCodeRequired")]
@@ -103,33 +105,36 @@ impl Process for Server {
         match self {
             Server::CreateSession(cmd) => {
                 let response = cmd.process(client, config).await?;
-                Ok(format!(
-                    "Session created for: {}\nDID: {}",
-                    response.handle, response.did
-                ))
+                format::render(config.output, &response, |response| {
+                    format!(
+                        "Session created for: {}\nDID: {}",
+                        response.handle, response.did
+                    )
+                })
             }
             Server::GetSession(cmd) => {
                 let response = cmd.process(client, config).await?;
-                Ok(format!(
-                    "Handle: {}\nDID: {}",
-                    response.handle, response.did
-                ))
+                format::render(config.output, &response, |response| {
+                    format!("Handle: {}\nDID: {}", response.handle, response.did)
+                })
             }
             Server::RefreshSession(cmd) => {
                 cmd.process(client, config).await?;
-                Ok("Session refreshed successfully".to_string())
+                format::render_success(config.output, "Session refreshed successfully")
             }
             Server::DeleteSession(cmd) => {
                 cmd.process(client, config).await?;
-                Ok("Session deleted successfully".to_string())
+                format::render_success(config.output, "Session deleted successfully")
             }
             Server::DescribeServer(cmd) => {
                 let response = cmd.process(client, config).await?;
-                Ok(format!(
-                    "Available domains: {}\nInvite required: {}",
-                    response.available_user_domains.join(", "),
-                    response.invite_code_required.unwrap_or(false)
-                ))
+                format::render(config.output, &response, |response| {
+                    format!(
+                        "Available domains: {}\nInvite required: {}",
+                        response.available_user_domains.join(", "),
+                        response.invite_code_required.unwrap_or(false)
+                    )
+                })
             }
         }
     }
@@ -140,17 +145,14 @@ impl Process for CreateSession {
     type Output = CreateSessionResponse;
 
     async fn process(&self, client: &Client, _config: &Config) -> anyhow::Result<Self::Output> {
-        let url = format!("{}/com.atproto.server.createSession", BASE_URL);
+        let url = client.xrpc("com.atproto.server.createSession");
         let body = serde_json::json!({
             "identifier": self.identifier,
             "password": self.password
         });
 
         let response = client.inner().post(&url).json(&body).send().await?;
-
-        if !response.status().is_success() {
-            anyhow::bail!("Failed to create session: {}", response.status());
-        }
+        let response = check(response).await.context("Failed to create session")?;
 
         let response: CreateSessionResponse = response.json().await?;
         Ok(response)
@@ -162,25 +164,10 @@ impl Process for GetSession {
     type Output = GetSessionResponse;
 
     async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
-        let session = config
-            .session
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Not logged in"))?;
-
-        let url = format!("{}/com.atproto.server.getSession", BASE_URL);
-        let response = client
-            .inner()
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", session.access_jwt))
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            anyhow::bail!("Failed to get session: {}", response.status());
-        }
-
-        let response: GetSessionResponse = response.json().await?;
-        Ok(response)
+        client
+            .authenticated_get(config, "com.atproto.server.getSession", &[])
+            .await
+            .context("Failed to get session")
     }
 }
 
@@ -189,25 +176,15 @@ impl Process for RefreshSession {
     type Output = RefreshSessionResponse;
 
     async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
-        let session = config
-            .session
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Not logged in"))?;
-
-        let url = format!("{}/com.atproto.server.refreshSession", BASE_URL);
-        let response = client
-            .inner()
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", session.refresh_jwt))
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            anyhow::bail!("Failed to refresh session: {}", response.status());
-        }
-
-        let response: RefreshSessionResponse = response.json().await?;
-        Ok(response)
+        let session = client
+            .refresh(config)
+            .await
+            .context("Failed to refresh session")?;
+
+        Ok(RefreshSessionResponse {
+            access_jwt: session.access_jwt,
+            refresh_jwt: session.refresh_jwt,
+        })
     }
 }
 
@@ -217,21 +194,19 @@ impl Process for DeleteSession {
 
     async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
         let session = config
-            .session
-            .as_ref()
+            .session()
             .ok_or_else(|| anyhow::anyhow!("Not logged in"))?;
 
-        let url = format!("{}/com.atproto.server.deleteSession", BASE_URL);
+        // deleteSession is authorized with the refresh token, not the access
+        // token, so it bypasses the refreshing request layer.
+        let url = client.xrpc("com.atproto.server.deleteSession");
         let response = client
             .inner()
             .post(&url)
-            .header("Authorization", format!("Bearer {}", session.refresh_jwt))
+            .bearer_auth(&session.refresh_jwt)
             .send()
             .await?;
-
-        if !response.status().is_success() {
-            anyhow::bail!("Failed to delete session: {}", response.status());
-        }
+        check(response).await.context("Failed to delete session")?;
 
         Ok(())
     }
@@ -242,14 +217,9 @@ impl Process for DescribeServer {
     type Output = DescribeServerResponse;
 
     async fn process(&self, client: &Client, _config: &Config) -> anyhow::Result<Self::Output> {
-        let url = format!("{}/com.atproto.server.describeServer", BASE_URL);
-        let response = client.inner().get(&url).send().await?;
-
-        if !response.status().is_success() {
-            anyhow::bail!("Failed to describe server: {}", response.status());
-        }
-
-        let response: DescribeServerResponse = response.json().await?;
-        Ok(response)
+        client
+            .get("com.atproto.server.describeServer", &[])
+            .await
+            .context("Failed to describe server")
     }
 }
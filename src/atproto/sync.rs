@@ -1,13 +1,33 @@
+use anyhow::Context;
 use async_trait::async_trait;
 use clap::Parser;
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
-
-use crate::{BASE_URL, Client, Config, Process};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::{
+    Client, Config, Process,
+    atproto::identity::ResolveDid,
+    car::CarReader,
+    cbor,
+    cid::{self, Cid},
+    crypto::PublicKey,
+    firehose, format, mst,
+    resolve::DidDocument,
+    syntax::{Did, Nsid, Tid},
+    xrpc::check,
+};
 
 #[derive(Parser)]
 pub enum Sync {
     /// Get a blob from the repository
     GetBlob(GetBlob),
+    /// Download a repository as a CAR file
+    GetRepo(GetRepo),
+    /// Verify a repository CAR's MST and commit signature
+    VerifyRepo(VerifyRepo),
     /// Get repository head
     GetHead(GetHead),
     /// Get latest commit
@@ -16,47 +36,145 @@ pub enum Sync {
     GetRepoStatus(GetRepoStatus),
     /// List repositories
     ListRepos(ListRepos),
+    /// List the blobs in a repository, optionally downloading them all
+    ListBlobs(ListBlobs),
+    /// Stream repository events from a relay or PDS firehose as JSONL
+    Subscribe(Subscribe),
 }
 
 #[derive(Parser)]
 pub struct GetBlob {
     /// Repository DID
     #[arg(long)]
-    pub did: String,
+    pub did: Did,
     /// Blob CID
     #[arg(long)]
-    pub cid: String,
+    pub cid: Cid,
+    /// Path to save the blob to, or - for stdout
+    #[arg(long)]
+    pub out: Option<String>,
+}
+
+#[derive(Parser)]
+pub struct GetRepo {
+    /// Repository DID
+    #[arg(long)]
+    pub did: Did,
+    /// Path to write the CAR file to
+    #[arg(long)]
+    pub out: String,
+    /// Only include changes since this revision
+    #[arg(long)]
+    pub since: Option<Tid>,
+}
+
+#[derive(Parser)]
+pub struct VerifyRepo {
+    /// Path to the repository CAR file
+    #[arg(long)]
+    pub car: String,
+    /// DID document JSON file to take the signing key from, instead of
+    /// resolving the commit's DID
+    #[arg(long)]
+    pub did_doc: Option<String>,
 }
 
 #[derive(Parser)]
 pub struct GetHead {
     /// Repository DID
     #[arg(long)]
-    pub did: String,
+    pub did: Did,
 }
 
 #[derive(Parser)]
 pub struct GetLatestCommit {
     /// Repository DID
     #[arg(long)]
-    pub did: String,
+    pub did: Did,
 }
 
 #[derive(Parser)]
 pub struct GetRepoStatus {
     /// Repository DID
     #[arg(long)]
-    pub did: String,
+    pub did: Did,
 }
 
 #[derive(Parser)]
 pub struct ListRepos {
-    /// Maximum number of repos to return
+    /// Maximum number of repos to return (per page with --all or --max)
     #[arg(long, default_value = "500")]
     pub limit: u32,
     /// Cursor for pagination
     #[arg(long)]
     pub cursor: Option<String>,
+    /// Follow the cursor through every page, streaming repos as JSONL
+    #[arg(long)]
+    pub all: bool,
+    /// Like --all, but stop after this many repos
+    #[arg(long)]
+    pub max: Option<usize>,
+}
+
+#[derive(Parser)]
+pub struct ListBlobs {
+    /// Repository DID
+    #[arg(long)]
+    pub did: Did,
+    /// Only list blobs added since this revision
+    #[arg(long)]
+    pub since: Option<Tid>,
+    /// Number of CIDs to request per page
+    #[arg(long, default_value = "500")]
+    pub limit: u32,
+    /// Download every blob into this directory, one file per CID
+    #[arg(long)]
+    pub download_dir: Option<String>,
+    /// Number of blobs to download at once
+    #[arg(long, default_value = "4", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=32))]
+    pub concurrency: usize,
+}
+
+#[derive(Parser)]
+pub struct Subscribe {
+    /// Relay or PDS to subscribe to (defaults to the account's PDS)
+    #[arg(long)]
+    pub relay: Option<String>,
+    /// Sequence number to resume from
+    #[arg(long)]
+    pub cursor: Option<i64>,
+    /// Only show commits touching this collection; may be repeated
+    #[arg(long)]
+    pub collection: Vec<Nsid>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GetBlobResponse {
+    pub did: String,
+    pub cid: String,
+    pub size: u64,
+    #[serde(rename = "mimeType")]
+    pub mime_type: Option<String>,
+    pub out: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GetRepoResponse {
+    pub did: String,
+    pub commit: String,
+    pub rev: String,
+    pub out: String,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct VerifyRepoResponse {
+    pub did: String,
+    pub commit: String,
+    pub rev: String,
+    pub nodes: usize,
+    pub records: usize,
+    pub key: String,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -84,6 +202,31 @@ pub struct ListReposResponse {
     pub cursor: Option<String>,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ListBlobsResponse {
+    pub did: String,
+    pub cids: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub downloads: Option<Vec<BlobDownload>>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BlobDownload {
+    pub cid: String,
+    pub out: String,
+    pub size: u64,
+    #[serde(rename = "mimeType")]
+    pub mime_type: Option<String>,
+    /// The file was already present and matched its CID
+    pub skipped: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListBlobsPage {
+    cids: Vec<String>,
+    cursor: Option<String>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct RepoRef {
     pub did: String,
@@ -96,11 +239,17 @@ pub struct RepoRef {
 impl Sync {
     pub fn needs_authentication(&self) -> bool {
         match self {
-            Sync::GetBlob(_) => false,         // Public endpoint
+            Sync::GetBlob(_) => false, // Public endpoint
+            Sync::GetRepo(_) => false, // Public endpoint
+            // Resolves the DID document from the PLC directory or did:web host
+            // unless given one
+            Sync::VerifyRepo(_) => false,
             Sync::GetHead(_) => false,         // Public endpoint
             Sync::GetLatestCommit(_) => false, // Public endpoint
             Sync::GetRepoStatus(_) => false,   // Public endpoint
             Sync::ListRepos(_) => false,       // Public endpoint
+            Sync::ListBlobs(_) => false,       // Public endpoint
+            Sync::Subscribe(_) => false,       // Public endpoint
         }
     }
 }
@@ -112,107 +261,392 @@ impl Process for Sync {
     async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
         match self {
             Sync::GetBlob(cmd) => {
-                let _response = cmd.process(client, config).await?;
-                Ok("Blob retrieved successfully".to_string())
+                let response = cmd.process(client, config).await?;
+                if cmd.out.as_deref() == Some("-") {
+                    return Ok(String::new());
+                }
+                format::render(config.output, &response, |response| {
+                    let mut output = match &response.out {
+                        Some(out) => format!("Saved blob {} to {}", response.cid, out),
+                        None => "Blob retrieved successfully".to_string(),
+                    };
+                    output.push_str(&format!("\nSize: {} bytes", response.size));
+                    if let Some(mime_type) = &response.mime_type {
+                        output.push_str(&format!("\nType: {}", mime_type));
+                    }
+                    output
+                })
+            }
+            Sync::GetRepo(cmd) => {
+                let response = cmd.process(client, config).await?;
+                format::render(config.output, &response, |response| {
+                    format!(
+                        "Commit: {}\nRev: {}\nWrote {} bytes to {}",
+                        response.commit, response.rev, response.bytes, response.out
+                    )
+                })
+            }
+            Sync::VerifyRepo(cmd) => {
+                let response = cmd.process(client, config).await?;
+                format::render(config.output, &response, |response| {
+                    format!(
+                        "Verified repository {} at rev {}\nCommit: {}\nMST: {} nodes, {} records\nSigned by: {}",
+                        response.did,
+                        response.rev,
+                        response.commit,
+                        response.nodes,
+                        response.records,
+                        response.key
+                    )
+                })
             }
             Sync::GetHead(cmd) => {
                 let response = cmd.process(client, config).await?;
-                Ok(format!("Head: {}", response.root))
+                format::render(config.output, &response, |response| {
+                    format!("Head: {}", response.root)
+                })
             }
             Sync::GetLatestCommit(cmd) => {
                 let response = cmd.process(client, config).await?;
-                Ok(format!(
-                    "Latest commit: {}\nRev: {}",
-                    response.cid, response.rev
-                ))
+                format::render(config.output, &response, |response| {
+                    format!("Latest commit: {}\nRev: {}", response.cid, response.rev)
+                })
             }
             Sync::GetRepoStatus(cmd) => {
                 let response = cmd.process(client, config).await?;
-                Ok(format!(
-                    "DID: {}\nActive: {}\nStatus: {}",
-                    response.did,
-                    response.active,
-                    response.status.unwrap_or_else(|| "None".to_string())
-                ))
+                format::render(config.output, &response, |response| {
+                    format!(
+                        "DID: {}\nActive: {}\nStatus: {}",
+                        response.did,
+                        response.active,
+                        response.status.as_deref().unwrap_or("None")
+                    )
+                })
+            }
+            Sync::Subscribe(cmd) => {
+                cmd.process(client, config).await?;
+                Ok(String::new())
+            }
+            Sync::ListRepos(cmd) if cmd.all || cmd.max.is_some() => {
+                cmd.stream(client).await?;
+                Ok(String::new())
+            }
+            Sync::ListBlobs(cmd) => {
+                let response = cmd.process(client, config).await?;
+                format::render(config.output, &response, |response| {
+                    match &response.downloads {
+                        Some(downloads) => {
+                            let skipped =
+                                downloads.iter().filter(|download| download.skipped).count();
+                            format!(
+                                "Found {} blobs\nDownloaded {}, skipped {} already present, to {}",
+                                response.cids.len(),
+                                downloads.len() - skipped,
+                                skipped,
+                                cmd.download_dir.as_deref().unwrap_or_default()
+                            )
+                        }
+                        None => {
+                            let mut output = format!("Found {} blobs:\n", response.cids.len());
+                            for cid in &response.cids {
+                                output.push_str(&format!("  {}\n", cid));
+                            }
+                            output
+                        }
+                    }
+                })
             }
             Sync::ListRepos(cmd) => {
                 let response = cmd.process(client, config).await?;
-                let mut output = format!("Found {} repositories:\n", response.repos.len());
-                for repo in response.repos {
-                    output.push_str(&format!("  {}: {} ({})\n", repo.did, repo.head, repo.rev));
-                }
-                if let Some(cursor) = response.cursor {
-                    output.push_str(&format!("Cursor: {}\n", cursor));
-                }
-                Ok(output)
+                format::render(config.output, &response, |response| {
+                    let mut output = format!("Found {} repositories:\n", response.repos.len());
+                    for repo in &response.repos {
+                        output.push_str(&format!("  {}: {} ({})\n", repo.did, repo.head, repo.rev));
+                    }
+                    if let Some(cursor) = &response.cursor {
+                        output.push_str(&format!("Cursor: {}\n", cursor));
+                    }
+                    output
+                })
             }
         }
     }
 }
 
+/// Where a downloaded blob's bytes go.
+enum BlobSink {
+    File(tokio::fs::File),
+    /// Held back until the CID checks out, since stdout can't be taken back
+    Stdout(Vec<u8>),
+    Discard,
+}
+
 #[async_trait]
 impl Process for GetBlob {
-    type Output = Vec<u8>;
+    type Output = GetBlobResponse;
 
     async fn process(&self, client: &Client, _config: &Config) -> anyhow::Result<Self::Output> {
-        let url = format!("{}/com.atproto.sync.getBlob", BASE_URL);
+        let client = client
+            .for_repo(self.did.as_str())
+            .await
+            .context("Failed to get blob")?;
+        self.download(&client).await
+    }
+}
+
+impl GetBlob {
+    /// Fetch the blob from `client`, which must already point at the
+    /// repository's PDS, checking it against its CID as it streams. Blobs
+    /// written to stdout are only printed once the check passes.
+    pub(crate) async fn download(&self, client: &Client) -> anyhow::Result<GetBlobResponse> {
+        let expected = self.cid.clone();
+        check_blob_cid(&expected).context("Failed to get blob")?;
         let response = client
             .inner()
-            .get(&url)
-            .query(&[("did", &self.did), ("cid", &self.cid)])
+            .get(client.xrpc("com.atproto.sync.getBlob"))
+            .query(&[("did", self.did.to_string()), ("cid", self.cid.to_string())])
             .send()
             .await?;
+        let mut response = check(response).await.context("Failed to get blob")?;
+        let mime_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        let mut sink = match self.out.as_deref() {
+            Some("-") => BlobSink::Stdout(Vec::new()),
+            Some(path) => BlobSink::File(
+                tokio::fs::File::create(path)
+                    .await
+                    .with_context(|| format!("Failed to create {}", path))?,
+            ),
+            None => BlobSink::Discard,
+        };
+        let mut hasher = Sha256::new();
+        let mut size = 0;
+        while let Some(chunk) = response.chunk().await.context("Failed to get blob")? {
+            hasher.update(&chunk);
+            size += chunk.len() as u64;
+            match &mut sink {
+                BlobSink::File(file) => file.write_all(&chunk).await?,
+                BlobSink::Stdout(buffer) => buffer.extend_from_slice(&chunk),
+                BlobSink::Discard => {}
+            }
+        }
+        if let BlobSink::File(file) = &mut sink {
+            file.flush().await?;
+        }
 
-        if !response.status().is_success() {
-            anyhow::bail!("Failed to get blob: {}", response.status());
+        let actual = Cid {
+            digest: hasher.finalize().to_vec(),
+            ..expected.clone()
+        };
+        if actual != expected {
+            if let (BlobSink::File(_), Some(path)) = (sink, &self.out) {
+                let _ = tokio::fs::remove_file(path).await;
+            }
+            anyhow::bail!(
+                "Blob does not match its CID: expected {}, got {}",
+                expected,
+                actual
+            );
+        }
+        if let BlobSink::Stdout(buffer) = sink {
+            let mut stdout = tokio::io::stdout();
+            stdout.write_all(&buffer).await?;
+            stdout.flush().await?;
         }
 
-        let bytes = response.bytes().await?;
-        Ok(bytes.to_vec())
+        Ok(GetBlobResponse {
+            did: self.did.to_string(),
+            cid: self.cid.to_string(),
+            size,
+            mime_type,
+            out: self.out.clone(),
+        })
     }
 }
 
 #[async_trait]
-impl Process for GetHead {
-    type Output = GetHeadResponse;
+impl Process for GetRepo {
+    type Output = GetRepoResponse;
 
     async fn process(&self, client: &Client, _config: &Config) -> anyhow::Result<Self::Output> {
-        let url = format!("{}/com.atproto.sync.getHead", BASE_URL);
+        let client = client
+            .for_repo(self.did.as_str())
+            .await
+            .context("Failed to get repo")?;
+        self.download(&client).await
+    }
+}
+
+impl GetRepo {
+    /// Stream the repository from `client`, which must already point at its
+    /// PDS, to `--out`.
+    pub(crate) async fn download(&self, client: &Client) -> anyhow::Result<GetRepoResponse> {
+        let mut query = vec![("did", self.did.to_string())];
+        if let Some(since) = &self.since {
+            query.push(("since", since.to_string()));
+        }
         let response = client
             .inner()
-            .get(&url)
-            .query(&[("did", &self.did)])
+            .get(client.xrpc("com.atproto.sync.getRepo"))
+            .query(&query)
             .send()
-            .await?;
+            .await
+            .context("Failed to get repo")?;
+        let mut response = check(response).await.context("Failed to get repo")?;
+
+        let mut file = tokio::fs::File::create(&self.out)
+            .await
+            .with_context(|| format!("Failed to create {}", self.out))?;
+        let mut bytes = 0;
+        while let Some(chunk) = response.chunk().await.context("Failed to get repo")? {
+            file.write_all(&chunk).await?;
+            bytes += chunk.len() as u64;
+        }
+        file.flush().await?;
+
+        let out = self.out.clone();
+        let (commit, rev) = tokio::task::spawn_blocking(move || read_commit(&out)).await??;
+        Ok(GetRepoResponse {
+            did: self.did.to_string(),
+            commit,
+            rev,
+            out: self.out.clone(),
+            bytes,
+        })
+    }
+}
 
-        if !response.status().is_success() {
-            anyhow::bail!("Failed to get head: {}", response.status());
+/// Check that a blob CID uses SHA-256, so its content can be checked.
+fn check_blob_cid(cid: &Cid) -> anyhow::Result<()> {
+    if cid.hash_code != cid::SHA2_256 {
+        anyhow::bail!("Blob CID {} does not use a SHA-256 hash", cid);
+    }
+    Ok(())
+}
+
+/// Find the commit a repository CAR file is rooted at, returning its CID
+/// and revision.
+fn read_commit(path: &str) -> anyhow::Result<(String, String)> {
+    let file = std::fs::File::open(path).with_context(|| format!("Failed to open {}", path))?;
+    let mut car = CarReader::new(std::io::BufReader::new(file))?;
+    let root = car
+        .roots
+        .first()
+        .cloned()
+        .context("Repository CAR has no root")?;
+    while let Some((cid, block)) = car.next_block()? {
+        if cid == root {
+            let commit = cbor::decode(&block).context("Invalid commit block")?;
+            let rev = commit
+                .get("rev")
+                .and_then(cbor::Value::as_str)
+                .context("Commit has no rev")?;
+            return Ok((cid.to_string(), rev.to_string()));
         }
+    }
+    anyhow::bail!("Repository CAR does not contain its commit block {}", root)
+}
 
-        let response: GetHeadResponse = response.json().await?;
-        Ok(response)
+#[async_trait]
+impl Process for VerifyRepo {
+    type Output = VerifyRepoResponse;
+
+    async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
+        let bytes = tokio::fs::read(&self.car)
+            .await
+            .with_context(|| format!("Failed to read {}", self.car))?;
+        let mut car = CarReader::new(std::io::Cursor::new(bytes))
+            .with_context(|| format!("Invalid CAR file {}", self.car))?;
+        let root = car.roots.first().cloned().context("CAR file has no root")?;
+        let blocks = car.blocks()?;
+
+        mst::verify_block(&blocks, &root).context("Invalid commit block")?;
+        let commit = cbor::decode(&blocks[&root]).context("Invalid commit block")?;
+        let field = |name: &str| {
+            commit
+                .get(name)
+                .with_context(|| format!("Commit has no {}", name))
+        };
+        let did = field("did")?.as_str().context("Invalid commit did")?;
+        let rev = field("rev")?.as_str().context("Invalid commit rev")?;
+        let data = field("data")?.as_link().context("Invalid commit data")?;
+        let sig = field("sig")?.as_bytes().context("Invalid commit sig")?;
+
+        let stats = mst::verify(&blocks, data).context("MST verification failed")?;
+
+        let did_doc: DidDocument = match &self.did_doc {
+            Some(path) => {
+                let contents = tokio::fs::read_to_string(path)
+                    .await
+                    .with_context(|| format!("Failed to read {}", path))?;
+                serde_json::from_str(&contents).context("Invalid DID document")?
+            }
+            None => {
+                ResolveDid { did: did.parse()? }
+                    .process(client, config)
+                    .await?
+                    .did_doc
+            }
+        };
+        if did_doc.id != did {
+            anyhow::bail!("DID document is for {}, not {}", did_doc.id, did);
+        }
+        let key = PublicKey::from_did_document(&did_doc)?;
+
+        let mut unsigned = commit.clone();
+        if let cbor::Value::Map(fields) = &mut unsigned {
+            fields.remove("sig");
+        }
+        key.verify(&cbor::encode(&unsigned), sig)
+            .context("Commit signature verification failed")?;
+
+        Ok(VerifyRepoResponse {
+            did: did.to_string(),
+            commit: root.to_string(),
+            rev: rev.to_string(),
+            nodes: stats.nodes,
+            records: stats.records,
+            key: key.to_did_key(),
+        })
     }
 }
 
 #[async_trait]
-impl Process for GetLatestCommit {
-    type Output = GetLatestCommitResponse;
+impl Process for GetHead {
+    type Output = GetHeadResponse;
 
     async fn process(&self, client: &Client, _config: &Config) -> anyhow::Result<Self::Output> {
-        let url = format!("{}/com.atproto.sync.getLatestCommit", BASE_URL);
-        let response = client
-            .inner()
-            .get(&url)
-            .query(&[("did", &self.did)])
-            .send()
-            .await?;
+        let client = client
+            .for_repo(self.did.as_str())
+            .await
+            .context("Failed to get head")?;
+        client
+            .get("com.atproto.sync.getHead", &[("did", self.did.to_string())])
+            .await
+            .context("Failed to get head")
+    }
+}
 
-        if !response.status().is_success() {
-            anyhow::bail!("Failed to get latest commit: {}", response.status());
-        }
+#[async_trait]
+impl Process for GetLatestCommit {
+    type Output = GetLatestCommitResponse;
 
-        let response: GetLatestCommitResponse = response.json().await?;
-        Ok(response)
+    async fn process(&self, client: &Client, _config: &Config) -> anyhow::Result<Self::Output> {
+        let client = client
+            .for_repo(self.did.as_str())
+            .await
+            .context("Failed to get latest commit")?;
+        client
+            .get(
+                "com.atproto.sync.getLatestCommit",
+                &[("did", self.did.to_string())],
+            )
+            .await
+            .context("Failed to get latest commit")
     }
 }
 
@@ -221,20 +655,60 @@ impl Process for GetRepoStatus {
     type Output = GetRepoStatusResponse;
 
     async fn process(&self, client: &Client, _config: &Config) -> anyhow::Result<Self::Output> {
-        let url = format!("{}/com.atproto.sync.getRepoStatus", BASE_URL);
-        let response = client
-            .inner()
-            .get(&url)
-            .query(&[("did", &self.did)])
-            .send()
-            .await?;
+        let client = client
+            .for_repo(self.did.as_str())
+            .await
+            .context("Failed to get repo status")?;
+        client
+            .get(
+                "com.atproto.sync.getRepoStatus",
+                &[("did", self.did.to_string())],
+            )
+            .await
+            .context("Failed to get repo status")
+    }
+}
 
-        if !response.status().is_success() {
-            anyhow::bail!("Failed to get repo status: {}", response.status());
+impl ListRepos {
+    async fn page(
+        &self,
+        client: &Client,
+        cursor: Option<&str>,
+        limit: u32,
+    ) -> anyhow::Result<ListReposResponse> {
+        let mut query = vec![("limit", limit.to_string())];
+
+        if let Some(cursor) = cursor {
+            query.push(("cursor", cursor.to_string()));
         }
 
-        let response: GetRepoStatusResponse = response.json().await?;
-        Ok(response)
+        client
+            .get("com.atproto.sync.listRepos", &query)
+            .await
+            .context("Failed to list repos")
+    }
+
+    /// Follow the cursor until every repo (or `--max`) has been listed,
+    /// printing each one as a JSONL line as its page arrives.
+    pub async fn stream(&self, client: &Client) -> anyhow::Result<()> {
+        let mut remaining = self.max.unwrap_or(usize::MAX);
+        let mut cursor = self.cursor.clone();
+        while remaining > 0 {
+            let limit = self.limit.min(u32::try_from(remaining).unwrap_or(u32::MAX));
+            let page = self.page(client, cursor.as_deref(), limit).await?;
+            let exhausted = page.repos.is_empty() || page.cursor.is_none();
+            for repo in page.repos.iter().take(remaining) {
+                if !format::print_jsonl(repo)? {
+                    return Ok(());
+                }
+                remaining -= 1;
+            }
+            if exhausted {
+                break;
+            }
+            cursor = page.cursor;
+        }
+        Ok(())
     }
 }
 
@@ -243,21 +717,197 @@ impl Process for ListRepos {
     type Output = ListReposResponse;
 
     async fn process(&self, client: &Client, _config: &Config) -> anyhow::Result<Self::Output> {
-        let url = format!("{}/com.atproto.sync.listRepos", BASE_URL);
-        let limit_str = self.limit.to_string();
-        let mut query = vec![("limit", limit_str.as_str())];
+        self.page(client, self.cursor.as_deref(), self.limit).await
+    }
+}
+
+impl ListBlobs {
+    /// List the blobs from `client`, which must already point at the
+    /// repository's PDS, and download them if `--download-dir` was given.
+    pub(crate) async fn fetch(&self, client: &Client) -> anyhow::Result<ListBlobsResponse> {
+        let cids = self.list(client).await?;
+        let downloads = match &self.download_dir {
+            Some(dir) => Some(self.download(client, dir, &cids).await?),
+            None => None,
+        };
+        Ok(ListBlobsResponse {
+            did: self.did.to_string(),
+            cids,
+            downloads,
+        })
+    }
+
+    /// Follow the cursor until every blob CID has been listed.
+    async fn list(&self, client: &Client) -> anyhow::Result<Vec<String>> {
+        let mut cids = Vec::new();
+        let mut cursor = None;
+        loop {
+            let mut query = vec![
+                ("did", self.did.to_string()),
+                ("limit", self.limit.to_string()),
+            ];
+            if let Some(since) = &self.since {
+                query.push(("since", since.to_string()));
+            }
+            if let Some(cursor) = cursor {
+                query.push(("cursor", cursor));
+            }
+            let page: ListBlobsPage = client
+                .get("com.atproto.sync.listBlobs", &query)
+                .await
+                .context("Failed to list blobs")?;
+            let exhausted = page.cids.is_empty() || page.cursor.is_none();
+            cids.extend(page.cids);
+            if exhausted {
+                return Ok(cids);
+            }
+            cursor = page.cursor;
+        }
+    }
 
-        if let Some(cursor) = &self.cursor {
-            query.push(("cursor", cursor));
+    /// Download each blob into `dir` through [`GetBlob`], at most
+    /// `--concurrency` at a time. Files already present are kept if they
+    /// match their CID. Every blob is attempted before any failure is
+    /// reported.
+    async fn download(
+        &self,
+        client: &Client,
+        dir: &str,
+        cids: &[String],
+    ) -> anyhow::Result<Vec<BlobDownload>> {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .with_context(|| format!("Failed to create {}", dir))?;
+
+        let results: Vec<_> = futures_util::stream::iter(cids.iter().cloned())
+            .map(|cid| async move {
+                let out = std::path::Path::new(dir)
+                    .join(&cid)
+                    .to_string_lossy()
+                    .into_owned();
+                let result = self.download_one(client, &cid, &out).await;
+                (cid, result)
+            })
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await;
+
+        let mut downloads = Vec::with_capacity(results.len());
+        let mut failures = Vec::new();
+        for (cid, result) in results {
+            match result {
+                Ok(download) => downloads.push(download),
+                Err(error) => failures.push(format!("  {}: {:#}", cid, error)),
+            }
+        }
+        if !failures.is_empty() {
+            anyhow::bail!(
+                "Failed to download {} of {} blobs:\n{}",
+                failures.len(),
+                cids.len(),
+                failures.join("\n")
+            );
+        }
+        Ok(downloads)
+    }
+
+    async fn download_one(
+        &self,
+        client: &Client,
+        cid: &str,
+        out: &str,
+    ) -> anyhow::Result<BlobDownload> {
+        let expected: Cid = cid.parse()?;
+        check_blob_cid(&expected)?;
+        if let Ok(existing) = tokio::fs::read(out).await
+            && Cid::for_block(expected.codec, &existing) == expected
+        {
+            return Ok(BlobDownload {
+                cid: cid.to_string(),
+                out: out.to_string(),
+                size: existing.len() as u64,
+                mime_type: None,
+                skipped: true,
+            });
         }
 
-        let response = client.inner().get(&url).query(&query).send().await?;
+        let blob = GetBlob {
+            did: self.did.clone(),
+            cid: expected,
+            out: Some(out.to_string()),
+        }
+        .download(client)
+        .await?;
+        Ok(BlobDownload {
+            cid: blob.cid,
+            out: out.to_string(),
+            size: blob.size,
+            mime_type: blob.mime_type,
+            skipped: false,
+        })
+    }
+}
+
+#[async_trait]
+impl Process for ListBlobs {
+    type Output = ListBlobsResponse;
+
+    async fn process(&self, client: &Client, _config: &Config) -> anyhow::Result<Self::Output> {
+        let client = client
+            .for_repo(self.did.as_str())
+            .await
+            .context("Failed to list blobs")?;
+        self.fetch(&client).await
+    }
+}
+
+#[async_trait]
+impl Process for Subscribe {
+    type Output = ();
 
-        if !response.status().is_success() {
-            anyhow::bail!("Failed to list repos: {}", response.status());
+    async fn process(&self, client: &Client, _config: &Config) -> anyhow::Result<Self::Output> {
+        let base = self.relay.as_deref().unwrap_or(client.service());
+        let base = if let Some(host) = base.strip_prefix("https://") {
+            format!("wss://{host}")
+        } else if let Some(host) = base.strip_prefix("http://") {
+            format!("ws://{host}")
+        } else if base.contains("://") {
+            base.to_string()
+        } else {
+            format!("wss://{base}")
+        };
+        let mut url = format!(
+            "{}/xrpc/com.atproto.sync.subscribeRepos",
+            base.trim_end_matches('/')
+        );
+        if let Some(cursor) = self.cursor {
+            url.push_str(&format!("?cursor={cursor}"));
         }
 
-        let response: ListReposResponse = response.json().await?;
-        Ok(response)
+        let (mut stream, _) = tokio_tungstenite::connect_async(url.as_str())
+            .await
+            .with_context(|| format!("Failed to subscribe to {}", base))?;
+        while let Some(message) = stream.next().await {
+            let frame = match message.context("Firehose connection failed")? {
+                Message::Binary(frame) => frame,
+                Message::Close(_) => break,
+                _ => continue,
+            };
+            let event = match firehose::decode_frame(&frame, &self.collection) {
+                Ok(Some(event)) => event,
+                Ok(None) => continue,
+                Err(error) if error.is::<firehose::StreamError>() => return Err(error),
+                // One bad frame shouldn't end the subscription; note it on
+                // stderr so stdout stays JSON lines
+                Err(error) => {
+                    eprintln!("Skipping malformed frame: {error:#}");
+                    continue;
+                }
+            };
+            if !format::print_jsonl(&event)? {
+                break;
+            }
+        }
+        Ok(())
     }
 }
@@ -1,12 +1,19 @@
+use anyhow::Context;
 use clap::Parser;
 use serde::{Deserialize, Serialize};
 
-use crate::{Client, Config, BASE_URL};
+use crate::{Account, Client, resolve, xrpc::check};
 
 #[derive(Parser)]
 pub enum Auth {
+    /// Log in and store the session as a named account
     Login(Login),
+    /// Show the active account's session
     Session,
+    /// List stored accounts
+    List,
+    /// Make an account the default for future commands
+    Use(Use),
 }
 
 #[derive(Parser, Serialize)]
@@ -15,15 +22,45 @@ pub struct Login {
     identifier: String,
     #[clap(short, long)]
     password: String,
+    /// Name to store the account under (defaults to its handle)
+    #[clap(short, long)]
+    name: Option<String>,
+}
+
+#[derive(Parser)]
+pub struct Use {
+    /// Account name as shown by `atp auth list`
+    pub name: String,
 }
 
 impl Login {
-    pub async fn process(&self, client: &Client) -> anyhow::Result<LoginResponse> {
+    /// Name the account should be stored under: `--name`, then the global
+    /// `--account`, then the account's handle.
+    pub fn account_name(&self, account: &Account, global: Option<String>) -> String {
+        self.name
+            .clone()
+            .or(global)
+            .unwrap_or_else(|| account.session.handle.clone())
+    }
+
+    pub async fn process(&self, client: &Client) -> anyhow::Result<Account> {
         let identifier = self.identifier.trim_start_matches('@');
-        let url = format!("{BASE_URL}/com.atproto.server.createSession");
-        let res = client
+
+        // Email identifiers can't be resolved up front, so they log in through
+        // the default service and the PDS is looked up from the returned DID.
+        let pds = if identifier.contains('@') {
+            None
+        } else {
+            Some(resolve::resolve_pds(client, identifier).await?.1)
+        };
+        let service = match &pds {
+            Some(pds) => Client::with_service(pds.as_str()),
+            None => client.clone(),
+        };
+
+        let res = service
             .inner()
-            .post(url)
+            .post(service.xrpc("com.atproto.server.createSession"))
             .json(&serde_json::json!({
                 "identifier": identifier,
                 "password": self.password
@@ -31,12 +68,13 @@ impl Login {
             .send()
             .await?;
 
-        if !res.status().is_success() {
-            let error = res.text().await?;
-            anyhow::bail!("Login failed: {}", error);
-        }
+        let session: LoginResponse = check(res).await.context("Login failed")?.json().await?;
+        let pds = match pds {
+            Some(pds) => pds,
+            None => resolve::resolve_pds(client, &session.did).await?.1,
+        };
 
-        Ok(res.json().await?)
+        Ok(Account { pds, session })
     }
 }
 
@@ -50,75 +88,11 @@ pub struct LoginResponse {
     pub(crate) refresh_jwt: String,
 }
 
-pub(super) async fn make_authenticated_request<T: serde::de::DeserializeOwned>(
-    client: &Client,
-    config: &Config,
-    endpoint: &str,
-    query: &[(&str, String)],
-) -> anyhow::Result<T> {
-    let url = format!("{}/app.bsky.actor.{}", super::BASE_URL, endpoint);
-    let session = config
-        .session
-        .as_ref()
-        .ok_or_else(|| anyhow::anyhow!("Not logged in"))?;
-
-    // First attempt with current access token
-    let res = client
-        .inner()
-        .get(&url)
-        .header("Authorization", format!("Bearer {}", session.access_jwt))
-        .query(query)
-        .send()
-        .await?;
-
-    let status = res.status();
-    if status.is_success() {
-        return Ok(res.json().await?);
-    }
-
-    let error_text = res.text().await?;
-
-    println!("Error: {}", error_text);
-    println!("Status: {}", status);
-
-    // Check specifically for 401 status and ExpiredToken error
-    // TODO: do this more gracefully
-    if error_text.contains("ExpiredToken") {
-        let new_session = refresh_session(client, &session.refresh_jwt).await?;
-
-        let mut new_config = config.clone();
-        new_config.session = Some(new_session);
-        new_config
-            .write(&directories::BaseDirs::new().unwrap())
-            .await?;
-
-        let retry_res = client
-            .inner()
-            .get(&url)
-            .header(
-                "Authorization",
-                format!("Bearer {}", new_config.session.unwrap().access_jwt),
-            )
-            .query(query)
-            .send()
-            .await?;
-
-        if retry_res.status().is_success() {
-            return Ok(retry_res.json().await?);
-        }
-
-        let retry_error = retry_res.text().await?;
-        anyhow::bail!("Request failed after token refresh: {}", retry_error);
-    }
-
-    anyhow::bail!("Request failed: {}", error_text)
-}
-
-pub(super) async fn refresh_session(
+pub(crate) async fn refresh_session(
     client: &Client,
     refresh_jwt: &str,
 ) -> anyhow::Result<LoginResponse> {
-    let url = format!("{BASE_URL}/com.atproto.server.refreshSession");
+    let url = client.xrpc("com.atproto.server.refreshSession");
     let res = client
         .inner()
         .post(url)
@@ -126,11 +100,6 @@ pub(super) async fn refresh_session(
         .send()
         .await?;
 
-    if !res.status().is_success() {
-        let error = res.text().await?;
-
-        anyhow::bail!("Session refresh failed: {}", error);
-    }
-
+    let res = check(res).await.context("Session refresh failed")?;
     Ok(res.json().await?)
 }
@@ -0,0 +1,383 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Client, Config, Process,
+    atproto::{
+        repo,
+        sync::{GetRepo, ListBlobs},
+    },
+    car::{self, CarReader},
+    cbor,
+    cid::{self, Cid},
+    format, mst,
+    syntax::Tid,
+};
+
+const MANIFEST: &str = "manifest.json";
+const REPO_CAR: &str = "repo.car";
+const PREFERENCES: &str = "preferences.json";
+const BLOBS_DIR: &str = "blobs";
+
+/// Back up an account to a directory and restore it
+#[derive(Parser)]
+pub enum Backup {
+    /// Back up the active account's repository, blobs and preferences
+    Create(Create),
+    /// Import a backup into the active account
+    Restore(Restore),
+}
+
+#[derive(Parser)]
+pub struct Create {
+    /// Directory to write the backup to; an existing backup there is updated
+    #[arg(long)]
+    pub dir: String,
+    /// Download the whole repository, ignoring the revision of the last backup
+    #[arg(long)]
+    pub full: bool,
+    /// Number of blobs to download at once
+    #[arg(long, default_value = "4", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=32))]
+    pub concurrency: usize,
+}
+
+#[derive(Parser)]
+pub struct Restore {
+    /// Backup directory written by `atp backup create`
+    #[arg(long)]
+    pub dir: String,
+}
+
+/// What a backup holds, written last so an interrupted run leaves the
+/// previous manifest in place.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Manifest {
+    pub did: String,
+    pub handle: String,
+    pub rev: String,
+    pub commit: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    /// Revision of the backup this one was built on, if incremental
+    pub since: Option<String>,
+    /// Blob CIDs with their MIME types, where known
+    pub blobs: BTreeMap<String, Option<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateBackupResponse {
+    pub did: String,
+    pub rev: String,
+    pub since: Option<String>,
+    pub dir: String,
+    pub records: usize,
+    pub blobs: usize,
+    pub downloaded: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RestoreBackupResponse {
+    pub did: String,
+    pub rev: String,
+    pub blobs: usize,
+    pub preferences: bool,
+}
+
+#[async_trait]
+impl Process for Backup {
+    type Output = String;
+
+    async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
+        match self {
+            Backup::Create(cmd) => {
+                let response = cmd.process(client, config).await?;
+                format::render(config.output, &response, |response| {
+                    let mut output = format!(
+                        "Backed up {} at rev {} to {}\n",
+                        response.did, response.rev, response.dir
+                    );
+                    if let Some(since) = &response.since {
+                        output.push_str(&format!("Incremental since: {}\n", since));
+                    }
+                    output.push_str(&format!(
+                        "Records: {}\nBlobs: {} ({} downloaded)",
+                        response.records, response.blobs, response.downloaded
+                    ));
+                    output
+                })
+            }
+            Backup::Restore(cmd) => {
+                let response = cmd.process(client, config).await?;
+                format::render(config.output, &response, |response| {
+                    format!(
+                        "Restored {} at rev {}\nBlobs uploaded: {}\nPreferences: {}",
+                        response.did,
+                        response.rev,
+                        response.blobs,
+                        if response.preferences {
+                            "restored"
+                        } else {
+                            "none"
+                        }
+                    )
+                })
+            }
+        }
+    }
+}
+
+async fn read_manifest(dir: &Path) -> anyhow::Result<Option<Manifest>> {
+    match tokio::fs::read(dir.join(MANIFEST)).await {
+        Ok(bytes) => Ok(Some(
+            serde_json::from_slice(&bytes).context("Invalid backup manifest")?,
+        )),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error).context("Failed to read backup manifest"),
+    }
+}
+
+#[async_trait]
+impl Process for Create {
+    type Output = CreateBackupResponse;
+
+    async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
+        let session = config
+            .session()
+            .ok_or_else(|| anyhow::anyhow!("Not logged in"))?;
+        let dir = PathBuf::from(&self.dir);
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .with_context(|| format!("Failed to create {}", self.dir))?;
+
+        let previous = match read_manifest(&dir).await? {
+            Some(manifest) if manifest.did != session.did => anyhow::bail!(
+                "{} holds a backup of {}, not {}",
+                self.dir,
+                manifest.did,
+                session.did
+            ),
+            manifest => manifest,
+        };
+        let since = previous
+            .as_ref()
+            .filter(|_| !self.full && dir.join(REPO_CAR).exists())
+            .map(|manifest| manifest.rev.parse::<Tid>())
+            .transpose()
+            .context("Invalid revision in the backup manifest")?;
+
+        // Fetch what changed into a side file, then fold it into repo.car
+        let partial = dir.join("repo.car.partial");
+        let repo = GetRepo {
+            did: session.did.parse()?,
+            out: partial.to_string_lossy().into_owned(),
+            since: since.clone(),
+        }
+        .download(client)
+        .await
+        .context("Failed to back up repo")?;
+        let merge_dir = dir.clone();
+        let incremental = since.is_some();
+        let records =
+            tokio::task::spawn_blocking(move || merge_repo(&merge_dir, incremental)).await??;
+
+        let blobs = ListBlobs {
+            did: session.did.parse()?,
+            since: since.clone(),
+            limit: 500,
+            download_dir: Some(dir.join(BLOBS_DIR).to_string_lossy().into_owned()),
+            concurrency: self.concurrency,
+        }
+        .fetch(client)
+        .await
+        .context("Failed to back up blobs")?;
+        let downloads = blobs.downloads.unwrap_or_default();
+
+        let preferences: serde_json::Value = client
+            .authenticated_get(config, "app.bsky.actor.getPreferences", &[])
+            .await
+            .context("Failed to back up preferences")?;
+        tokio::fs::write(
+            dir.join(PREFERENCES),
+            serde_json::to_vec_pretty(&preferences)?,
+        )
+        .await
+        .context("Failed to write preferences")?;
+
+        let mut known = previous.map(|manifest| manifest.blobs).unwrap_or_default();
+        for download in &downloads {
+            let mime_type = known.remove(&download.cid).flatten();
+            known.insert(
+                download.cid.clone(),
+                download.mime_type.clone().or(mime_type),
+            );
+        }
+        let manifest = Manifest {
+            did: session.did.clone(),
+            handle: session.handle.clone(),
+            rev: repo.rev.clone(),
+            commit: repo.commit.clone(),
+            created_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            since: since.as_ref().map(Tid::to_string),
+            blobs: known,
+        };
+        tokio::fs::write(dir.join(MANIFEST), serde_json::to_vec_pretty(&manifest)?)
+            .await
+            .context("Failed to write backup manifest")?;
+
+        Ok(CreateBackupResponse {
+            did: manifest.did,
+            rev: manifest.rev,
+            since: manifest.since,
+            dir: self.dir.clone(),
+            records,
+            blobs: manifest.blobs.len(),
+            downloaded: downloads
+                .iter()
+                .filter(|download| !download.skipped)
+                .count(),
+        })
+    }
+}
+
+/// Fold `repo.car.partial` into `repo.car`: the new commit plus every
+/// block its tree still refers to, taken from either file. Blocks only the
+/// old commit used are dropped. Returns the number of records.
+fn merge_repo(dir: &Path, incremental: bool) -> anyhow::Result<usize> {
+    let partial = dir.join("repo.car.partial");
+    let target = dir.join(REPO_CAR);
+
+    let open = |path: &Path| {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        CarReader::new(std::io::BufReader::new(file))
+            .with_context(|| format!("Invalid CAR file {}", path.display()))
+    };
+    let mut car = open(&partial)?;
+    let roots = car.roots.clone();
+    let mut blocks = car.blocks()?;
+    if incremental {
+        for (cid, block) in open(&target)?.blocks()? {
+            blocks.entry(cid).or_insert(block);
+        }
+    }
+
+    let root = roots.first().context("Repository CAR has no root")?;
+    let commit = blocks
+        .get(root)
+        .with_context(|| format!("Commit block {} is missing", root))?;
+    let data = cbor::decode(commit)
+        .context("Invalid commit block")?
+        .get("data")
+        .and_then(cbor::Value::as_link)
+        .cloned()
+        .context("Commit has no data root")?;
+
+    let cids = mst::reachable(&blocks, &data)?;
+    let mut order = vec![root];
+    for cid in &cids {
+        if !blocks.contains_key(cid) {
+            anyhow::bail!("Backup is missing block {}; run again with --full", cid);
+        }
+        order.push(cid);
+    }
+    let records = mst::walk(&blocks, &data)?.len();
+
+    let temp = dir.join("repo.car.tmp");
+    let mut file = std::io::BufWriter::new(
+        std::fs::File::create(&temp)
+            .with_context(|| format!("Failed to create {}", temp.display()))?,
+    );
+    car::write(
+        &mut file,
+        std::slice::from_ref(root),
+        order.into_iter().map(|cid| (cid, blocks[cid].as_slice())),
+    )?;
+    std::io::Write::flush(&mut file)?;
+    drop(file);
+    std::fs::rename(&temp, &target)
+        .with_context(|| format!("Failed to write {}", target.display()))?;
+    std::fs::remove_file(&partial)?;
+    Ok(records)
+}
+
+#[async_trait]
+impl Process for Restore {
+    type Output = RestoreBackupResponse;
+
+    async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
+        let session = config
+            .session()
+            .ok_or_else(|| anyhow::anyhow!("Not logged in"))?;
+        let dir = PathBuf::from(&self.dir);
+        let manifest = read_manifest(&dir)
+            .await?
+            .with_context(|| format!("{} has no backup manifest", self.dir))?;
+        // A repository is signed for its DID and can only be imported into it
+        if manifest.did != session.did {
+            anyhow::bail!(
+                "Backup is of {}, but the active account is {}",
+                manifest.did,
+                session.did
+            );
+        }
+
+        let car = tokio::fs::read(dir.join(REPO_CAR))
+            .await
+            .with_context(|| format!("Failed to read {}", REPO_CAR))?;
+        repo::import_repo(client, config, car).await?;
+
+        for (cid, mime_type) in &manifest.blobs {
+            let expected: Cid = cid.parse()?;
+            let path = dir.join(BLOBS_DIR).join(expected.to_string());
+            let bytes = tokio::fs::read(&path)
+                .await
+                .with_context(|| format!("Failed to read blob {}", path.display()))?;
+            if expected.hash_code != cid::SHA2_256
+                || Cid::for_block(expected.codec, &bytes) != expected
+            {
+                anyhow::bail!("Blob {} does not match its CID", path.display());
+            }
+
+            let mime_type = mime_type.as_deref().unwrap_or("application/octet-stream");
+            let response = repo::upload_blob(client, config, bytes, mime_type)
+                .await
+                .with_context(|| format!("Failed to upload blob {}", cid))?;
+            if response.blob.ref_["$link"].as_str() != Some(cid.as_str()) {
+                anyhow::bail!(
+                    "Server stored blob {} as {}",
+                    cid,
+                    response.blob.ref_["$link"]
+                );
+            }
+        }
+
+        let preferences = match tokio::fs::read(dir.join(PREFERENCES)).await {
+            Ok(bytes) => {
+                let preferences: serde_json::Value =
+                    serde_json::from_slice(&bytes).context("Invalid preferences backup")?;
+                let url = client.xrpc("app.bsky.actor.putPreferences");
+                client
+                    .send_authenticated(config, |http| http.post(&url).json(&preferences))
+                    .await
+                    .context("Failed to restore preferences")?;
+                true
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => false,
+            Err(error) => return Err(error).context("Failed to read preferences"),
+        };
+
+        Ok(RestoreBackupResponse {
+            did: manifest.did,
+            rev: manifest.rev,
+            blobs: manifest.blobs.len(),
+            preferences,
+        })
+    }
+}
@@ -1,8 +1,11 @@
 use async_trait::async_trait;
 use clap::Parser;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::{auth::make_authenticated_request, format, Client, Config, Process};
+use crate::{
+    Client, Config, Process,
+    format::{self, OutputFormat},
+};
 
 impl Profile {
     pub(crate) async fn process(
@@ -11,13 +14,13 @@ impl Profile {
         config: &Config,
     ) -> anyhow::Result<ProfileResponse> {
         let actor = self.actor.trim_start_matches('@');
-        make_authenticated_request(
-            client,
-            config,
-            "getProfile",
-            &[("actor", actor.to_string())],
-        )
-        .await
+        client
+            .authenticated_get(
+                config,
+                "app.bsky.actor.getProfile",
+                &[("actor", actor.to_string())],
+            )
+            .await
     }
 }
 
@@ -27,7 +30,9 @@ impl Preferences {
         client: &Client,
         config: &Config,
     ) -> anyhow::Result<PreferencesResponse> {
-        make_authenticated_request(client, config, "getPreferences", &[]).await
+        client
+            .authenticated_get(config, "app.bsky.actor.getPreferences", &[])
+            .await
     }
 }
 
@@ -48,7 +53,9 @@ impl Profiles {
             .map(|actor| ("actors", actor.to_string()))
             .collect();
 
-        make_authenticated_request(client, config, "getProfiles", &query).await
+        client
+            .authenticated_get(config, "app.bsky.actor.getProfiles", &query)
+            .await
     }
 }
 
@@ -63,7 +70,9 @@ impl Suggestions {
             query.push(("cursor", cursor.to_string()));
         }
 
-        make_authenticated_request(client, config, "getSuggestions", &query).await
+        client
+            .authenticated_get(config, "app.bsky.actor.getSuggestions", &query)
+            .await
     }
 }
 
@@ -78,7 +87,9 @@ impl SearchActors {
             query.push(("cursor", cursor.to_string()));
         }
 
-        make_authenticated_request(client, config, "searchActors", &query).await
+        client
+            .authenticated_get(config, "app.bsky.actor.searchActors", &query)
+            .await
     }
 }
 
@@ -133,62 +144,83 @@ pub struct SearchActors {
     cursor: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct ProfileResponse {
     #[allow(dead_code)]
     pub(crate) did: String,
     pub(crate) handle: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) avatar: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) banner: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) followers_count: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) follows_count: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) posts_count: Option<i64>,
     pub(crate) indexed_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) viewer: Option<ViewerState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) labels: Option<Vec<Label>>,
+    /// Fields not named above, kept so JSON output matches the response
+    #[serde(flatten)]
+    pub(crate) extra: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct PreferencesResponse {
     pub(crate) preferences: serde_json::Value,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct ViewerState {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) muted: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) blocked_by: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) blocking: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) following: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) followed_by: Option<bool>,
+    #[serde(flatten)]
+    pub(crate) extra: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct Label {
     pub(crate) val: String,
     #[allow(dead_code)]
     pub(crate) src: String,
+    #[serde(flatten)]
+    pub(crate) extra: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct ProfilesResponse {
     pub(crate) profiles: Vec<ProfileResponse>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct SuggestionsResponse {
     pub(crate) actors: Vec<ProfileResponse>,
     pub(crate) cursor: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct SearchActorsResponse {
     pub(crate) actors: Vec<ProfileResponse>,
@@ -203,23 +235,38 @@ impl Process for Bsky {
         match self {
             Bsky::Actor(Actor::Profile(cmd)) => {
                 let response = cmd.process(client, config).await?;
-                Ok(format::format_profile(&response).await)
+                match config.output {
+                    OutputFormat::Text => Ok(format::format_profile(&response).await),
+                    output => format::structured(output, &response),
+                }
             }
             Bsky::Actor(Actor::Profiles(cmd)) => {
                 let response = cmd.process(client, config).await?;
-                Ok(format::format_profiles(&response).await)
+                match config.output {
+                    OutputFormat::Text => Ok(format::format_profiles(&response).await),
+                    output => format::structured(output, &response),
+                }
             }
             Bsky::Actor(Actor::Preferences(cmd)) => {
                 let response = cmd.process(client, config).await?;
-                Ok(format::format_preferences(&response).await)
+                match config.output {
+                    OutputFormat::Text => Ok(format::format_preferences(&response).await),
+                    output => format::structured(output, &response),
+                }
             }
             Bsky::Actor(Actor::Suggestions(cmd)) => {
                 let response = cmd.process(client, config).await?;
-                Ok(format::format_suggestions(&response).await)
+                match config.output {
+                    OutputFormat::Text => Ok(format::format_suggestions(&response).await),
+                    output => format::structured(output, &response),
+                }
             }
             Bsky::Actor(Actor::Search(cmd)) => {
                 let response = cmd.process(client, config).await?;
-                Ok(format::format_search_actors(&response).await)
+                match config.output {
+                    OutputFormat::Text => Ok(format::format_search_actors(&response).await),
+                    output => format::structured(output, &response),
+                }
             }
         }
     }
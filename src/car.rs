@@ -0,0 +1,329 @@
+use std::{
+    collections::HashMap,
+    io::{ErrorKind, Read, Write},
+};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use clap::Parser;
+use serde::Serialize;
+
+use crate::{
+    Client, Config, Process, cbor,
+    cid::{self, Cid},
+    format, mst,
+};
+
+/// Larger sections than this are treated as a corrupt length prefix.
+const MAX_SECTION_LEN: u64 = 64 * 1024 * 1024;
+
+/// A reader for CAR v1 files: a DAG-CBOR header naming the root CIDs,
+/// followed by length-prefixed `(CID, block)` pairs.
+pub struct CarReader<R> {
+    reader: R,
+    pub roots: Vec<Cid>,
+}
+
+impl<R: Read> CarReader<R> {
+    /// Read the CAR header from `reader`, leaving it at the first block.
+    pub fn new(mut reader: R) -> anyhow::Result<Self> {
+        let len = read_varint(&mut reader)?.context("CAR file is empty")?;
+        let mut header = vec![0; section_len(len)?];
+        reader
+            .read_exact(&mut header)
+            .context("CAR header is truncated")?;
+        let header = cbor::decode(&header).context("Invalid CAR header")?;
+
+        let version = header.get("version").and_then(cbor::Value::as_integer);
+        if version != Some(1) {
+            anyhow::bail!("Unsupported CAR version {:?}", version);
+        }
+        let roots = header
+            .get("roots")
+            .and_then(cbor::Value::as_array)
+            .context("CAR header has no roots")?
+            .iter()
+            .map(|root| root.as_link().cloned().context("Invalid CAR root"))
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Self { reader, roots })
+    }
+
+    /// Read every remaining block into a map keyed by CID.
+    pub fn blocks(&mut self) -> anyhow::Result<HashMap<Cid, Vec<u8>>> {
+        let mut blocks = HashMap::new();
+        while let Some((cid, block)) = self.next_block()? {
+            blocks.insert(cid, block);
+        }
+        Ok(blocks)
+    }
+
+    /// Read the next block, or `None` at the end of the file.
+    pub fn next_block(&mut self) -> anyhow::Result<Option<(Cid, Vec<u8>)>> {
+        let Some(len) = read_varint(&mut self.reader)? else {
+            return Ok(None);
+        };
+        let mut section = vec![0; section_len(len)?];
+        self.reader
+            .read_exact(&mut section)
+            .context("CAR block is truncated")?;
+        let (cid, cid_len) = Cid::read_bytes(&section).context("Invalid block CID")?;
+        section.drain(..cid_len);
+        Ok(Some((cid, section)))
+    }
+}
+
+/// Write a CAR v1 file naming `roots`, with `blocks` in the order given.
+pub fn write<'a>(
+    writer: &mut impl Write,
+    roots: &[Cid],
+    blocks: impl IntoIterator<Item = (&'a Cid, &'a [u8])>,
+) -> std::io::Result<()> {
+    let header = cbor::encode(&cbor::Value::Map(
+        [
+            ("version".to_string(), cbor::Value::Integer(1)),
+            (
+                "roots".to_string(),
+                cbor::Value::Array(roots.iter().cloned().map(cbor::Value::Link).collect()),
+            ),
+        ]
+        .into(),
+    ));
+    let mut prefix = Vec::new();
+    cid::write_varint(&mut prefix, header.len() as u64);
+    writer.write_all(&prefix)?;
+    writer.write_all(&header)?;
+    for (cid, block) in blocks {
+        let cid = cid.to_bytes();
+        prefix.clear();
+        cid::write_varint(&mut prefix, (cid.len() + block.len()) as u64);
+        writer.write_all(&prefix)?;
+        writer.write_all(&cid)?;
+        writer.write_all(block)?;
+    }
+    Ok(())
+}
+
+fn section_len(len: u64) -> anyhow::Result<usize> {
+    if len > MAX_SECTION_LEN {
+        anyhow::bail!("CAR section of {} bytes is too large", len);
+    }
+    Ok(len as usize)
+}
+
+/// Read a varint section length, or `None` at a clean end of input.
+fn read_varint(reader: &mut impl Read) -> anyhow::Result<Option<u64>> {
+    let mut value = 0u64;
+    for index in 0..10 {
+        let mut byte = [0u8];
+        match reader.read_exact(&mut byte) {
+            Ok(()) => {}
+            Err(error) if error.kind() == ErrorKind::UnexpectedEof && index == 0 => {
+                return Ok(None);
+            }
+            Err(error) => return Err(error).context("CAR file is truncated"),
+        }
+        value |= u64::from(byte[0] & 0x7f) << (7 * index);
+        if byte[0] & 0x80 == 0 {
+            return Ok(Some(value));
+        }
+    }
+    anyhow::bail!("Invalid varint in CAR file")
+}
+
+/// Inspect a CAR file offline
+#[derive(Parser)]
+pub enum Car {
+    /// List the blocks in a CAR file
+    Ls(Ls),
+    /// Print a block as JSON
+    Cat(Cat),
+    /// List every record in a repository CAR file
+    Records(Records),
+}
+
+#[derive(Parser)]
+pub struct Ls {
+    /// Path to the CAR file
+    pub file: String,
+}
+
+#[derive(Parser)]
+pub struct Cat {
+    /// Path to the CAR file
+    pub file: String,
+    /// CID of the block to print
+    pub cid: Cid,
+}
+
+#[derive(Parser)]
+pub struct Records {
+    /// Path to the CAR file
+    pub file: String,
+    /// Only list records in this collection
+    #[arg(long)]
+    pub collection: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BlockSummary {
+    pub cid: String,
+    pub codec: String,
+    pub size: usize,
+    pub root: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecordEntry {
+    pub collection: String,
+    pub rkey: String,
+    pub cid: String,
+    pub value: serde_json::Value,
+}
+
+#[async_trait]
+impl Process for Car {
+    type Output = String;
+
+    async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
+        match self {
+            Car::Ls(cmd) => {
+                let blocks = cmd.process(client, config).await?;
+                format::render(config.output, &blocks, |blocks| {
+                    blocks
+                        .iter()
+                        .map(|block| {
+                            let root = if block.root { " (root)" } else { "" };
+                            format!(
+                                "{}  {}  {} bytes{}",
+                                block.cid, block.codec, block.size, root
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })
+            }
+            Car::Cat(cmd) => {
+                let value = cmd.process(client, config).await?;
+                format::render(config.output, &value, format::format_json)
+            }
+            Car::Records(cmd) => {
+                let records = cmd.process(client, config).await?;
+                format::render(config.output, &records, |records| {
+                    records
+                        .iter()
+                        .map(|record| {
+                            format!("{}/{}: {}", record.collection, record.rkey, record.value)
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })
+            }
+        }
+    }
+}
+
+/// Open a CAR file, read fully into memory.
+async fn open(path: &str) -> anyhow::Result<CarReader<std::io::Cursor<Vec<u8>>>> {
+    let bytes = tokio::fs::read(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path))?;
+    CarReader::new(std::io::Cursor::new(bytes))
+        .with_context(|| format!("Invalid CAR file {}", path))
+}
+
+fn codec_name(codec: u64) -> String {
+    match codec {
+        cid::DAG_CBOR => "dag-cbor".to_string(),
+        cid::RAW => "raw".to_string(),
+        codec => format!("0x{codec:x}"),
+    }
+}
+
+#[async_trait]
+impl Process for Ls {
+    type Output = Vec<BlockSummary>;
+
+    async fn process(&self, _client: &Client, _config: &Config) -> anyhow::Result<Self::Output> {
+        let mut car = open(&self.file).await?;
+        let mut blocks = Vec::new();
+        while let Some((cid, block)) = car.next_block()? {
+            blocks.push(BlockSummary {
+                root: car.roots.contains(&cid),
+                cid: cid.to_string(),
+                codec: codec_name(cid.codec),
+                size: block.len(),
+            });
+        }
+        Ok(blocks)
+    }
+}
+
+#[async_trait]
+impl Process for Cat {
+    type Output = serde_json::Value;
+
+    async fn process(&self, _client: &Client, _config: &Config) -> anyhow::Result<Self::Output> {
+        let mut car = open(&self.file).await?;
+        while let Some((cid, block)) = car.next_block()? {
+            if cid != self.cid {
+                continue;
+            }
+            return match cid.codec {
+                cid::DAG_CBOR => Ok(cbor::decode(&block)
+                    .with_context(|| format!("Invalid DAG-CBOR block {}", cid))?
+                    .to_json()),
+                _ => Ok(cbor::Value::Bytes(block).to_json()),
+            };
+        }
+        anyhow::bail!("Block {} is not in {}", self.cid, self.file)
+    }
+}
+
+#[async_trait]
+impl Process for Records {
+    type Output = Vec<RecordEntry>;
+
+    async fn process(&self, _client: &Client, _config: &Config) -> anyhow::Result<Self::Output> {
+        let mut car = open(&self.file).await?;
+        let root = car.roots.first().cloned().context("CAR file has no root")?;
+        let blocks = car.blocks()?;
+
+        let commit = blocks
+            .get(&root)
+            .with_context(|| format!("Commit block {} is missing", root))?;
+        let commit = cbor::decode(commit).context("Invalid commit block")?;
+        let data = commit
+            .get("data")
+            .and_then(cbor::Value::as_link)
+            .context("Commit has no data root; is this a repository CAR?")?;
+
+        let mut records = Vec::new();
+        for (key, cid) in mst::walk(&blocks, data)? {
+            let Some((collection, rkey)) = key.split_once('/') else {
+                anyhow::bail!("Invalid repository key {}", key);
+            };
+            if self
+                .collection
+                .as_ref()
+                .is_some_and(|wanted| wanted != collection)
+            {
+                continue;
+            }
+            let value = match blocks.get(&cid) {
+                Some(block) => cbor::decode(block)
+                    .with_context(|| format!("Invalid record {}", key))?
+                    .to_json(),
+                // Partial CARs (e.g. from --since) may leave records out
+                None => serde_json::Value::Null,
+            };
+            records.push(RecordEntry {
+                collection: collection.to_string(),
+                rkey: rkey.to_string(),
+                cid: cid.to_string(),
+                value,
+            });
+        }
+        Ok(records)
+    }
+}
@@ -0,0 +1,330 @@
+use std::collections::BTreeMap;
+
+use base64::{Engine, engine::general_purpose::STANDARD_NO_PAD};
+
+use crate::cid::Cid;
+
+/// The CBOR tag DAG-CBOR uses for CID links.
+const CID_TAG: u64 = 42;
+/// Deeper nesting than this is treated as malformed input.
+const MAX_DEPTH: usize = 128;
+
+/// A decoded DAG-CBOR value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    Bytes(Vec<u8>),
+    String(String),
+    Array(Vec<Value>),
+    Map(BTreeMap<String, Value>),
+    Link(Cid),
+}
+
+impl Value {
+    /// Look up a key in a map value.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Map(fields) => fields.get(key),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Integer(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_link(&self) -> Option<&Cid> {
+        match self {
+            Value::Link(cid) => Some(cid),
+            _ => None,
+        }
+    }
+
+    /// Convert to the atproto JSON representation, where links become
+    /// `{"$link": cid}` and bytes become `{"$bytes": base64}`.
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Null => serde_json::Value::Null,
+            Value::Bool(b) => serde_json::Value::Bool(*b),
+            Value::Integer(n) => serde_json::Value::from(*n),
+            Value::Float(f) => serde_json::Value::from(*f),
+            Value::Bytes(bytes) => serde_json::json!({ "$bytes": STANDARD_NO_PAD.encode(bytes) }),
+            Value::String(s) => serde_json::Value::String(s.clone()),
+            Value::Array(items) => items.iter().map(Value::to_json).collect(),
+            Value::Map(fields) => fields
+                .iter()
+                .map(|(key, value)| (key.clone(), value.to_json()))
+                .collect(),
+            Value::Link(cid) => serde_json::json!({ "$link": cid.to_string() }),
+        }
+    }
+
+    /// Convert from the atproto JSON representation; the inverse of
+    /// [`Value::to_json`].
+    pub fn from_json(json: &serde_json::Value) -> anyhow::Result<Self> {
+        Ok(match json {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(b) => Value::Bool(*b),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(n) => Value::Integer(n),
+                None => Value::Float(
+                    n.as_f64()
+                        .ok_or_else(|| anyhow::anyhow!("Unsupported number {}", n))?,
+                ),
+            },
+            serde_json::Value::String(s) => Value::String(s.clone()),
+            serde_json::Value::Array(items) => Value::Array(
+                items
+                    .iter()
+                    .map(Value::from_json)
+                    .collect::<anyhow::Result<_>>()?,
+            ),
+            serde_json::Value::Object(fields) => {
+                match (fields.len(), fields.get("$link"), fields.get("$bytes")) {
+                    (1, Some(serde_json::Value::String(link)), _) => Value::Link(link.parse()?),
+                    (1, _, Some(serde_json::Value::String(bytes))) => Value::Bytes(
+                        STANDARD_NO_PAD
+                            .decode(bytes.trim_end_matches('='))
+                            .map_err(|error| anyhow::anyhow!("Invalid $bytes: {}", error))?,
+                    ),
+                    _ => Value::Map(
+                        fields
+                            .iter()
+                            .map(|(key, value)| Ok((key.clone(), Value::from_json(value)?)))
+                            .collect::<anyhow::Result<_>>()?,
+                    ),
+                }
+            }
+        })
+    }
+}
+
+/// Encode a value as canonical DAG-CBOR: shortest-form lengths, 64-bit
+/// floats, and map keys sorted by length and then bytewise.
+pub fn encode(value: &Value) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode_into(&mut out, value);
+    out
+}
+
+fn encode_into(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Null => out.push(0xf6),
+        Value::Bool(false) => out.push(0xf4),
+        Value::Bool(true) => out.push(0xf5),
+        Value::Integer(n) if *n >= 0 => write_head(out, 0, *n as u64),
+        Value::Integer(n) => write_head(out, 1, (-1 - *n) as u64),
+        Value::Float(f) => {
+            out.push(0xfb);
+            out.extend_from_slice(&f.to_be_bytes());
+        }
+        Value::Bytes(bytes) => {
+            write_head(out, 2, bytes.len() as u64);
+            out.extend_from_slice(bytes);
+        }
+        Value::String(s) => {
+            write_head(out, 3, s.len() as u64);
+            out.extend_from_slice(s.as_bytes());
+        }
+        Value::Array(items) => {
+            write_head(out, 4, items.len() as u64);
+            for item in items {
+                encode_into(out, item);
+            }
+        }
+        Value::Map(fields) => {
+            let mut keys: Vec<&String> = fields.keys().collect();
+            keys.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+            write_head(out, 5, keys.len() as u64);
+            for key in keys {
+                write_head(out, 3, key.len() as u64);
+                out.extend_from_slice(key.as_bytes());
+                encode_into(out, &fields[key]);
+            }
+        }
+        Value::Link(cid) => {
+            write_head(out, 6, CID_TAG);
+            let bytes = cid.to_bytes();
+            write_head(out, 2, bytes.len() as u64 + 1);
+            out.push(0);
+            out.extend_from_slice(&bytes);
+        }
+    }
+}
+
+fn write_head(out: &mut Vec<u8>, major: u8, value: u64) {
+    let major = major << 5;
+    match value {
+        0..=23 => out.push(major | value as u8),
+        24..=0xff => out.extend_from_slice(&[major | 24, value as u8]),
+        0x100..=0xffff => {
+            out.push(major | 25);
+            out.extend_from_slice(&(value as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(major | 26);
+            out.extend_from_slice(&(value as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 27);
+            out.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+}
+
+/// Decode a DAG-CBOR value that makes up the whole of `bytes`.
+pub fn decode(bytes: &[u8]) -> anyhow::Result<Value> {
+    let (value, len) = decode_prefix(bytes)?;
+    if len != bytes.len() {
+        anyhow::bail!("Trailing bytes after CBOR value");
+    }
+    Ok(value)
+}
+
+/// Decode one DAG-CBOR value from the start of `bytes`, returning it and the
+/// number of bytes it took up.
+pub fn decode_prefix(bytes: &[u8]) -> anyhow::Result<(Value, usize)> {
+    let mut decoder = Decoder { bytes, pos: 0 };
+    let value = decoder.value(0)?;
+    Ok((value, decoder.pos))
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Decoder<'_> {
+    fn take(&mut self, len: usize) -> anyhow::Result<&[u8]> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| anyhow::anyhow!("Truncated CBOR input"))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn uint(&mut self, additional: u8) -> anyhow::Result<u64> {
+        Ok(match additional {
+            0..=23 => u64::from(additional),
+            24 => u64::from(self.take(1)?[0]),
+            25 => u64::from(u16::from_be_bytes(self.take(2)?.try_into()?)),
+            26 => u64::from(u32::from_be_bytes(self.take(4)?.try_into()?)),
+            27 => u64::from_be_bytes(self.take(8)?.try_into()?),
+            _ => anyhow::bail!("Unsupported CBOR length encoding {}", additional),
+        })
+    }
+
+    fn len(&mut self, additional: u8) -> anyhow::Result<usize> {
+        let len = self.uint(additional)?;
+        // Every item takes at least a byte, so a longer length can't be valid
+        if len > (self.bytes.len() - self.pos) as u64 {
+            anyhow::bail!("Truncated CBOR input");
+        }
+        Ok(len as usize)
+    }
+
+    fn value(&mut self, depth: usize) -> anyhow::Result<Value> {
+        if depth > MAX_DEPTH {
+            anyhow::bail!("CBOR nesting is too deep");
+        }
+        let initial = self.take(1)?[0];
+        let (major, additional) = (initial >> 5, initial & 0x1f);
+        Ok(match major {
+            0 => Value::Integer(i64::try_from(self.uint(additional)?)?),
+            1 => Value::Integer(-1 - i64::try_from(self.uint(additional)?)?),
+            2 => {
+                let len = self.len(additional)?;
+                Value::Bytes(self.take(len)?.to_vec())
+            }
+            3 => {
+                let len = self.len(additional)?;
+                Value::String(String::from_utf8(self.take(len)?.to_vec())?)
+            }
+            4 => {
+                let len = self.len(additional)?;
+                let items = (0..len)
+                    .map(|_| self.value(depth + 1))
+                    .collect::<anyhow::Result<_>>()?;
+                Value::Array(items)
+            }
+            5 => {
+                let len = self.len(additional)?;
+                let mut fields = BTreeMap::new();
+                for _ in 0..len {
+                    let key = match self.value(depth + 1)? {
+                        Value::String(key) => key,
+                        _ => anyhow::bail!("DAG-CBOR map keys must be strings"),
+                    };
+                    let value = self.value(depth + 1)?;
+                    fields.insert(key, value);
+                }
+                Value::Map(fields)
+            }
+            6 => {
+                let tag = self.uint(additional)?;
+                if tag != CID_TAG {
+                    anyhow::bail!("Unsupported CBOR tag {}", tag);
+                }
+                match self.value(depth + 1)? {
+                    Value::Bytes(bytes) if bytes.first() == Some(&0) => {
+                        Value::Link(Cid::from_bytes(&bytes[1..])?)
+                    }
+                    _ => anyhow::bail!("Invalid CID link"),
+                }
+            }
+            _ => match additional {
+                20 => Value::Bool(false),
+                21 => Value::Bool(true),
+                22 | 23 => Value::Null,
+                25 => Value::Float(half_to_f64(u16::from_be_bytes(self.take(2)?.try_into()?))),
+                26 => Value::Float(f64::from(f32::from_be_bytes(self.take(4)?.try_into()?))),
+                27 => Value::Float(f64::from_be_bytes(self.take(8)?.try_into()?)),
+                _ => anyhow::bail!("Unsupported CBOR simple value {}", additional),
+            },
+        })
+    }
+}
+
+fn half_to_f64(half: u16) -> f64 {
+    let exponent = (half >> 10) & 0x1f;
+    let mantissa = f64::from(half & 0x3ff);
+    let magnitude = match exponent {
+        0 => mantissa * 2f64.powi(-24),
+        31 if mantissa == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f64.powi(i32::from(exponent) - 15),
+    };
+    if half & 0x8000 != 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
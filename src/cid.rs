@@ -0,0 +1,165 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::Context;
+use sha2::{Digest, Sha256};
+
+/// Multicodec code for DAG-CBOR blocks (records, commits, MST nodes).
+pub const DAG_CBOR: u64 = 0x71;
+/// Multicodec code for raw binary blocks (blobs).
+pub const RAW: u64 = 0x55;
+/// Multihash code for SHA-256.
+pub const SHA2_256: u64 = 0x12;
+
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// A version 1 content identifier, as used throughout atproto.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Cid {
+    pub codec: u64,
+    pub hash_code: u64,
+    pub digest: Vec<u8>,
+}
+
+impl Cid {
+    /// The SHA-256 CID of a block's bytes.
+    pub fn for_block(codec: u64, bytes: &[u8]) -> Self {
+        Self {
+            codec,
+            hash_code: SHA2_256,
+            digest: Sha256::digest(bytes).to_vec(),
+        }
+    }
+
+    /// Parse a binary CID from the start of `bytes`, returning it and the
+    /// number of bytes it took up.
+    pub fn read_bytes(bytes: &[u8]) -> anyhow::Result<(Self, usize)> {
+        let mut pos = 0;
+        let mut next = || -> anyhow::Result<u64> {
+            let (value, len) = read_varint(&bytes[pos..])?;
+            pos += len;
+            Ok(value)
+        };
+        let version = next()?;
+        if version == SHA2_256 {
+            anyhow::bail!("CIDv0 is not supported");
+        }
+        if version != 1 {
+            anyhow::bail!("Unsupported CID version {}", version);
+        }
+        let codec = next()?;
+        let hash_code = next()?;
+        let len = next()?;
+        let end = usize::try_from(len)
+            .ok()
+            .and_then(|len| pos.checked_add(len))
+            .context("CID digest is truncated")?;
+        let digest = bytes
+            .get(pos..end)
+            .context("CID digest is truncated")?
+            .to_vec();
+        Ok((
+            Self {
+                codec,
+                hash_code,
+                digest,
+            },
+            end,
+        ))
+    }
+
+    /// Parse a binary CID that makes up the whole of `bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let (cid, len) = Self::read_bytes(bytes)?;
+        if len != bytes.len() {
+            anyhow::bail!("Trailing bytes after CID");
+        }
+        Ok(cid)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.digest.len() + 4);
+        write_varint(&mut bytes, 1);
+        write_varint(&mut bytes, self.codec);
+        write_varint(&mut bytes, self.hash_code);
+        write_varint(&mut bytes, self.digest.len() as u64);
+        bytes.extend_from_slice(&self.digest);
+        bytes
+    }
+}
+
+impl fmt::Display for Cid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "b{}", base32_encode(&self.to_bytes()))
+    }
+}
+
+impl FromStr for Cid {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let encoded = s
+            .strip_prefix('b')
+            .ok_or_else(|| anyhow::anyhow!("Invalid CID {}: expected base32 (b...)", s))?;
+        let bytes = base32_decode(encoded)
+            .ok_or_else(|| anyhow::anyhow!("Invalid CID {}: bad base32", s))?;
+        Self::from_bytes(&bytes).map_err(|error| anyhow::anyhow!("Invalid CID {}: {}", s, error))
+    }
+}
+
+/// Read an unsigned LEB128 varint from the start of `bytes`, returning it
+/// and the number of bytes it took up.
+pub fn read_varint(bytes: &[u8]) -> anyhow::Result<(u64, usize)> {
+    let mut value = 0u64;
+    for (index, byte) in bytes.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Ok((value, index + 1));
+        }
+    }
+    anyhow::bail!("Invalid or truncated varint")
+}
+
+pub fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Lowercase RFC 4648 base32 without padding, as used by multibase `b`.
+pub fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 8 / 5 + 1);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | u32::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+pub fn base32_decode(encoded: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(encoded.len() * 5 / 8);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in encoded.bytes() {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&a| a == c.to_ascii_lowercase())?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
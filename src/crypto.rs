@@ -0,0 +1,89 @@
+use anyhow::Context;
+use k256::ecdsa::signature::Verifier;
+
+use crate::resolve::DidDocument;
+
+/// Multicodec prefix for a compressed secp256k1 public key (0xe7).
+const SECP256K1_PUB: [u8; 2] = [0xe7, 0x01];
+/// Multicodec prefix for a compressed P-256 public key (0x1200).
+const P256_PUB: [u8; 2] = [0x80, 0x24];
+
+/// A public key of one of the two curves atproto signs with.
+#[derive(Clone, Debug)]
+pub enum PublicKey {
+    K256(k256::ecdsa::VerifyingKey),
+    P256(p256::ecdsa::VerifyingKey),
+}
+
+impl PublicKey {
+    /// Parse a multibase (`z...`) multicodec-prefixed key, as found in
+    /// `publicKeyMultibase` and `did:key` identifiers.
+    pub fn from_multibase(encoded: &str) -> anyhow::Result<Self> {
+        let bytes = encoded
+            .strip_prefix('z')
+            .and_then(|encoded| bs58::decode(encoded).into_vec().ok())
+            .with_context(|| format!("Invalid multibase key {}", encoded))?;
+        match bytes.split_at_checked(2) {
+            Some((prefix, key)) if prefix == SECP256K1_PUB => Ok(Self::K256(
+                k256::ecdsa::VerifyingKey::from_sec1_bytes(key).context("Invalid secp256k1 key")?,
+            )),
+            Some((prefix, key)) if prefix == P256_PUB => Ok(Self::P256(
+                p256::ecdsa::VerifyingKey::from_sec1_bytes(key).context("Invalid P-256 key")?,
+            )),
+            _ => anyhow::bail!("Unsupported key type in {}", encoded),
+        }
+    }
+
+    /// Parse a `did:key:z...` identifier.
+    pub fn from_did_key(did_key: &str) -> anyhow::Result<Self> {
+        let encoded = did_key
+            .strip_prefix("did:key:")
+            .with_context(|| format!("Invalid did:key {}", did_key))?;
+        Self::from_multibase(encoded)
+    }
+
+    /// Find the `#atproto` signing key in a DID document.
+    pub fn from_did_document(did_doc: &DidDocument) -> anyhow::Result<Self> {
+        let encoded = did_doc
+            .verification_method("atproto")
+            .and_then(|method| method.public_key_multibase.as_deref())
+            .context("DID document has no #atproto verification key")?;
+        Self::from_multibase(encoded)
+    }
+
+    pub fn to_did_key(&self) -> String {
+        let (prefix, key) = match self {
+            Self::K256(key) => (
+                SECP256K1_PUB,
+                key.to_encoded_point(true).as_bytes().to_vec(),
+            ),
+            Self::P256(key) => (P256_PUB, key.to_encoded_point(true).as_bytes().to_vec()),
+        };
+        let bytes = [prefix.as_slice(), &key].concat();
+        format!("did:key:z{}", bs58::encode(bytes).into_string())
+    }
+
+    /// Check a 64-byte compact signature over the SHA-256 of `message`.
+    /// atproto only accepts low-S signatures.
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> anyhow::Result<()> {
+        match self {
+            Self::K256(key) => {
+                let signature = k256::ecdsa::Signature::from_slice(signature)
+                    .context("Invalid signature encoding")?;
+                if signature.normalize_s().is_some() {
+                    anyhow::bail!("Signature is not in low-S form");
+                }
+                key.verify(message, &signature)
+            }
+            Self::P256(key) => {
+                let signature = p256::ecdsa::Signature::from_slice(signature)
+                    .context("Invalid signature encoding")?;
+                if signature.normalize_s().is_some() {
+                    anyhow::bail!("Signature is not in low-S form");
+                }
+                key.verify(message, &signature)
+            }
+        }
+        .map_err(|_| anyhow::anyhow!("Signature does not match the key"))
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt::Display;
+
+use anyhow::Context;
+use serde_json::Value;
+
+use crate::{car::CarReader, cbor, syntax::Nsid};
+
+/// An error frame (`op` -1) from the relay, which closes the stream after it.
+#[derive(Debug)]
+pub struct StreamError {
+    pub error: String,
+    pub message: String,
+}
+
+impl Display for StreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Stream error {}: {}", self.error, self.message)
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+/// Decode one `subscribeRepos` frame: a DAG-CBOR header `{op, t}` followed by
+/// a DAG-CBOR body. Returns the event as JSON with its type in `$type`, or
+/// `None` for a commit with no ops left after filtering by `collections`.
+///
+/// Commit blocks are unpacked so each op carries its record, rather than the
+/// raw CAR bytes. An error frame is returned as a [`StreamError`].
+pub fn decode_frame(frame: &[u8], collections: &[Nsid]) -> anyhow::Result<Option<Value>> {
+    let (header, len) = cbor::decode_prefix(frame).context("Invalid frame header")?;
+    let body = cbor::decode(&frame[len..]).context("Invalid frame body")?;
+
+    if header.get("op").and_then(cbor::Value::as_integer) == Some(-1) {
+        return Err(StreamError {
+            error: body
+                .get("error")
+                .and_then(cbor::Value::as_str)
+                .unwrap_or("Unknown")
+                .to_string(),
+            message: body
+                .get("message")
+                .and_then(cbor::Value::as_str)
+                .unwrap_or("")
+                .to_string(),
+        }
+        .into());
+    }
+    let kind = header
+        .get("t")
+        .and_then(cbor::Value::as_str)
+        .context("Frame header has no message type")?;
+
+    let mut event = body.to_json();
+    let fields = event
+        .as_object_mut()
+        .context("Frame body is not an object")?;
+    match kind {
+        "#commit" => {
+            let blocks = match body.get("blocks").and_then(cbor::Value::as_bytes) {
+                Some(bytes) if !bytes.is_empty() => CarReader::new(bytes)?.blocks()?,
+                _ => Default::default(),
+            };
+            let Some(Value::Array(ops)) = fields.get_mut("ops") else {
+                anyhow::bail!("Commit has no ops");
+            };
+            if !collections.is_empty() {
+                ops.retain(|op| {
+                    op["path"].as_str().is_some_and(|path| {
+                        collections
+                            .iter()
+                            .any(|collection| path.split('/').next() == Some(collection.as_str()))
+                    })
+                });
+                if ops.is_empty() {
+                    return Ok(None);
+                }
+            }
+            for op in ops.iter_mut() {
+                let Some(cid) = op["cid"]["$link"].as_str() else {
+                    continue;
+                };
+                if let Some(block) = blocks.get(&cid.parse()?) {
+                    op["record"] = cbor::decode(block)
+                        .with_context(|| format!("Invalid record {}", cid))?
+                        .to_json();
+                }
+            }
+            fields.remove("blocks");
+        }
+        "#sync" => {
+            // The blocks hold just the commit; its CID is what's useful
+            if let Some(bytes) = body.get("blocks").and_then(cbor::Value::as_bytes) {
+                let car = CarReader::new(bytes)?;
+                if let Some(root) = car.roots.first() {
+                    fields.insert(
+                        "commit".to_string(),
+                        serde_json::json!({ "$link": root.to_string() }),
+                    );
+                }
+            }
+            fields.remove("blocks");
+        }
+        _ => {}
+    }
+
+    let mut typed = serde_json::Map::new();
+    typed.insert("$type".to_string(), Value::String(kind.to_string()));
+    typed.append(fields);
+    Ok(Some(Value::Object(typed)))
+}
@@ -1,7 +1,9 @@
 use chrono::DateTime;
+use clap::ValueEnum;
 use colored::Colorize;
 use image::load_from_memory;
 use serde::Serialize;
+use serde_json::Value;
 use std::io::Write;
 use textwrap::fill;
 use viuer::Config as ViuerConfig;
@@ -16,37 +18,35 @@ pub(super) async fn format_profile(profile: &ProfileResponse) -> String {
     output.push_str("\n\n");
 
     // Try to display banner if available
-    if let Some(banner_url) = &profile.banner {
-        if let Ok(image_data) = download_image(banner_url).await {
-            if let Ok(image) = load_from_memory(&image_data) {
-                let conf = ViuerConfig {
-                    width: Some(80),
-                    height: Some(12),
-                    ..Default::default()
-                };
-                let _ = viuer::print(&image, &conf);
-                std::io::stdout().flush().unwrap();
-            }
-        }
+    if let Some(banner_url) = &profile.banner
+        && let Ok(image_data) = download_image(banner_url).await
+        && let Ok(image) = load_from_memory(&image_data)
+    {
+        let conf = ViuerConfig {
+            width: Some(80),
+            height: Some(12),
+            ..Default::default()
+        };
+        let _ = viuer::print(&image, &conf);
+        std::io::stdout().flush().unwrap();
     }
 
     // Try to display avatar if available
-    if let Some(avatar_url) = &profile.avatar {
-        if let Ok(image_data) = download_image(avatar_url).await {
-            if let Ok(image) = load_from_memory(&image_data) {
-                let height = 12; // Fixed height
-                let width = height * 2; // Double the width to account for terminal character aspect ratio
-                let conf = ViuerConfig {
-                    width: Some(width),
-                    height: Some(height),
-                    absolute_offset: true,
-                    x: 5,
-                    y: (height / 2) as i16,
-                    ..Default::default()
-                };
-                let _ = viuer::print(&image, &conf);
-            }
-        }
+    if let Some(avatar_url) = &profile.avatar
+        && let Ok(image_data) = download_image(avatar_url).await
+        && let Ok(image) = load_from_memory(&image_data)
+    {
+        let height = 12; // Fixed height
+        let width = height * 2; // Double the width to account for terminal character aspect ratio
+        let conf = ViuerConfig {
+            width: Some(width),
+            height: Some(height),
+            absolute_offset: true,
+            x: 5,
+            y: (height / 2) as i16,
+            ..Default::default()
+        };
+        let _ = viuer::print(&image, &conf);
     }
 
     // Display name and handle section
@@ -153,6 +153,158 @@ pub fn format_json<T: Serialize>(value: &T) -> String {
     serde_json::to_string_pretty(value).unwrap()
 }
 
+/// How command results are written to stdout.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+    /// Pretty-printed JSON
+    Json,
+    /// One JSON value per line, one line per listed item
+    Jsonl,
+    /// YAML
+    Yaml,
+    /// Aligned columns, one row per listed item
+    Table,
+}
+
+/// Render a command result, using `text` for the human-readable format.
+pub fn render<T: Serialize>(
+    format: OutputFormat,
+    value: &T,
+    text: impl FnOnce(&T) -> String,
+) -> anyhow::Result<String> {
+    match format {
+        OutputFormat::Text => Ok(text(value)),
+        format => structured(format, value),
+    }
+}
+
+/// Render the result of a command that produces no output of its own.
+pub fn render_success(format: OutputFormat, message: &str) -> anyhow::Result<String> {
+    render(format, &serde_json::json!({ "success": true }), |_| {
+        message.to_string()
+    })
+}
+
+/// Render a command result in one of the machine-readable formats. The text
+/// format falls back to pretty JSON.
+pub fn structured<T: Serialize>(format: OutputFormat, value: &T) -> anyhow::Result<String> {
+    let value = serde_json::to_value(value)?;
+    match format {
+        OutputFormat::Text | OutputFormat::Json => Ok(serde_json::to_string_pretty(&value)?),
+        OutputFormat::Jsonl => Ok(rows(&value)
+            .iter()
+            .map(|row| row.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")),
+        OutputFormat::Yaml => Ok(serde_yaml::to_string(&value)?.trim_end().to_string()),
+        OutputFormat::Table => Ok(format_table(&value)),
+    }
+}
+
+/// Write one value to stdout as a JSONL line and flush it, so results can be
+/// consumed while a long listing is still being fetched. Returns `false`
+/// once stdout has been closed, e.g. by `| head`.
+pub fn print_jsonl<T: Serialize>(value: &T) -> anyhow::Result<bool> {
+    let mut stdout = std::io::stdout().lock();
+    let line = serde_json::to_string(value)?;
+    match writeln!(stdout, "{line}").and_then(|_| stdout.flush()) {
+        Ok(()) => Ok(true),
+        Err(error) if error.kind() == std::io::ErrorKind::BrokenPipe => Ok(false),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// The items a result lists: the elements of an array, or of the single
+/// array of objects in a page-like object such as `{"records": [...],
+/// "cursor": "..."}`. Anything else is a single item.
+fn rows(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(items) => items.iter().collect(),
+        Value::Object(fields) => {
+            let (lists, rest): (Vec<_>, Vec<_>) = fields.values().partition(|v| v.is_array());
+            match lists.as_slice() {
+                [Value::Array(items)]
+                    if items.iter().all(Value::is_object)
+                        && rest.iter().all(|v| !v.is_object()) =>
+                {
+                    items.iter().collect()
+                }
+                _ => vec![value],
+            }
+        }
+        _ => vec![value],
+    }
+}
+
+fn format_table(value: &Value) -> String {
+    let rows = rows(value);
+
+    let (header, cells): (Vec<String>, Vec<Vec<String>>) = match rows.as_slice() {
+        // A single object reads best as a two-column key/value table
+        [Value::Object(fields)] => (
+            vec!["KEY".to_string(), "VALUE".to_string()],
+            fields
+                .iter()
+                .map(|(key, value)| vec![key.clone(), cell(value)])
+                .collect(),
+        ),
+        rows if rows.iter().all(|row| row.is_object()) => {
+            let mut columns: Vec<String> = Vec::new();
+            for row in rows {
+                for key in row.as_object().unwrap().keys() {
+                    if !columns.contains(key) {
+                        columns.push(key.clone());
+                    }
+                }
+            }
+            let cells = rows
+                .iter()
+                .map(|row| columns.iter().map(|column| cell(&row[column])).collect())
+                .collect();
+            (
+                columns.iter().map(|column| column.to_uppercase()).collect(),
+                cells,
+            )
+        }
+        rows => (
+            vec!["VALUE".to_string()],
+            rows.iter().map(|row| vec![cell(row)]).collect(),
+        ),
+    };
+
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    std::iter::once(&header)
+        .chain(&cells)
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
 async fn download_image(url: &str) -> anyhow::Result<Vec<u8>> {
     let response = reqwest::get(url).await?;
     Ok(response.bytes().await?.to_vec())
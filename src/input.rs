@@ -0,0 +1,18 @@
+use anyhow::Context;
+use tokio::io::AsyncReadExt;
+
+/// Read a command-line value that may name its source: `@path` reads the
+/// file at `path`, `-` reads stdin, and anything else is used as given.
+pub async fn read_arg(arg: &str) -> anyhow::Result<String> {
+    if arg == "-" {
+        let mut input = String::new();
+        tokio::io::stdin().read_to_string(&mut input).await?;
+        Ok(input)
+    } else if let Some(path) = arg.strip_prefix('@') {
+        tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Failed to read {}", path))
+    } else {
+        Ok(arg.to_string())
+    }
+}
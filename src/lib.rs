@@ -1,9 +1,26 @@
+pub mod account;
 pub mod atproto;
 pub mod auth;
+pub mod backup;
 pub mod bsky;
+pub mod car;
+pub mod cbor;
+pub mod cid;
+pub mod crypto;
+pub mod firehose;
 pub mod format;
+pub mod input;
+pub mod mst;
+pub mod plc;
+pub mod resolve;
+pub mod syntax;
+pub mod xrpc;
 
-use std::fmt::Display;
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    sync::{Arc, Mutex},
+};
 
 use anyhow::Ok;
 use async_trait::async_trait;
@@ -12,30 +29,115 @@ use directories::BaseDirs;
 use serde::{Deserialize, Serialize};
 use tokio::fs::read_to_string;
 
-use crate::auth::LoginResponse;
+use crate::{auth::LoginResponse, format::OutputFormat};
 
-const BASE_URL: &str = "https://bsky.social/xrpc";
+const DEFAULT_SERVICE: &str = "https://bsky.social";
 
-#[derive(Default)]
+#[derive(Clone)]
 pub struct Client {
     client: reqwest::Client,
+    service: String,
+    /// PLC directory used to resolve did:plc identities
+    plc_directory: String,
+    /// Session tokens refreshed during this run
+    refreshed: Arc<Mutex<Option<LoginResponse>>>,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Client {
     pub fn new() -> Self {
+        Self::with_service(DEFAULT_SERVICE)
+    }
+
+    /// Create a client that sends XRPC requests to the given service host.
+    pub fn with_service(service: impl Into<String>) -> Self {
         Self {
             client: reqwest::Client::new(),
+            service: service.into().trim_end_matches('/').to_string(),
+            plc_directory: plc::DEFAULT_DIRECTORY.to_string(),
+            refreshed: Arc::default(),
         }
     }
 
+    /// Resolve did:plc identities through the given PLC directory.
+    pub fn with_plc_directory(mut self, directory: impl Into<String>) -> Self {
+        self.plc_directory = directory.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Create a client routed to the active account's PDS, if any.
+    pub fn for_config(config: &Config) -> Self {
+        let client = match config.account() {
+            Some(account) => Self::with_service(account.pds.as_str()),
+            None => Self::new(),
+        };
+        client.with_plc_directory(config.plc_directory())
+    }
+
+    /// Create a client routed to the PDS hosting the given handle or DID.
+    pub async fn for_repo(&self, repo: &str) -> anyhow::Result<Self> {
+        let (_, pds) = resolve::resolve_pds(self, repo).await?;
+        Ok(Self {
+            client: self.client.clone(),
+            service: pds,
+            plc_directory: self.plc_directory.clone(),
+            // Same account, so tokens refreshed by either client stay shared
+            refreshed: self.refreshed.clone(),
+        })
+    }
+
     pub fn inner(&self) -> &reqwest::Client {
         &self.client
     }
+
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    pub fn plc_directory(&self) -> &str {
+        &self.plc_directory
+    }
+
+    /// Build the URL for an XRPC method on this client's service.
+    pub fn xrpc(&self, nsid: &str) -> String {
+        format!("{}/xrpc/{}", self.service, nsid)
+    }
+}
+
+/// A named account profile with its own session and PDS.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Account {
+    /// PDS endpoint resolved from the account's DID document
+    pub pds: String,
+    #[serde(flatten)]
+    pub session: LoginResponse,
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Config {
-    pub session: Option<LoginResponse>,
+    /// Name of the account used when `--account` isn't given
+    pub current: Option<String>,
+    #[serde(default)]
+    pub accounts: BTreeMap<String, Account>,
+    /// Account chosen with `--account` for this invocation only
+    #[serde(skip)]
+    selected: Option<String>,
+    /// Output format chosen with `--output` for this invocation
+    #[serde(skip)]
+    pub output: OutputFormat,
+    /// PLC directory for did:plc resolution, instead of plc.directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plc_directory: Option<String>,
+    // Single-session layout written by earlier versions; migrated on load.
+    #[serde(default, skip_serializing)]
+    pds: Option<String>,
+    #[serde(default, skip_serializing)]
+    session: Option<LoginResponse>,
 }
 
 impl Config {
@@ -59,15 +161,142 @@ impl Config {
     pub async fn load(base_dirs: &BaseDirs) -> anyhow::Result<Self> {
         let file = base_dirs.config_local_dir().join("atp").join("config.toml");
         let file = read_to_string(file).await?;
-        Ok(toml::from_str(&file)?)
+        let mut config: Self = toml::from_str(&file)?;
+
+        if let Some(session) = config.session.take() {
+            let name = session.handle.clone();
+            let pds = config
+                .pds
+                .take()
+                .unwrap_or_else(|| DEFAULT_SERVICE.to_string());
+            config
+                .accounts
+                .entry(name.clone())
+                .or_insert(Account { pds, session });
+            config.current.get_or_insert(name);
+        }
+
+        Ok(config)
+    }
+
+    /// Use the named account for this invocation without changing the default.
+    pub fn select(&mut self, name: &str) -> anyhow::Result<()> {
+        if !self.accounts.contains_key(name) {
+            anyhow::bail!("No account named '{}' (see `atp auth list`)", name);
+        }
+        self.selected = Some(name.to_string());
+        Ok(())
     }
+
+    /// Name of the account commands run as.
+    pub fn account_name(&self) -> Option<&str> {
+        self.selected.as_deref().or(self.current.as_deref())
+    }
+
+    /// PLC directory to resolve did:plc identities through.
+    pub fn plc_directory(&self) -> &str {
+        self.plc_directory
+            .as_deref()
+            .unwrap_or(plc::DEFAULT_DIRECTORY)
+    }
+
+    pub fn account(&self) -> Option<&Account> {
+        self.accounts.get(self.account_name()?)
+    }
+
+    pub fn session(&self) -> Option<&LoginResponse> {
+        self.account().map(|account| &account.session)
+    }
+
+    /// Replace the tokens of the active account, e.g. after a refresh.
+    pub fn set_session(&mut self, session: LoginResponse) {
+        let Some(name) = self.account_name().map(str::to_string) else {
+            return;
+        };
+        if let Some(account) = self.accounts.get_mut(&name) {
+            account.session = session;
+        }
+    }
+
+    /// Store an account under `name` and make it the default.
+    pub fn add_account(&mut self, name: String, account: Account) {
+        self.accounts.insert(name.clone(), account);
+        self.current = Some(name);
+    }
+
+    /// Make the named account the default for future invocations.
+    pub fn use_account(&mut self, name: &str) -> anyhow::Result<()> {
+        if !self.accounts.contains_key(name) {
+            anyhow::bail!("No account named '{}' (see `atp auth list`)", name);
+        }
+        self.current = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Describe the stored accounts without their tokens.
+    pub fn summaries(&self) -> Vec<AccountSummary> {
+        let active = self.account_name();
+        self.accounts
+            .iter()
+            .map(|(name, account)| AccountSummary {
+                name: name.clone(),
+                active: Some(name.as_str()) == active,
+                did: account.session.did.clone(),
+                handle: account.session.handle.clone(),
+                email: account.session.email.clone(),
+                pds: account.pds.clone(),
+            })
+            .collect()
+    }
+
+    /// Describe the active account without its tokens.
+    pub fn summary(&self) -> Option<AccountSummary> {
+        self.summaries().into_iter().find(|summary| summary.active)
+    }
+
+    /// Render the stored accounts, marking the active one.
+    pub fn list_accounts(&self) -> String {
+        if self.accounts.is_empty() {
+            return "No accounts".to_string();
+        }
+        let active = self.account_name();
+        self.accounts
+            .iter()
+            .map(|(name, account)| {
+                let marker = if Some(name.as_str()) == active {
+                    "*"
+                } else {
+                    " "
+                };
+                format!(
+                    "{} {} ({}, {}) {}",
+                    marker, name, account.session.handle, account.session.did, account.pds
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// An account as shown by `atp auth list` and `atp auth session`.
+#[derive(Debug, Serialize)]
+pub struct AccountSummary {
+    pub name: String,
+    pub active: bool,
+    pub did: String,
+    pub handle: String,
+    pub email: Option<String>,
+    pub pds: String,
 }
 
 impl Display for Config {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if let Some(session) = &self.session {
+        if let (Some(name), Some(account)) = (self.account_name(), self.account()) {
+            let session = &account.session;
+            writeln!(f, "account: {}", name)?;
             writeln!(f, "did: {}", session.did)?;
             writeln!(f, "handle: {}", session.handle)?;
+            writeln!(f, "pds: {}", account.pds)?;
             if let Some(email) = &session.email {
                 writeln!(f, "email: {}", email)?;
             }
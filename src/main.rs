@@ -1,52 +1,178 @@
-use atp::{Client, Config, Process, atproto::Atproto, auth::Auth, bsky::actor::Bsky};
+use atp::{
+    Client, Config, Process,
+    account::Account,
+    atproto::Atproto,
+    auth::Auth,
+    backup::Backup,
+    bsky::actor::Bsky,
+    car::Car,
+    format::{self, OutputFormat},
+    syntax::Syntax,
+    xrpc,
+};
 use clap::Parser;
 use directories::BaseDirs;
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() {
     let opts: Options = Options::parse();
+    if let Err(error) = run(opts).await {
+        eprintln!("Error: {error:?}");
+        std::process::exit(xrpc::exit_code(&error));
+    }
+}
+
+async fn run(opts: Options) -> anyhow::Result<()> {
     let base_dirs = BaseDirs::new().expect("Unable to find home directory");
     let client = Client::new();
 
-    match opts {
-        Options::Auth(Auth::Login(cmd)) => {
-            let response = cmd.process(&client).await?;
-            let config = Config {
-                session: Some(response),
-            };
+    match opts.command {
+        Command::Auth(Auth::Login(cmd)) => {
+            let mut config = Config::load(&base_dirs).await.unwrap_or_default();
+            let directory = opts.plc_directory.as_deref();
+            let client = client.with_plc_directory(directory.unwrap_or(config.plc_directory()));
+            let account = cmd.process(&client).await?;
+            let name = cmd.account_name(&account, opts.account);
+            config.add_account(name.clone(), account);
             config.write(&base_dirs).await?;
-            println!("Login successful");
+            let output = format::render(opts.output, &config.summary(), |_| {
+                format!("Login successful (account: {name})")
+            })?;
+            println!("{output}");
+        }
+        Command::Auth(Auth::Session) => {
+            let config = load_config(&base_dirs, &opts).await?;
+            let output = format::render(opts.output, &config.summary(), |_| config.to_string())?;
+            println!("{output}");
         }
-        Options::Auth(Auth::Session) => {
-            let config = Config::load(&base_dirs).await?;
-            println!("{config}");
+        Command::Auth(Auth::List) => {
+            let config = load_config(&base_dirs, &opts).await?;
+            let output =
+                format::render(opts.output, &config.summaries(), |_| config.list_accounts())?;
+            println!("{output}");
         }
-        Options::Bsky(cmd) => {
-            let config = Config::load(&base_dirs).await?;
+        Command::Auth(Auth::Use(cmd)) => {
+            let mut config = Config::load(&base_dirs).await?;
+            config.use_account(&cmd.name)?;
+            config.write(&base_dirs).await?;
+            let output =
+                format::render_success(opts.output, &format!("Now using account: {}", cmd.name))?;
+            println!("{output}");
+        }
+        Command::Bsky(ref cmd) => {
+            let config = load_config(&base_dirs, &opts).await?;
+            let client = Client::for_config(&config);
             let response = cmd.process(&client, &config).await?;
             println!("{response}");
         }
-        Options::Atproto(cmd) => {
+        Command::Atproto(ref cmd) => {
             let needs_auth = cmd.needs_authentication();
             let config = if needs_auth {
-                Config::load(&base_dirs).await?
+                load_config(&base_dirs, &opts).await?
             } else {
-                Config::default()
+                load_optional_config(&base_dirs, &opts).await?
             };
+            let client = Client::for_config(&config);
+            let response = cmd.process(&client, &config).await?;
+            // Streaming commands print as they go and return nothing
+            if !response.is_empty() {
+                println!("{response}");
+            }
+        }
+        Command::Account(ref cmd) => {
+            let config = load_config(&base_dirs, &opts).await?;
+            let client = Client::for_config(&config);
+            let response = cmd.process(&client, &config).await?;
+            println!("{response}");
+        }
+        Command::Backup(ref cmd) => {
+            let config = load_config(&base_dirs, &opts).await?;
+            let client = Client::for_config(&config);
+            let response = cmd.process(&client, &config).await?;
+            println!("{response}");
+        }
+        Command::Car(ref cmd) => {
+            let config = load_optional_config(&base_dirs, &opts).await?;
+            let response = cmd.process(&client, &config).await?;
+            println!("{response}");
+        }
+        Command::Syntax(ref cmd) => {
+            let config = load_optional_config(&base_dirs, &opts).await?;
             let response = cmd.process(&client, &config).await?;
             println!("{response}");
         }
+        Command::Xrpc(ref cmd) => {
+            let config = load_optional_config(&base_dirs, &opts).await?;
+            let client = Client::for_config(&config);
+            let response = cmd.process(&client, &config).await?;
+            if !response.is_empty() {
+                println!("{response}");
+            }
+        }
     }
     Ok(())
 }
 
+/// Load the config and apply the global options: the `--account` profile,
+/// the `--output` format and the `--plc-directory`.
+async fn load_config(base_dirs: &BaseDirs, opts: &Options) -> anyhow::Result<Config> {
+    let mut config = Config::load(base_dirs).await?;
+    if let Some(name) = &opts.account {
+        config.select(name)?;
+    }
+    config.output = opts.output;
+    if let Some(directory) = &opts.plc_directory {
+        config.plc_directory = Some(directory.clone());
+    }
+    Ok(config)
+}
+
+/// Like [`load_config`], but for commands that also work without a stored
+/// session. A missing config is only an error if `--account` was given.
+async fn load_optional_config(base_dirs: &BaseDirs, opts: &Options) -> anyhow::Result<Config> {
+    if opts.account.is_some() {
+        return load_config(base_dirs, opts).await;
+    }
+    let mut config = Config::load(base_dirs).await.unwrap_or_default();
+    config.output = opts.output;
+    if let Some(directory) = &opts.plc_directory {
+        config.plc_directory = Some(directory.clone());
+    }
+    Ok(config)
+}
+
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
-enum Options {
+struct Options {
+    /// Account to run the command as, instead of the current default
+    #[arg(long, global = true)]
+    account: Option<String>,
+    /// Output format for command results
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+    /// PLC directory to resolve did:plc identities through, instead of the
+    /// configured one (default https://plc.directory)
+    #[arg(long, global = true)]
+    plc_directory: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Parser)]
+enum Command {
     #[command(subcommand)]
     Auth(Auth),
     #[command(subcommand)]
     Bsky(Bsky),
     #[command(subcommand)]
+    Account(Account),
+    #[command(subcommand)]
     Atproto(Atproto),
+    #[command(subcommand)]
+    Backup(Backup),
+    #[command(subcommand)]
+    Car(Car),
+    #[command(subcommand)]
+    Syntax(Syntax),
+    Xrpc(xrpc::Xrpc),
 }
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use sha2::{Digest, Sha256};
+
+use crate::{cbor, cid::Cid};
+
+/// Deeper trees than this are treated as malformed, which also stops nodes
+/// that link back to themselves. Real trees are a few dozen layers at most.
+const MAX_DEPTH: usize = 128;
+
+/// A decoded MST node, with entry keys expanded from their prefix-compressed
+/// form.
+struct Node {
+    left: Option<Cid>,
+    entries: Vec<Entry>,
+}
+
+struct Entry {
+    key: Vec<u8>,
+    value: Cid,
+    right: Option<Cid>,
+}
+
+/// What [`verify`] checked.
+#[derive(Debug, Default)]
+pub struct Stats {
+    pub nodes: usize,
+    pub records: usize,
+}
+
+/// Walk the Merkle Search Tree rooted at `root`, returning every
+/// `collection/rkey` key with the CID of its record, in key order.
+pub fn walk(blocks: &HashMap<Cid, Vec<u8>>, root: &Cid) -> anyhow::Result<Vec<(String, Cid)>> {
+    let mut entries = Vec::new();
+    visit(blocks, root, 0, &mut entries)?;
+    Ok(entries)
+}
+
+/// Every block the tree rooted at `root` refers to: its nodes and the
+/// records they point at, in tree order. Records missing from `blocks` are
+/// still listed; a missing node is an error.
+pub fn reachable(blocks: &HashMap<Cid, Vec<u8>>, root: &Cid) -> anyhow::Result<Vec<Cid>> {
+    let mut cids = Vec::new();
+    collect(blocks, root, 0, &mut cids)?;
+    Ok(cids)
+}
+
+fn collect(
+    blocks: &HashMap<Cid, Vec<u8>>,
+    cid: &Cid,
+    depth: usize,
+    cids: &mut Vec<Cid>,
+) -> anyhow::Result<()> {
+    check_depth(cid, depth)?;
+    let node = read_node(blocks, cid)?;
+    cids.push(cid.clone());
+    if let Some(left) = &node.left {
+        collect(blocks, left, depth + 1, cids)?;
+    }
+    for entry in node.entries {
+        cids.push(entry.value);
+        if let Some(right) = &entry.right {
+            collect(blocks, right, depth + 1, cids)?;
+        }
+    }
+    Ok(())
+}
+
+fn visit(
+    blocks: &HashMap<Cid, Vec<u8>>,
+    node: &Cid,
+    depth: usize,
+    entries: &mut Vec<(String, Cid)>,
+) -> anyhow::Result<()> {
+    check_depth(node, depth)?;
+    let node = read_node(blocks, node)?;
+    if let Some(left) = &node.left {
+        visit(blocks, left, depth + 1, entries)?;
+    }
+    for entry in node.entries {
+        let key = String::from_utf8(entry.key).context("MST key is not UTF-8")?;
+        entries.push((key, entry.value));
+        if let Some(right) = &entry.right {
+            visit(blocks, right, depth + 1, entries)?;
+        }
+    }
+    Ok(())
+}
+
+fn check_depth(cid: &Cid, depth: usize) -> anyhow::Result<()> {
+    if depth > MAX_DEPTH {
+        anyhow::bail!(
+            "MST is nested more than {} nodes deep at {}",
+            MAX_DEPTH,
+            cid
+        );
+    }
+    Ok(())
+}
+
+/// Check the whole tree rooted at `root`: every node is present and hashes
+/// to its CID, keys are in strictly increasing order, each key sits on the
+/// layer its hash puts it on, and records that are present hash to their
+/// CIDs.
+pub fn verify(blocks: &HashMap<Cid, Vec<u8>>, root: &Cid) -> anyhow::Result<Stats> {
+    let mut stats = Stats::default();
+    let mut last_key = None;
+    verify_node(blocks, root, None, 0, &mut last_key, &mut stats)?;
+    Ok(stats)
+}
+
+fn verify_node(
+    blocks: &HashMap<Cid, Vec<u8>>,
+    cid: &Cid,
+    parent_layer: Option<u32>,
+    depth: usize,
+    last_key: &mut Option<Vec<u8>>,
+    stats: &mut Stats,
+) -> anyhow::Result<()> {
+    check_depth(cid, depth)?;
+    verify_block(blocks, cid)?;
+    let node = read_node(blocks, cid)?;
+    stats.nodes += 1;
+
+    // All entries in a node share a layer, below the layer of its parent
+    let layer = match node.entries.first() {
+        Some(entry) => {
+            let layer = key_layer(&entry.key);
+            if parent_layer.is_some_and(|parent| layer >= parent) {
+                anyhow::bail!(
+                    "MST node {} is on layer {}, not below its parent",
+                    cid,
+                    layer
+                );
+            }
+            Some(layer)
+        }
+        None => parent_layer.map(|parent| parent.saturating_sub(1)),
+    };
+    let child_layer = layer.or(parent_layer);
+
+    if let Some(left) = &node.left {
+        verify_node(blocks, left, child_layer, depth + 1, last_key, stats)?;
+    }
+    for entry in &node.entries {
+        if Some(key_layer(&entry.key)) != layer {
+            anyhow::bail!(
+                "MST key {} is on the wrong layer in node {}",
+                String::from_utf8_lossy(&entry.key),
+                cid
+            );
+        }
+        if last_key.as_ref().is_some_and(|last| *last >= entry.key) {
+            anyhow::bail!(
+                "MST key {} is out of order in node {}",
+                String::from_utf8_lossy(&entry.key),
+                cid
+            );
+        }
+        *last_key = Some(entry.key.clone());
+
+        if blocks.contains_key(&entry.value) {
+            verify_block(blocks, &entry.value)?;
+        }
+        stats.records += 1;
+
+        if let Some(right) = &entry.right {
+            verify_node(blocks, right, child_layer, depth + 1, last_key, stats)?;
+        }
+    }
+    Ok(())
+}
+
+/// Check that a block's bytes hash to its CID.
+pub fn verify_block(blocks: &HashMap<Cid, Vec<u8>>, cid: &Cid) -> anyhow::Result<()> {
+    let block = blocks
+        .get(cid)
+        .with_context(|| format!("Block {} is missing", cid))?;
+    if cid.hash_code != crate::cid::SHA2_256 {
+        anyhow::bail!("Block {} uses an unsupported hash", cid);
+    }
+    if Cid::for_block(cid.codec, block) != *cid {
+        anyhow::bail!("Block {} does not match its hash", cid);
+    }
+    Ok(())
+}
+
+/// The MST layer of a key: the number of leading pairs of zero bits in its
+/// SHA-256 hash, giving a fanout of 4.
+fn key_layer(key: &[u8]) -> u32 {
+    let mut zeros = 0;
+    for byte in Sha256::digest(key) {
+        zeros += byte.leading_zeros();
+        if byte != 0 {
+            break;
+        }
+    }
+    zeros / 2
+}
+
+fn read_node(blocks: &HashMap<Cid, Vec<u8>>, cid: &Cid) -> anyhow::Result<Node> {
+    let block = blocks
+        .get(cid)
+        .with_context(|| format!("MST node {} is missing", cid))?;
+    let node = cbor::decode(block).with_context(|| format!("Invalid MST node {}", cid))?;
+
+    // Each entry's key shares a prefix of `p` bytes with the one before it
+    let mut key: Vec<u8> = Vec::new();
+    let mut entries = Vec::new();
+    for entry in node
+        .get("e")
+        .and_then(cbor::Value::as_array)
+        .with_context(|| format!("MST node {} has no entries", cid))?
+    {
+        let prefix = entry
+            .get("p")
+            .and_then(cbor::Value::as_integer)
+            .with_context(|| format!("MST entry in {} has no prefix length", cid))?;
+        let suffix = entry
+            .get("k")
+            .and_then(cbor::Value::as_bytes)
+            .with_context(|| format!("MST entry in {} has no key", cid))?;
+        let value = entry
+            .get("v")
+            .and_then(cbor::Value::as_link)
+            .with_context(|| format!("MST entry in {} has no value", cid))?;
+
+        let prefix = usize::try_from(prefix).unwrap_or(usize::MAX);
+        if prefix > key.len() {
+            anyhow::bail!("MST entry in {} has an invalid prefix length", cid);
+        }
+        key.truncate(prefix);
+        key.extend_from_slice(suffix);
+        entries.push(Entry {
+            key: key.clone(),
+            value: value.clone(),
+            right: entry.get("t").and_then(cbor::Value::as_link).cloned(),
+        });
+    }
+
+    Ok(Node {
+        left: node.get("l").and_then(cbor::Value::as_link).cloned(),
+        entries,
+    })
+}
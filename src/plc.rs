@@ -0,0 +1,197 @@
+use anyhow::Context;
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    Client, cbor,
+    cid::{self, Cid},
+    crypto::PublicKey,
+};
+
+pub const DEFAULT_DIRECTORY: &str = "https://plc.directory";
+
+/// An entry in a PLC directory audit log.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LogEntry {
+    pub did: String,
+    pub operation: serde_json::Value,
+    pub cid: String,
+    #[serde(default)]
+    pub nullified: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+/// The identity an operation sets, read from either the current
+/// `plc_operation` format or the legacy `create` format.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct State {
+    #[serde(rename = "rotationKeys")]
+    pub rotation_keys: Vec<String>,
+    #[serde(rename = "alsoKnownAs")]
+    pub also_known_as: Vec<String>,
+    #[serde(rename = "signingKey")]
+    pub signing_key: Option<String>,
+    pub pds: Option<String>,
+}
+
+impl State {
+    /// The state after `operation`, or `None` for a tombstone.
+    pub fn from_operation(operation: &serde_json::Value) -> anyhow::Result<Option<Self>> {
+        let strings = |value: &serde_json::Value| -> Vec<String> {
+            value
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|item| item.as_str().map(str::to_string))
+                .collect()
+        };
+        let string = |value: &serde_json::Value| value.as_str().map(str::to_string);
+        match operation["type"].as_str() {
+            Some("plc_operation") => Ok(Some(Self {
+                rotation_keys: strings(&operation["rotationKeys"]),
+                also_known_as: strings(&operation["alsoKnownAs"]),
+                signing_key: string(&operation["verificationMethods"]["atproto"]),
+                pds: string(&operation["services"]["atproto_pds"]["endpoint"]),
+            })),
+            Some("create") => Ok(Some(Self {
+                rotation_keys: [&operation["recoveryKey"], &operation["signingKey"]]
+                    .into_iter()
+                    .filter_map(string)
+                    .collect(),
+                also_known_as: string(&operation["handle"])
+                    .map(|handle| format!("at://{}", handle))
+                    .into_iter()
+                    .collect(),
+                signing_key: string(&operation["signingKey"]),
+                pds: string(&operation["service"]),
+            })),
+            Some("plc_tombstone") => Ok(None),
+            other => anyhow::bail!("Unknown PLC operation type {:?}", other),
+        }
+    }
+}
+
+/// Fetch the audit log for `did` from the client's PLC directory, oldest
+/// operation first, including operations later nullified by a recovery.
+pub async fn audit_log(client: &Client, did: &str) -> anyhow::Result<Vec<LogEntry>> {
+    let url = format!("{}/{}/log/audit", client.plc_directory(), did);
+    let response = client.inner().get(&url).send().await?;
+    if !response.status().is_success() {
+        anyhow::bail!("Failed to fetch PLC log for {}: {}", did, response.status());
+    }
+    response.json().await.context("Invalid PLC audit log")
+}
+
+/// The CID of a signed operation.
+pub fn operation_cid(operation: &serde_json::Value) -> anyhow::Result<Cid> {
+    let bytes = cbor::encode(&cbor::Value::from_json(operation)?);
+    Ok(Cid::for_block(cid::DAG_CBOR, &bytes))
+}
+
+/// The DID a signed genesis operation creates: the first 24 characters of
+/// the base32 SHA-256 of its DAG-CBOR encoding.
+pub fn genesis_did(operation: &serde_json::Value) -> anyhow::Result<String> {
+    let bytes = cbor::encode(&cbor::Value::from_json(operation)?);
+    let encoded = cid::base32_encode(&Sha256::digest(bytes));
+    Ok(format!("did:plc:{}", &encoded[..24]))
+}
+
+/// Check `operation`'s signature against each of `rotation_keys` in turn,
+/// returning the key that signed it.
+pub fn verify_signature(
+    operation: &serde_json::Value,
+    rotation_keys: &[String],
+) -> anyhow::Result<String> {
+    let sig = operation["sig"]
+        .as_str()
+        .context("Operation is not signed")?;
+    let sig = URL_SAFE_NO_PAD
+        .decode(sig.trim_end_matches('='))
+        .context("Invalid operation signature encoding")?;
+    let mut unsigned = operation.clone();
+    if let Some(fields) = unsigned.as_object_mut() {
+        fields.remove("sig");
+    }
+    let message = cbor::encode(&cbor::Value::from_json(&unsigned)?);
+
+    for did_key in rotation_keys {
+        let Ok(key) = PublicKey::from_did_key(did_key) else {
+            continue;
+        };
+        if key.verify(&message, &sig).is_ok() {
+            return Ok(did_key.clone());
+        }
+    }
+    anyhow::bail!("Not signed by any rotation key of the previous operation")
+}
+
+/// A log entry that has passed verification.
+#[derive(Clone, Debug)]
+pub struct VerifiedEntry {
+    pub entry: LogEntry,
+    /// Rotation key that signed the operation
+    pub signed_by: String,
+    /// Index of the operation this one follows, `None` for the genesis
+    pub prev: Option<usize>,
+    /// Identity after this operation, `None` for a tombstone
+    pub state: Option<State>,
+}
+
+/// Verify an audit log: each entry's CID matches its operation, the genesis
+/// operation hashes to the DID and is signed by one of its own rotation
+/// keys, and every later operation names an earlier one as `prev` and is
+/// signed by one of that operation's rotation keys.
+pub fn verify_log(did: &str, log: Vec<LogEntry>) -> anyhow::Result<Vec<VerifiedEntry>> {
+    let mut verified: Vec<VerifiedEntry> = Vec::with_capacity(log.len());
+    for entry in log {
+        let cid = &entry.cid;
+        let result = (|| {
+            if entry.did != did {
+                anyhow::bail!("Entry is for {}", entry.did);
+            }
+            if operation_cid(&entry.operation)?.to_string() != *cid {
+                anyhow::bail!("Operation does not match its CID");
+            }
+            let state = State::from_operation(&entry.operation)?;
+
+            let prev = match entry.operation["prev"].as_str() {
+                None if verified.is_empty() => None,
+                None => anyhow::bail!("Only the first operation may omit prev"),
+                Some(prev) => Some(
+                    verified
+                        .iter()
+                        .position(|earlier| earlier.entry.cid == prev)
+                        .with_context(|| format!("prev {} is not an earlier operation", prev))?,
+                ),
+            };
+            let rotation_keys = match prev {
+                Some(index) => match &verified[index].state {
+                    Some(state) => state.rotation_keys.clone(),
+                    None => anyhow::bail!("Follows a tombstone"),
+                },
+                None => {
+                    if genesis_did(&entry.operation)? != did {
+                        anyhow::bail!("Genesis operation does not hash to {}", did);
+                    }
+                    state
+                        .as_ref()
+                        .map(|state| state.rotation_keys.clone())
+                        .context("Genesis operation is a tombstone")?
+                }
+            };
+            let signed_by = verify_signature(&entry.operation, &rotation_keys)?;
+            Ok((signed_by, prev, state))
+        })();
+        let (signed_by, prev, state) =
+            result.with_context(|| format!("PLC operation {} failed verification", cid))?;
+        verified.push(VerifiedEntry {
+            entry,
+            signed_by,
+            prev,
+            state,
+        });
+    }
+    Ok(verified)
+}
@@ -0,0 +1,323 @@
+use std::{fmt, net::SocketAddr};
+
+use anyhow::Context;
+use hickory_resolver::{
+    Resolver,
+    config::{NameServerConfig, ResolverConfig},
+    name_server::TokioConnectionProvider,
+    proto::xfer::Protocol,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::Client;
+
+/// A DID document, keeping the fields atproto uses.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DidDocument {
+    #[serde(
+        rename = "@context",
+        default,
+        skip_serializing_if = "serde_json::Value::is_null"
+    )]
+    pub context: serde_json::Value,
+    pub id: String,
+    #[serde(rename = "alsoKnownAs", default)]
+    pub also_known_as: Vec<String>,
+    #[serde(rename = "verificationMethod", default)]
+    pub verification_method: Vec<VerificationMethod>,
+    #[serde(default)]
+    pub service: Vec<Service>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct VerificationMethod {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub controller: String,
+    #[serde(
+        rename = "publicKeyMultibase",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub public_key_multibase: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Service {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    /// Usually a URL, though the DID spec also allows maps and sets
+    #[serde(rename = "serviceEndpoint")]
+    pub service_endpoint: serde_json::Value,
+}
+
+impl DidDocument {
+    /// The handle claimed by the first `at://` alias. It is only verified
+    /// once the handle resolves back to this DID.
+    pub fn handle(&self) -> Option<&str> {
+        self.also_known_as
+            .iter()
+            .find_map(|alias| alias.strip_prefix("at://"))
+    }
+
+    /// Whether an `at://` alias names `handle`.
+    pub fn claims_handle(&self, handle: &str) -> bool {
+        self.also_known_as
+            .iter()
+            .filter_map(|alias| alias.strip_prefix("at://"))
+            .any(|alias| alias.eq_ignore_ascii_case(handle))
+    }
+
+    /// Find a verification method by fragment (e.g. `atproto`), whether its
+    /// id is relative (`#atproto`) or absolute (`did:...#atproto`).
+    pub fn verification_method(&self, fragment: &str) -> Option<&VerificationMethod> {
+        self.verification_method
+            .iter()
+            .find(|method| method.id.rsplit_once('#').map(|(_, id)| id) == Some(fragment))
+    }
+
+    /// The `#atproto_pds` service endpoint.
+    pub fn pds_endpoint(&self) -> Option<String> {
+        self.service.iter().find_map(|service| {
+            let is_pds = service.id.rsplit_once('#').map(|(_, id)| id) == Some("atproto_pds")
+                && service.type_ == "AtprotoPersonalDataServer";
+            if !is_pds {
+                return None;
+            }
+            service
+                .service_endpoint
+                .as_str()
+                .map(|endpoint| endpoint.trim_end_matches('/').to_string())
+        })
+    }
+}
+
+/// Where client-side handle resolution looks, overridable for testing
+/// against local servers.
+#[derive(clap::Args, Clone, Debug, Default)]
+pub struct HandleResolver {
+    /// DNS server (host:port) to look up `_atproto.<handle>` TXT records
+    /// with, instead of the system's resolvers
+    #[arg(long)]
+    pub dns_server: Option<SocketAddr>,
+    /// Base URL to fetch `/.well-known/atproto-did` from, instead of
+    /// `https://<handle>`
+    #[arg(long)]
+    pub http_base: Option<String>,
+}
+
+/// How a handle was resolved to its DID.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HandleMethod {
+    /// A `did=` TXT record on `_atproto.<handle>`
+    Dns,
+    /// The `/.well-known/atproto-did` file on the handle's host
+    Http,
+}
+
+impl fmt::Display for HandleMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HandleMethod::Dns => "DNS TXT record",
+            HandleMethod::Http => "HTTPS well-known file",
+        })
+    }
+}
+
+/// The outcome of resolving a handle on the client.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HandleResolution {
+    pub handle: String,
+    pub did: String,
+    pub method: HandleMethod,
+    /// Whether the DID document's `alsoKnownAs` claims the handle back
+    pub valid: bool,
+}
+
+impl HandleResolver {
+    /// Look up the `did=` value of the `_atproto.<handle>` TXT record.
+    pub async fn dns(&self, handle: &str) -> anyhow::Result<String> {
+        let resolver = match self.dns_server {
+            Some(server) => {
+                let config = ResolverConfig::from_parts(
+                    None,
+                    Vec::new(),
+                    vec![NameServerConfig::new(server, Protocol::Udp)],
+                );
+                Resolver::builder_with_config(config, TokioConnectionProvider::default()).build()
+            }
+            None => Resolver::builder_tokio()
+                .context("Failed to read the system DNS configuration")?
+                .build(),
+        };
+
+        let lookup = resolver
+            .txt_lookup(format!("_atproto.{}.", handle))
+            .await
+            .with_context(|| format!("No TXT record at _atproto.{}", handle))?;
+        let mut dids: Vec<String> = lookup
+            .iter()
+            .filter_map(|txt| {
+                let value: Vec<u8> = txt.txt_data().concat();
+                String::from_utf8(value)
+                    .ok()?
+                    .strip_prefix("did=")
+                    .map(|did| did.trim().to_string())
+            })
+            .collect();
+        dids.sort();
+        dids.dedup();
+        match dids.as_slice() {
+            [did] if did.starts_with("did:") => Ok(did.clone()),
+            [] => anyhow::bail!("No did= TXT record at _atproto.{}", handle),
+            [did] => anyhow::bail!("TXT record at _atproto.{} has invalid DID {}", handle, did),
+            _ => anyhow::bail!("Conflicting did= TXT records at _atproto.{}", handle),
+        }
+    }
+
+    /// Fetch the DID from `/.well-known/atproto-did` on the handle's host.
+    pub async fn http(&self, client: &Client, handle: &str) -> anyhow::Result<String> {
+        let base = match &self.http_base {
+            Some(base) => base.trim_end_matches('/').to_string(),
+            None => format!("https://{}", handle),
+        };
+        let url = format!("{}/.well-known/atproto-did", base);
+        let response = client
+            .inner()
+            .get(&url)
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch {}", url))?;
+        if !response.status().is_success() {
+            anyhow::bail!("{} returned {}", url, response.status());
+        }
+        let did = response.text().await?.trim().to_string();
+        if !did.starts_with("did:") || did.contains(char::is_whitespace) {
+            anyhow::bail!("{} does not contain a DID", url);
+        }
+        Ok(did)
+    }
+
+    /// Look a handle up through DNS, falling back to HTTPS, without checking
+    /// the DID it points at.
+    async fn lookup(
+        &self,
+        client: &Client,
+        handle: &str,
+    ) -> anyhow::Result<(String, HandleMethod)> {
+        match self.dns(handle).await {
+            Ok(did) => Ok((did, HandleMethod::Dns)),
+            Err(dns_error) => match self.http(client, handle).await {
+                Ok(did) => Ok((did, HandleMethod::Http)),
+                Err(http_error) => anyhow::bail!(
+                    "Failed to resolve handle {}\n  DNS: {:#}\n  HTTPS: {:#}",
+                    handle,
+                    dns_error,
+                    http_error
+                ),
+            },
+        }
+    }
+
+    /// Resolve a handle through DNS, falling back to HTTPS, then check that
+    /// the DID document names the handle in `alsoKnownAs`.
+    pub async fn resolve(&self, client: &Client, handle: &str) -> anyhow::Result<HandleResolution> {
+        let handle = handle.trim_start_matches('@').to_ascii_lowercase();
+        let (did, method) = self.lookup(client, &handle).await?;
+        let did_doc = resolve_did_document(client, &did).await?;
+        Ok(HandleResolution {
+            valid: did_doc.claims_handle(&handle),
+            handle,
+            did,
+            method,
+        })
+    }
+
+    /// Resolve a handle to its DID and DID document, failing unless the
+    /// document claims the handle back.
+    pub async fn verified(
+        &self,
+        client: &Client,
+        handle: &str,
+    ) -> anyhow::Result<(String, DidDocument)> {
+        let handle = handle.trim_start_matches('@').to_ascii_lowercase();
+        let (did, _) = self.lookup(client, &handle).await?;
+        let did_doc = resolve_did_document(client, &did).await?;
+        if !did_doc.claims_handle(&handle) {
+            anyhow::bail!(
+                "Handle {} resolves to {}, but its DID document does not list at://{}",
+                handle,
+                did,
+                handle
+            );
+        }
+        Ok((did, did_doc))
+    }
+}
+
+/// The URL a DID document is served from: the client's PLC directory for
+/// `did:plc`, or `/.well-known/did.json` on the host for `did:web`.
+pub fn did_document_url(client: &Client, did: &str) -> anyhow::Result<String> {
+    if did.starts_with("did:plc:") {
+        Ok(format!("{}/{}", client.plc_directory(), did))
+    } else if let Some(host) = did.strip_prefix("did:web:") {
+        // atproto only uses hostname did:webs, without the `:path` form
+        if host.is_empty() || host.contains(':') {
+            anyhow::bail!("Unsupported did:web {}: only hostnames are allowed", did);
+        }
+        // A port is percent-encoded; local hosts are served over plain HTTP
+        let host = host.replace("%3A", ":");
+        let scheme = if host.starts_with("localhost:") || host.starts_with("127.0.0.1:") {
+            "http"
+        } else {
+            "https"
+        };
+        Ok(format!("{}://{}/.well-known/did.json", scheme, host))
+    } else {
+        anyhow::bail!("Unsupported DID method: {}", did)
+    }
+}
+
+/// Fetch and parse the DID document for a `did:plc` or `did:web`
+/// identifier, without going through a PDS.
+pub async fn resolve_did_document(client: &Client, did: &str) -> anyhow::Result<DidDocument> {
+    let url = did_document_url(client, did)?;
+    let response = client.inner().get(&url).send().await?;
+    if !response.status().is_success() {
+        anyhow::bail!("Failed to resolve DID {}: {}", did, response.status());
+    }
+
+    let document: DidDocument = response
+        .json()
+        .await
+        .with_context(|| format!("Invalid DID document for {}", did))?;
+    if document.id != did {
+        anyhow::bail!("DID document for {} is for {}", did, document.id);
+    }
+    Ok(document)
+}
+
+/// Resolve a handle or DID to the DID and the PDS hosting its repository.
+/// Handles are resolved on the client and must be claimed back by their DID
+/// document.
+pub async fn resolve_pds(client: &Client, identifier: &str) -> anyhow::Result<(String, String)> {
+    let identifier = identifier.trim_start_matches('@');
+    let (did, did_doc) = if identifier.starts_with("did:") {
+        (
+            identifier.to_string(),
+            resolve_did_document(client, identifier).await?,
+        )
+    } else {
+        HandleResolver::default()
+            .verified(client, identifier)
+            .await?
+    };
+
+    let pds = did_doc
+        .pds_endpoint()
+        .ok_or_else(|| anyhow::anyhow!("DID document for {} has no #atproto_pds service", did))?;
+    Ok((did, pds))
+}
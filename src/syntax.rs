@@ -0,0 +1,509 @@
+use std::{fmt, str::FromStr};
+
+use async_trait::async_trait;
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Deserializer, Serialize};
+
+pub use crate::cid::Cid;
+use crate::{Client, Config, Process, format};
+
+/// Characters a TID is written in, in sort order.
+const TID_ALPHABET: &str = "234567abcdefghijklmnopqrstuvwxyz";
+
+/// Check atproto identifiers offline
+#[derive(Parser)]
+pub enum Syntax {
+    /// Check whether a value is a valid identifier, and explain why not
+    Check(Check),
+}
+
+#[derive(Parser)]
+pub struct Check {
+    /// Identifier to check (DID, handle, NSID, record key, TID, CID or AT-URI)
+    pub value: String,
+    /// Only check against this kind of identifier
+    #[arg(long, value_enum)]
+    pub kind: Option<Kind>,
+}
+
+/// The identifier formats atproto defines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    Did,
+    Handle,
+    Nsid,
+    RecordKey,
+    Tid,
+    Cid,
+    AtUri,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Kind::Did => "DID",
+            Kind::Handle => "handle",
+            Kind::Nsid => "NSID",
+            Kind::RecordKey => "record key",
+            Kind::Tid => "TID",
+            Kind::Cid => "CID",
+            Kind::AtUri => "AT-URI",
+        })
+    }
+}
+
+impl Kind {
+    /// Why `value` isn't an identifier of this kind, if it isn't.
+    pub fn check(self, value: &str) -> Result<(), String> {
+        match self {
+            Kind::Did => check_did(value),
+            Kind::Handle => check_handle(value.strip_prefix('@').unwrap_or(value)),
+            Kind::Nsid => check_nsid(value),
+            Kind::RecordKey => check_record_key(value),
+            Kind::Tid => check_tid(value),
+            Kind::Cid => value.parse::<Cid>().map(|_| ()).map_err(reason),
+            Kind::AtUri => value.parse::<AtUri>().map(|_| ()).map_err(reason),
+        }
+    }
+}
+
+/// The reason in a parse error, without its "Invalid <kind> <value>: " prefix.
+fn reason(error: anyhow::Error) -> String {
+    let message = error.to_string();
+    match message.split_once(": ") {
+        Some((_, reason)) => reason.to_string(),
+        None => message,
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckResponse {
+    pub value: String,
+    /// Every kind of identifier the value is valid as
+    pub kinds: Vec<Kind>,
+}
+
+#[async_trait]
+impl Process for Syntax {
+    type Output = String;
+
+    async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
+        match self {
+            Syntax::Check(cmd) => {
+                let response = cmd.process(client, config).await?;
+                format::render(config.output, &response, |response| {
+                    let kinds: Vec<_> = response.kinds.iter().map(Kind::to_string).collect();
+                    format!("{} is a valid {}", response.value, kinds.join(", "))
+                })
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Process for Check {
+    type Output = CheckResponse;
+
+    async fn process(&self, _client: &Client, _config: &Config) -> anyhow::Result<Self::Output> {
+        let value = &self.value;
+        if let Some(kind) = self.kind {
+            kind.check(value).map_err(|reason| {
+                anyhow::anyhow!("{} is not a valid {}: {}", value, kind, reason)
+            })?;
+            return Ok(CheckResponse {
+                value: value.clone(),
+                kinds: vec![kind],
+            });
+        }
+
+        // The prefixed formats are unambiguous; anything else could be one
+        // of several, and a record key allows almost anything
+        let candidates: &[Kind] = if value.starts_with("at://") {
+            &[Kind::AtUri]
+        } else if value.starts_with("did:") {
+            &[Kind::Did]
+        } else {
+            &[
+                Kind::Handle,
+                Kind::Nsid,
+                Kind::Tid,
+                Kind::Cid,
+                Kind::RecordKey,
+            ]
+        };
+        let mut kinds = Vec::new();
+        let mut reasons = Vec::new();
+        for &kind in candidates {
+            match kind.check(value) {
+                Ok(()) => kinds.push(kind),
+                Err(reason) => reasons.push(format!("  {}: {}", kind, reason)),
+            }
+        }
+        if kinds.is_empty() {
+            anyhow::bail!(
+                "{} is not a valid identifier:\n{}",
+                value,
+                reasons.join("\n")
+            );
+        }
+        Ok(CheckResponse {
+            value: value.clone(),
+            kinds,
+        })
+    }
+}
+
+fn check_did(value: &str) -> Result<(), String> {
+    let rest = value.strip_prefix("did:").ok_or("must start with did:")?;
+    let (method, id) = rest
+        .split_once(':')
+        .ok_or("must look like did:<method>:<identifier>")?;
+    if method.is_empty() || !method.bytes().all(|b| b.is_ascii_lowercase()) {
+        return Err(format!(
+            "method '{}' must be one or more lowercase letters",
+            method
+        ));
+    }
+    if id.is_empty() {
+        return Err("identifier after the method is empty".to_string());
+    }
+    if let Some(c) = id
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || "._:%-".contains(*c)))
+    {
+        return Err(format!("identifier contains '{}'", c));
+    }
+    if id.ends_with(':') || id.ends_with('%') {
+        return Err("must not end with ':' or '%'".to_string());
+    }
+    if value.len() > 2048 {
+        return Err("longer than 2048 characters".to_string());
+    }
+    Ok(())
+}
+
+/// Check the dot-separated hostname labels shared by handles and NSIDs.
+fn check_labels<'a>(labels: impl Iterator<Item = (usize, &'a str)>) -> Result<(), String> {
+    for (index, label) in labels {
+        let position = index + 1;
+        if label.is_empty() {
+            return Err(format!("segment {} is empty", position));
+        }
+        if label.len() > 63 {
+            return Err(format!("segment {} is longer than 63 characters", position));
+        }
+        if let Some(c) = label
+            .chars()
+            .find(|c| !(c.is_ascii_alphanumeric() || *c == '-'))
+        {
+            return Err(format!("segment {} '{}' contains '{}'", position, label, c));
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Err(format!(
+                "segment {} '{}' starts or ends with a hyphen",
+                position, label
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn check_handle(value: &str) -> Result<(), String> {
+    if value.len() > 253 {
+        return Err("longer than 253 characters".to_string());
+    }
+    let labels: Vec<&str> = value.split('.').collect();
+    if labels.len() < 2 {
+        return Err(
+            "needs at least two dot-separated segments, like alice.example.com".to_string(),
+        );
+    }
+    check_labels(labels.iter().copied().enumerate())?;
+    if labels
+        .last()
+        .is_some_and(|tld| tld.starts_with(|c: char| c.is_ascii_digit()))
+    {
+        return Err("the last segment must not start with a digit".to_string());
+    }
+    Ok(())
+}
+
+fn check_nsid(value: &str) -> Result<(), String> {
+    if value.len() > 317 {
+        return Err("longer than 317 characters".to_string());
+    }
+    let segments: Vec<&str> = value.split('.').collect();
+    let Some((name, authority)) = segments.split_last().filter(|_| segments.len() >= 3) else {
+        return Err("needs a reversed domain and a name, like app.bsky.feed.post".to_string());
+    };
+    check_labels(authority.iter().copied().enumerate())?;
+    if authority[0].starts_with(|c: char| c.is_ascii_digit()) {
+        return Err("the first segment must not start with a digit".to_string());
+    }
+    if authority
+        .iter()
+        .map(|segment| segment.len() + 1)
+        .sum::<usize>()
+        > 254
+    {
+        return Err("domain authority is longer than 253 characters".to_string());
+    }
+    if name.is_empty() {
+        return Err("name after the last dot is empty".to_string());
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric())
+        || name.starts_with(|c: char| c.is_ascii_digit())
+    {
+        return Err(format!(
+            "name '{}' must be letters and digits, starting with a letter",
+            name
+        ));
+    }
+    Ok(())
+}
+
+fn check_record_key(value: &str) -> Result<(), String> {
+    if value.is_empty() {
+        return Err("is empty".to_string());
+    }
+    if value.len() > 512 {
+        return Err("longer than 512 characters".to_string());
+    }
+    if value == "." || value == ".." {
+        return Err(format!("'{}' is reserved", value));
+    }
+    if let Some(c) = value
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || "._:~-".contains(*c)))
+    {
+        return Err(format!("contains '{}'", c));
+    }
+    Ok(())
+}
+
+fn check_tid(value: &str) -> Result<(), String> {
+    if value.len() != 13 {
+        return Err(format!("must be 13 characters, not {}", value.len()));
+    }
+    if let Some(c) = value.chars().find(|c| !TID_ALPHABET.contains(*c)) {
+        return Err(format!(
+            "contains '{}'; only 2-7 and lowercase a-z are allowed",
+            c
+        ));
+    }
+    if !value.starts_with(|c: char| "234567abcdefghij".contains(c)) {
+        return Err("first character must be 2-7 or a-j".to_string());
+    }
+    Ok(())
+}
+
+/// Define a string-backed identifier type validated by `$check`.
+macro_rules! identifier {
+    ($(#[$meta:meta])* $name:ident, $kind:expr, $check:expr) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+        #[serde(transparent)]
+        pub struct $name(String);
+
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = anyhow::Error;
+
+            fn from_str(s: &str) -> anyhow::Result<Self> {
+                let check: fn(&str) -> Result<(), String> = $check;
+                check(s).map_err(|reason| anyhow::anyhow!("Invalid {} {}: {}", $kind, s, reason))?;
+                Ok(Self(s.to_string()))
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> String {
+                value.0
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = String::deserialize(deserializer)?;
+                value.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+identifier!(
+    /// A DID such as `did:plc:ewvi7nxzyoun6zhxrhs64oiz`.
+    Did,
+    Kind::Did,
+    check_did
+);
+identifier!(
+    /// A collection or method name such as `app.bsky.feed.post`.
+    Nsid,
+    Kind::Nsid,
+    check_nsid
+);
+identifier!(
+    /// The key of a record within its collection.
+    RecordKey,
+    Kind::RecordKey,
+    check_record_key
+);
+identifier!(
+    /// A timestamp identifier, as used for repo revisions and most record
+    /// keys.
+    Tid,
+    Kind::Tid,
+    check_tid
+);
+
+/// A domain-name handle such as `alice.bsky.social`. Handles are
+/// case-insensitive, so they are kept in lowercase; a leading `@` is dropped.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(transparent)]
+pub struct Handle(String);
+
+impl Handle {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Handle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Handle {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let handle = s.strip_prefix('@').unwrap_or(s);
+        check_handle(handle)
+            .map_err(|reason| anyhow::anyhow!("Invalid handle {}: {}", s, reason))?;
+        Ok(Self(handle.to_ascii_lowercase()))
+    }
+}
+
+/// A repository named by its DID or handle.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(untagged)]
+pub enum AtIdentifier {
+    Did(Did),
+    Handle(Handle),
+}
+
+impl AtIdentifier {
+    pub fn as_str(&self) -> &str {
+        match self {
+            AtIdentifier::Did(did) => did.as_str(),
+            AtIdentifier::Handle(handle) => handle.as_str(),
+        }
+    }
+}
+
+impl fmt::Display for AtIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AtIdentifier {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        if s.starts_with("did:") {
+            Ok(AtIdentifier::Did(s.parse()?))
+        } else {
+            Ok(AtIdentifier::Handle(s.parse()?))
+        }
+    }
+}
+
+/// An `at://` URI naming a repository, a collection in it, or a record.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AtUri {
+    pub authority: AtIdentifier,
+    pub collection: Option<Nsid>,
+    pub rkey: Option<RecordKey>,
+}
+
+impl fmt::Display for AtUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at://{}", self.authority)?;
+        if let Some(collection) = &self.collection {
+            write!(f, "/{}", collection)?;
+        }
+        if let Some(rkey) = &self.rkey {
+            write!(f, "/{}", rkey)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for AtUri {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let invalid = |reason: String| anyhow::anyhow!("Invalid AT-URI {}: {}", s, reason);
+        let rest = s
+            .strip_prefix("at://")
+            .ok_or_else(|| invalid("must start with at://".to_string()))?;
+        if rest.contains(['?', '#']) {
+            return Err(invalid(
+                "query and fragment parts are not supported".to_string(),
+            ));
+        }
+
+        let mut parts = rest.split('/');
+        let authority = parts.next().unwrap_or_default();
+        let authority = if authority.starts_with("did:") {
+            check_did(authority)
+                .map(|()| AtIdentifier::Did(Did(authority.to_string())))
+                .map_err(|reason| invalid(format!("repository DID {}", reason)))?
+        } else {
+            check_handle(authority)
+                .map(|()| AtIdentifier::Handle(Handle(authority.to_ascii_lowercase())))
+                .map_err(|reason| invalid(format!("repository handle {}", reason)))?
+        };
+        let collection = parts
+            .next()
+            .map(|collection| {
+                check_nsid(collection)
+                    .map(|()| Nsid(collection.to_string()))
+                    .map_err(|reason| invalid(format!("collection {}", reason)))
+            })
+            .transpose()?;
+        let rkey = parts
+            .next()
+            .map(|rkey| {
+                check_record_key(rkey)
+                    .map(|()| RecordKey(rkey.to_string()))
+                    .map_err(|reason| invalid(format!("record key {}", reason)))
+            })
+            .transpose()?;
+        if parts.next().is_some() {
+            return Err(invalid(
+                "has more than a repository, collection and record key".to_string(),
+            ));
+        }
+        Ok(Self {
+            authority,
+            collection,
+            rkey,
+        })
+    }
+}
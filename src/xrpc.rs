@@ -0,0 +1,433 @@
+use std::fmt::Display;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use clap::Parser;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, de::DeserializeOwned};
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    Client, Config, Process,
+    auth::{LoginResponse, refresh_session},
+    format, input,
+};
+
+/// Call any XRPC method by its NSID
+#[derive(Parser)]
+pub struct Xrpc {
+    /// Method NSID (e.g., com.atproto.repo.describeRepo)
+    pub nsid: String,
+    /// Query parameter as key=value; may be repeated
+    #[arg(long = "param", short = 'p', value_name = "KEY=VALUE")]
+    pub params: Vec<String>,
+    /// JSON input body: inline JSON, @path to read a file, or - for stdin
+    #[arg(long)]
+    pub body: Option<String>,
+    /// Send as a procedure (POST) even without a body
+    #[arg(long)]
+    pub procedure: bool,
+}
+
+impl Xrpc {
+    fn query(&self) -> anyhow::Result<Vec<(&str, String)>> {
+        self.params
+            .iter()
+            .map(|param| {
+                param
+                    .split_once('=')
+                    .map(|(key, value)| (key, value.to_string()))
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("Invalid parameter '{}': expected key=value", param)
+                    })
+            })
+            .collect()
+    }
+
+    async fn send(
+        &self,
+        client: &Client,
+        config: &Config,
+        procedure: bool,
+        query: &[(&str, String)],
+        body: Option<&serde_json::Value>,
+    ) -> anyhow::Result<Response> {
+        let url = client.xrpc(&self.nsid);
+        let build = |http: &reqwest::Client| {
+            let request = if procedure {
+                http.post(&url)
+            } else {
+                http.get(&url)
+            };
+            let request = request.query(query);
+            match body {
+                Some(body) => request.json(body),
+                None => request,
+            }
+        };
+
+        if config.session().is_some() {
+            client.send_authenticated(config, build).await
+        } else {
+            check(build(client.inner()).send().await?).await
+        }
+    }
+}
+
+#[async_trait]
+impl Process for Xrpc {
+    type Output = String;
+
+    async fn process(&self, client: &Client, config: &Config) -> anyhow::Result<Self::Output> {
+        let query = self.query()?;
+        let body = match &self.body {
+            Some(body) => Some(
+                serde_json::from_str::<serde_json::Value>(&input::read_arg(body).await?)
+                    .context("Invalid JSON body")?,
+            ),
+            None => None,
+        };
+
+        // Without a body the method is assumed to be a query, falling back to
+        // a procedure if the server says the HTTP method is wrong.
+        let procedure = self.procedure || body.is_some();
+        let response = match self
+            .send(client, config, procedure, &query, body.as_ref())
+            .await
+        {
+            Err(error) if !procedure && is_wrong_method(&error) => {
+                self.send(client, config, true, &query, body.as_ref()).await
+            }
+            response => response,
+        }
+        .with_context(|| format!("Failed to call {}", self.nsid))?;
+
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+
+        if content_type.starts_with("application/json") {
+            let value: serde_json::Value = response.json().await?;
+            format::render(config.output, &value, format::format_json)
+        } else if content_type.starts_with("text/") {
+            Ok(response.text().await?)
+        } else {
+            // Binary output such as blobs or CAR files goes to stdout untouched
+            let bytes = response.bytes().await?;
+            let mut stdout = tokio::io::stdout();
+            stdout.write_all(&bytes).await?;
+            stdout.flush().await?;
+            Ok(String::new())
+        }
+    }
+}
+
+fn is_wrong_method(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<XrpcError>().is_some_and(|error| {
+        error.status == StatusCode::METHOD_NOT_ALLOWED
+            || (error.kind == XrpcErrorKind::InvalidRequest
+                && error
+                    .message
+                    .as_deref()
+                    .is_some_and(|message| message.contains("Incorrect HTTP method")))
+    })
+}
+
+impl Client {
+    /// Send an unauthenticated XRPC query and decode its JSON output.
+    pub async fn get<T: DeserializeOwned>(
+        &self,
+        nsid: &str,
+        query: &[(&str, String)],
+    ) -> anyhow::Result<T> {
+        let response = self
+            .inner()
+            .get(self.xrpc(nsid))
+            .query(query)
+            .send()
+            .await?;
+        Ok(check(response).await?.json().await?)
+    }
+
+    /// Send an authenticated XRPC query and decode its JSON output.
+    pub async fn authenticated_get<T: DeserializeOwned>(
+        &self,
+        config: &Config,
+        nsid: &str,
+        query: &[(&str, String)],
+    ) -> anyhow::Result<T> {
+        let url = self.xrpc(nsid);
+        let response = self
+            .send_authenticated(config, |client| client.get(&url).query(query))
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    /// Send an authenticated XRPC procedure with a JSON body and decode its
+    /// JSON output.
+    pub async fn authenticated_post<T: DeserializeOwned>(
+        &self,
+        config: &Config,
+        nsid: &str,
+        body: &serde_json::Value,
+    ) -> anyhow::Result<T> {
+        let url = self.xrpc(nsid);
+        let response = self
+            .send_authenticated(config, |client| client.post(&url).json(body))
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    /// Send a request built by `build` with the active account's access token.
+    ///
+    /// If the server rejects the token as expired, the session is refreshed,
+    /// the new tokens are written to the config and the request is retried
+    /// once. Unsuccessful responses are turned into errors.
+    pub async fn send_authenticated<F>(&self, config: &Config, build: F) -> anyhow::Result<Response>
+    where
+        F: Fn(&reqwest::Client) -> RequestBuilder,
+    {
+        let session = self.session(config)?;
+        let response = build(self.inner())
+            .bearer_auth(&session.access_jwt)
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            return Ok(response);
+        }
+
+        let error = XrpcError::from_response(response).await;
+        if error.kind != XrpcErrorKind::ExpiredToken {
+            return Err(error.into());
+        }
+
+        let session = self.refresh(config).await?;
+        let response = build(self.inner())
+            .bearer_auth(&session.access_jwt)
+            .send()
+            .await?;
+        check(response).await
+    }
+
+    /// Refresh the active account's session and persist the new tokens.
+    pub async fn refresh(&self, config: &Config) -> anyhow::Result<LoginResponse> {
+        let session = self.session(config)?;
+        let new_session = refresh_session(self, &session.refresh_jwt).await?;
+
+        // Refresh tokens are single-use, so keep the new ones for this run
+        // even if storing them fails
+        *self.refreshed.lock().unwrap() = Some(new_session.clone());
+
+        // Store only the new tokens, in a fresh copy of the file, so neither
+        // sessions written since this run started (e.g. by account migration)
+        // nor this run's --account, --output or --plc-directory choices are
+        // overwritten
+        let name = config
+            .account_name()
+            .ok_or_else(|| anyhow::anyhow!("Not logged in"))?;
+        let base_dirs = directories::BaseDirs::new()
+            .ok_or_else(|| anyhow::anyhow!("Failed to find the home directory"))?;
+        let mut stored = Config::load(&base_dirs)
+            .await
+            .context("Failed to store refreshed tokens")?;
+        stored
+            .select(name)
+            .context("Failed to store refreshed tokens")?;
+        stored.set_session(new_session.clone());
+        stored.write(&base_dirs).await?;
+        Ok(new_session)
+    }
+
+    /// The session to authenticate with, preferring tokens refreshed earlier
+    /// in this run since refresh tokens are single-use.
+    fn session(&self, config: &Config) -> anyhow::Result<LoginResponse> {
+        if let Some(session) = self.refreshed.lock().unwrap().clone() {
+            return Ok(session);
+        }
+        config
+            .session()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Not logged in"))
+    }
+}
+
+/// Pass successful responses through and turn the rest into an [`XrpcError`].
+pub(crate) async fn check(response: Response) -> anyhow::Result<Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+
+    Err(XrpcError::from_response(response).await.into())
+}
+
+/// The error names defined by XRPC and the `com.atproto` lexicons that
+/// commands care to tell apart.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum XrpcErrorKind {
+    InvalidRequest,
+    ExpiredToken,
+    InvalidToken,
+    AuthRequired,
+    Forbidden,
+    RateLimitExceeded,
+    RecordNotFound,
+    RepoNotFound,
+    BlobNotFound,
+    InvalidSwap,
+    MethodNotImplemented,
+    InternalServerError,
+    UpstreamFailure,
+    /// Any other error name returned by the server
+    Other(String),
+}
+
+impl XrpcErrorKind {
+    fn parse(name: &str) -> Self {
+        match name {
+            "InvalidRequest" => Self::InvalidRequest,
+            "ExpiredToken" => Self::ExpiredToken,
+            "InvalidToken" => Self::InvalidToken,
+            "AuthRequired" | "AuthenticationRequired" => Self::AuthRequired,
+            "Forbidden" => Self::Forbidden,
+            "RateLimitExceeded" => Self::RateLimitExceeded,
+            "RecordNotFound" => Self::RecordNotFound,
+            "RepoNotFound" => Self::RepoNotFound,
+            "BlobNotFound" => Self::BlobNotFound,
+            "InvalidSwap" => Self::InvalidSwap,
+            "MethodNotImplemented" => Self::MethodNotImplemented,
+            "InternalServerError" => Self::InternalServerError,
+            "UpstreamFailure" => Self::UpstreamFailure,
+            other => Self::Other(other.to_string()),
+        }
+    }
+
+    /// The kind implied by the status code when the body names no error.
+    fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::BAD_REQUEST => Self::InvalidRequest,
+            StatusCode::UNAUTHORIZED => Self::AuthRequired,
+            StatusCode::FORBIDDEN => Self::Forbidden,
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimitExceeded,
+            StatusCode::NOT_IMPLEMENTED => Self::MethodNotImplemented,
+            StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT => Self::UpstreamFailure,
+            status if status.is_server_error() => Self::InternalServerError,
+            status => Self::Other(status.to_string()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::InvalidRequest => "InvalidRequest",
+            Self::ExpiredToken => "ExpiredToken",
+            Self::InvalidToken => "InvalidToken",
+            Self::AuthRequired => "AuthRequired",
+            Self::Forbidden => "Forbidden",
+            Self::RateLimitExceeded => "RateLimitExceeded",
+            Self::RecordNotFound => "RecordNotFound",
+            Self::RepoNotFound => "RepoNotFound",
+            Self::BlobNotFound => "BlobNotFound",
+            Self::InvalidSwap => "InvalidSwap",
+            Self::MethodNotImplemented => "MethodNotImplemented",
+            Self::InternalServerError => "InternalServerError",
+            Self::UpstreamFailure => "UpstreamFailure",
+            Self::Other(name) => name,
+        }
+    }
+}
+
+/// An unsuccessful XRPC response, decoded from its `{"error", "message"}` body.
+#[derive(Debug)]
+pub struct XrpcError {
+    pub status: StatusCode,
+    pub kind: XrpcErrorKind,
+    pub message: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: Option<String>,
+    message: Option<String>,
+}
+
+impl XrpcError {
+    pub async fn from_response(response: Response) -> Self {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        Self::from_body(status, &body)
+    }
+
+    pub fn from_body(status: StatusCode, body: &str) -> Self {
+        match serde_json::from_str::<ErrorBody>(body) {
+            Ok(ErrorBody { error, message }) => Self {
+                status,
+                kind: error
+                    .as_deref()
+                    .map(XrpcErrorKind::parse)
+                    .unwrap_or_else(|| XrpcErrorKind::from_status(status)),
+                message,
+            },
+            Err(_) => Self {
+                status,
+                kind: XrpcErrorKind::from_status(status),
+                message: Some(body.trim().to_string()).filter(|body| !body.is_empty()),
+            },
+        }
+    }
+
+    /// Process exit code for this error, so scripts can branch on the kind of
+    /// failure without parsing messages.
+    ///
+    /// | Code | Errors |
+    /// |------|--------|
+    /// | 3 | `AuthRequired`, `InvalidToken`, `ExpiredToken`, `Forbidden` |
+    /// | 4 | `RecordNotFound`, `RepoNotFound`, `BlobNotFound` |
+    /// | 5 | `InvalidRequest` |
+    /// | 6 | `RateLimitExceeded` |
+    /// | 7 | `InvalidSwap` |
+    /// | 8 | `InternalServerError`, `UpstreamFailure`, other 5xx |
+    /// | 9 | any other XRPC error |
+    pub fn exit_code(&self) -> i32 {
+        match self.kind {
+            XrpcErrorKind::AuthRequired
+            | XrpcErrorKind::InvalidToken
+            | XrpcErrorKind::ExpiredToken
+            | XrpcErrorKind::Forbidden => 3,
+            XrpcErrorKind::RecordNotFound
+            | XrpcErrorKind::RepoNotFound
+            | XrpcErrorKind::BlobNotFound => 4,
+            XrpcErrorKind::InvalidRequest => 5,
+            XrpcErrorKind::RateLimitExceeded => 6,
+            XrpcErrorKind::InvalidSwap => 7,
+            XrpcErrorKind::InternalServerError | XrpcErrorKind::UpstreamFailure => 8,
+            _ if self.status.is_server_error() => 8,
+            _ => 9,
+        }
+    }
+}
+
+impl Display for XrpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind.name())?;
+        if let Some(message) = &self.message {
+            write!(f, ": {}", message)?;
+        }
+        write!(f, " ({})", self.status)
+    }
+}
+
+impl std::error::Error for XrpcError {}
+
+/// Exit code for a failed command: the [`XrpcError`] code if the failure came
+/// from the server, otherwise 1.
+pub fn exit_code(error: &anyhow::Error) -> i32 {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<XrpcError>())
+        .map_or(1, XrpcError::exit_code)
+}
@@ -0,0 +1,323 @@
+mod common;
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use atp::cid::{Cid, RAW};
+use common::{
+    MockServer, atp_command_with_home, json_response, repo_car, session_config, write_config,
+};
+use serde_json::json;
+
+// =============================================================================
+// ACCOUNT TESTS - atp account migrate
+// =============================================================================
+
+const DID: &str = "did:plc:test123";
+const BLOB: &[u8] = b"GIF89a not really an image";
+
+/// The PDS the account is moving away from
+fn source_pds() -> MockServer {
+    MockServer::start(|request| match request.route() {
+        "/xrpc/com.atproto.server.getServiceAuth" => {
+            json_response(200, json!({ "token": "service-token" }))
+        }
+        "/xrpc/com.atproto.sync.getRepo" => (
+            200,
+            "application/vnd.ipld.car",
+            repo_car(
+                DID,
+                &[("app.bsky.feed.post/3kpost1", json!({ "text": "hello" }))],
+            ),
+        ),
+        "/xrpc/com.atproto.sync.getBlob" => (200, "image/gif", BLOB.to_vec()),
+        "/xrpc/app.bsky.actor.getPreferences" => json_response(200, json!({ "preferences": [] })),
+        "/xrpc/com.atproto.identity.requestPlcOperationSignature"
+        | "/xrpc/com.atproto.server.deactivateAccount" => (200, "application/json", Vec::new()),
+        "/xrpc/com.atproto.identity.signPlcOperation" => json_response(
+            200,
+            json!({ "operation": { "type": "plc_operation", "sig": "signed" } }),
+        ),
+        _ => json_response(404, json!({ "error": "MethodNotImplemented" })),
+    })
+}
+
+/// The PDS the account is moving to. `import_fails` makes the next
+/// importRepo fail once.
+fn target_pds(import_fails: Arc<AtomicBool>) -> MockServer {
+    let blob_cid = Cid::for_block(RAW, BLOB).to_string();
+    MockServer::start(move |request| match request.route() {
+        "/xrpc/com.atproto.server.describeServer" => json_response(
+            200,
+            json!({ "did": "did:web:pds.example.com", "availableUserDomains": [] }),
+        ),
+        "/xrpc/com.atproto.server.createAccount" => json_response(
+            200,
+            json!({
+                "did": DID,
+                "handle": "test.example.com",
+                "accessJwt": "new-access",
+                "refreshJwt": "new-refresh",
+            }),
+        ),
+        "/xrpc/com.atproto.repo.importRepo" if import_fails.swap(false, Ordering::SeqCst) => {
+            json_response(500, json!({ "error": "InternalServerError" }))
+        }
+        "/xrpc/com.atproto.repo.listMissingBlobs" => json_response(
+            200,
+            json!({ "blobs": [{ "cid": blob_cid, "recordUri": format!("at://{DID}/app.bsky.feed.post/3kpost1") }] }),
+        ),
+        "/xrpc/com.atproto.repo.uploadBlob" => json_response(
+            200,
+            json!({
+                "blob": {
+                    "$type": "blob",
+                    "ref": { "$link": blob_cid },
+                    "mimeType": "image/gif",
+                    "size": BLOB.len(),
+                }
+            }),
+        ),
+        "/xrpc/com.atproto.identity.getRecommendedDidCredentials" => json_response(
+            200,
+            json!({
+                "rotationKeys": ["did:key:zQ3shrotation"],
+                "alsoKnownAs": ["at://test.example.com"],
+                "verificationMethods": { "atproto": "did:key:zQ3shsigning" },
+                "services": {
+                    "atproto_pds": {
+                        "type": "AtprotoPersonalDataServer",
+                        "endpoint": "https://pds.example.com",
+                    }
+                },
+            }),
+        ),
+        "/xrpc/com.atproto.repo.importRepo"
+        | "/xrpc/app.bsky.actor.putPreferences"
+        | "/xrpc/com.atproto.identity.submitPlcOperation"
+        | "/xrpc/com.atproto.server.activateAccount" => (200, "application/json", Vec::new()),
+        _ => json_response(404, json!({ "error": "MethodNotImplemented" })),
+    })
+}
+
+fn migrate(home: &std::path::Path, target: &str, extra: &[&str]) -> std::process::Output {
+    let state = home.join("migration.json");
+    atp_command_with_home(home)
+        .args([
+            "account",
+            "migrate",
+            "--from-session",
+            "--to",
+            target,
+            "--password",
+            "new-password",
+            "--state",
+            state.to_str().unwrap(),
+        ])
+        .args(extra)
+        .output()
+        .expect("Failed to execute account migrate")
+}
+
+fn routes(server: &MockServer) -> Vec<String> {
+    server
+        .requests()
+        .iter()
+        .map(|request| request.route().to_string())
+        .collect()
+}
+
+fn completed_steps(home: &std::path::Path) -> Vec<String> {
+    let state: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(home.join("migration.json")).unwrap()).unwrap();
+    serde_json::from_value(state["completed"].clone()).unwrap()
+}
+
+#[test]
+fn test_account_migrate_waits_for_plc_token_then_finishes() {
+    let source = source_pds();
+    let target = target_pds(Arc::default());
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&source.url));
+
+    let output = migrate(home.path(), &target.url, &[]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Requested a PLC operation signature"));
+    assert!(stdout.contains("--plc-token"));
+    assert_eq!(
+        completed_steps(home.path()),
+        [
+            "createAccount",
+            "importRepo",
+            "importBlobs",
+            "importPreferences",
+            "requestPlcSignature"
+        ]
+    );
+    assert!(!routes(&source).contains(&"/xrpc/com.atproto.identity.signPlcOperation".into()));
+
+    // The account is created under the existing DID with a service token
+    let requests = target.requests();
+    let create = requests
+        .iter()
+        .find(|request| request.route() == "/xrpc/com.atproto.server.createAccount")
+        .unwrap();
+    assert_eq!(create.header("authorization"), Some("Bearer service-token"));
+    let describe = requests
+        .iter()
+        .find(|request| request.route() == "/xrpc/com.atproto.server.describeServer")
+        .unwrap();
+    assert_eq!(
+        describe.header("authorization"),
+        None,
+        "The old session is never sent to the new PDS"
+    );
+    let body: serde_json::Value = serde_json::from_slice(&create.body).unwrap();
+    assert_eq!(body["did"], DID);
+    assert_eq!(body["handle"], "test.example.com");
+    let service_auth = source
+        .requests()
+        .into_iter()
+        .find(|request| request.route() == "/xrpc/com.atproto.server.getServiceAuth")
+        .unwrap();
+    assert!(
+        service_auth
+            .path
+            .contains("aud=did%3Aweb%3Apds.example.com")
+    );
+
+    // Later calls to the new PDS use the new account's session
+    let upload = requests
+        .iter()
+        .find(|request| request.route() == "/xrpc/com.atproto.repo.uploadBlob")
+        .unwrap();
+    assert_eq!(upload.header("authorization"), Some("Bearer new-access"));
+    assert_eq!(upload.body, BLOB);
+
+    let output = migrate(home.path(), &target.url, &["--plc-token", "ABCDE-12345"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Migration complete; now using account: test-migrated"));
+    assert!(!stdout.contains("Created the account"));
+
+    let sign = source
+        .requests()
+        .into_iter()
+        .find(|request| request.route() == "/xrpc/com.atproto.identity.signPlcOperation")
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&sign.body).unwrap();
+    assert_eq!(body["token"], "ABCDE-12345");
+    assert_eq!(body["rotationKeys"][0], "did:key:zQ3shrotation");
+    let submit = target
+        .requests()
+        .into_iter()
+        .find(|request| request.route() == "/xrpc/com.atproto.identity.submitPlcOperation")
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&submit.body).unwrap();
+    assert_eq!(body["operation"]["sig"], "signed");
+    assert!(routes(&target).contains(&"/xrpc/com.atproto.server.activateAccount".into()));
+    assert!(routes(&source).contains(&"/xrpc/com.atproto.server.deactivateAccount".into()));
+
+    let config = std::fs::read_to_string(home.path().join(".config/atp/config.toml")).unwrap();
+    assert!(config.contains("current = \"test-migrated\""));
+    assert!(config.contains(&format!("pds = \"{}\"", target.url)));
+}
+
+#[test]
+fn test_account_migrate_resumes_after_failure() {
+    let source = source_pds();
+    let target = target_pds(Arc::new(AtomicBool::new(true)));
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&source.url));
+
+    let output = migrate(home.path(), &target.url, &[]);
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Failed to import repo"));
+    assert_eq!(completed_steps(home.path()), ["createAccount"]);
+
+    let output = migrate(home.path(), &target.url, &["--plc-token", "ABCDE-12345"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let creates = routes(&target)
+        .iter()
+        .filter(|route| *route == "/xrpc/com.atproto.server.createAccount")
+        .count();
+    assert_eq!(creates, 1, "Should not create the account twice");
+    // The token was passed in, so no new one is requested
+    assert!(
+        !routes(&source)
+            .contains(&"/xrpc/com.atproto.identity.requestPlcOperationSignature".into())
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("Requested a PLC operation signature"));
+    assert!(
+        completed_steps(home.path()).contains(&"requestPlcSignature".to_string()),
+        "The skipped request is recorded as done"
+    );
+}
+
+#[test]
+fn test_account_migrate_requires_password() {
+    let source = source_pds();
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&source.url));
+
+    let output = atp_command_with_home(home.path())
+        .args([
+            "account",
+            "migrate",
+            "--from-session",
+            "--to",
+            "http://127.0.0.1:9",
+            "--state",
+            home.path().join("migration.json").to_str().unwrap(),
+        ])
+        .output()
+        .expect("Failed to execute account migrate");
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("--password is required"));
+}
+
+#[test]
+fn test_account_migrate_from_session_is_optional() {
+    let source = source_pds();
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&source.url));
+    let state = home.path().join("migration.json");
+    let state = state.to_str().unwrap();
+
+    // Without the flag the active account is moved
+    let output = atp_command_with_home(home.path())
+        .args(["account", "migrate", "--to", "http://127.0.0.1:9"])
+        .args(["--state", state])
+        .output()
+        .expect("Failed to execute account migrate");
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("--password is required"), "{stderr}");
+
+    let output = atp_command_with_home(home.path())
+        .args(["account", "migrate", "--from-session", "other"])
+        .args(["--to", "http://127.0.0.1:9", "--state", state])
+        .output()
+        .expect("Failed to execute account migrate");
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("No account named 'other'"), "{stderr}");
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use common::{atp_command_with_home, write_config};
+
+// =============================================================================
+// AUTH TESTS - local account profiles
+// =============================================================================
+
+const TWO_ACCOUNTS: &str = r#"
+current = "personal"
+
+[accounts.personal]
+pds = "https://pds.example.com"
+did = "did:plc:personal123"
+handle = "alice.example.com"
+accessJwt = "personal-access"
+refreshJwt = "personal-refresh"
+
+[accounts.bot]
+pds = "https://bsky.social"
+did = "did:plc:bot456"
+handle = "bot.bsky.social"
+accessJwt = "bot-access"
+refreshJwt = "bot-refresh"
+"#;
+
+#[test]
+fn test_auth_list_marks_current_account() {
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), TWO_ACCOUNTS);
+
+    let output = atp_command_with_home(home.path())
+        .args(["auth", "list"])
+        .output()
+        .expect("Failed to execute auth list");
+
+    assert!(output.status.success(), "Command should succeed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("* personal"), "Should mark current account");
+    assert!(stdout.contains("  bot"), "Should list other accounts");
+    assert!(
+        stdout.contains("did:plc:bot456"),
+        "Should show account DIDs"
+    );
+}
+
+#[test]
+fn test_auth_session_with_account_flag() {
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), TWO_ACCOUNTS);
+
+    let output = atp_command_with_home(home.path())
+        .args(["--account", "bot", "auth", "session"])
+        .output()
+        .expect("Failed to execute auth session");
+
+    assert!(output.status.success(), "Command should succeed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("handle: bot.bsky.social"));
+
+    // --account must not change the stored default
+    let output = atp_command_with_home(home.path())
+        .args(["auth", "session"])
+        .output()
+        .expect("Failed to execute auth session");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("handle: alice.example.com"));
+    assert!(stdout.contains("pds: https://pds.example.com"));
+}
+
+#[test]
+fn test_auth_use_switches_default_account() {
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), TWO_ACCOUNTS);
+
+    let output = atp_command_with_home(home.path())
+        .args(["auth", "use", "bot"])
+        .output()
+        .expect("Failed to execute auth use");
+    assert!(output.status.success(), "Command should succeed");
+
+    let output = atp_command_with_home(home.path())
+        .args(["auth", "session"])
+        .output()
+        .expect("Failed to execute auth session");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("account: bot"));
+}
+
+#[test]
+fn test_auth_unknown_account() {
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), TWO_ACCOUNTS);
+
+    let output = atp_command_with_home(home.path())
+        .args(["--account", "nobody", "auth", "session"])
+        .output()
+        .expect("Failed to execute auth session");
+
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("No account named 'nobody'"));
+}
+
+#[test]
+fn test_auth_migrates_single_session_config() {
+    let home = tempfile::tempdir().unwrap();
+    write_config(
+        home.path(),
+        r#"
+[session]
+did = "did:plc:legacy789"
+handle = "legacy.bsky.social"
+accessJwt = "access"
+refreshJwt = "refresh"
+"#,
+    );
+
+    let output = atp_command_with_home(home.path())
+        .args(["auth", "list"])
+        .output()
+        .expect("Failed to execute auth list");
+
+    assert!(output.status.success(), "Command should succeed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("* legacy.bsky.social"));
+}
@@ -0,0 +1,288 @@
+mod common;
+
+use atp::{
+    car::CarReader,
+    cid::{Cid, RAW},
+};
+use common::{
+    MockServer, atp_command_with_home, car_file, json_response, repo_car, session_config,
+    write_config,
+};
+use serde_json::json;
+
+// =============================================================================
+// BACKUP TESTS - atp backup create / restore
+// =============================================================================
+
+const DID: &str = "did:plc:test123";
+const BLOB: &[u8] = b"\x89PNG not really an image";
+
+fn first_repo() -> Vec<u8> {
+    repo_car(
+        DID,
+        &[
+            ("app.bsky.feed.post/3kpost1", json!({ "text": "first" })),
+            ("app.bsky.feed.post/3kpost2", json!({ "text": "second" })),
+        ],
+    )
+}
+
+fn second_repo() -> Vec<u8> {
+    repo_car(
+        DID,
+        &[
+            ("app.bsky.feed.post/3kpost1", json!({ "text": "first" })),
+            ("app.bsky.feed.post/3kpost2", json!({ "text": "second" })),
+            ("app.bsky.feed.post/3kpost5", json!({ "text": "third" })),
+        ],
+    )
+}
+
+/// The blocks of `car` that aren't in `base`, as a CAR with `car`'s root,
+/// like a PDS answers `getRepo` with `since`
+fn diff_car(car: &[u8], base: &[u8]) -> Vec<u8> {
+    let old = CarReader::new(base).unwrap().blocks().unwrap();
+    let mut reader = CarReader::new(car).unwrap();
+    let roots = reader.roots.clone();
+    let mut blocks = Vec::new();
+    while let Some((cid, block)) = reader.next_block().unwrap() {
+        if !old.contains_key(&cid) {
+            blocks.push((cid, block));
+        }
+    }
+    car_file(&roots, &blocks)
+}
+
+/// A PDS holding `first_repo`, which moves on to `second_repo` for requests
+/// with `since`
+fn backup_pds() -> MockServer {
+    let blob_cid = Cid::for_block(RAW, BLOB).to_string();
+    MockServer::start(move |request| match request.route() {
+        "/xrpc/com.atproto.sync.getRepo" if request.path.contains("since=") => (
+            200,
+            "application/vnd.ipld.car",
+            diff_car(&second_repo(), &first_repo()),
+        ),
+        "/xrpc/com.atproto.sync.getRepo" => (200, "application/vnd.ipld.car", first_repo()),
+        "/xrpc/com.atproto.sync.listBlobs" if request.path.contains("since=") => {
+            json_response(200, json!({ "cids": [] }))
+        }
+        "/xrpc/com.atproto.sync.listBlobs" => {
+            json_response(200, json!({ "cids": [blob_cid.clone()] }))
+        }
+        "/xrpc/com.atproto.sync.getBlob" => (200, "image/png", BLOB.to_vec()),
+        "/xrpc/app.bsky.actor.getPreferences" => json_response(
+            200,
+            json!({ "preferences": [{ "$type": "app.bsky.actor.defs#adultContentPref", "enabled": false }] }),
+        ),
+        _ => json_response(404, json!({ "error": "MethodNotImplemented" })),
+    })
+}
+
+fn create_backup(home: &std::path::Path, dir: &std::path::Path) -> std::process::Output {
+    atp_command_with_home(home)
+        .args(["backup", "create", "--dir", dir.to_str().unwrap()])
+        .output()
+        .expect("Failed to execute backup create")
+}
+
+#[test]
+fn test_backup_create_writes_backup() {
+    let server = backup_pds();
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+    let dir = home.path().join("backup");
+
+    let output = create_backup(home.path(), &dir);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Backed up did:plc:test123 at rev 3lrevision222"));
+    assert!(stdout.contains("Records: 2"));
+    assert!(stdout.contains("Blobs: 1 (1 downloaded)"));
+
+    let blob_cid = Cid::for_block(RAW, BLOB).to_string();
+    assert_eq!(
+        std::fs::read(dir.join("blobs").join(&blob_cid)).unwrap(),
+        BLOB
+    );
+    let (saved, served) = (std::fs::read(dir.join("repo.car")).unwrap(), first_repo());
+    let (mut saved, mut served) = (
+        CarReader::new(saved.as_slice()).unwrap(),
+        CarReader::new(served.as_slice()).unwrap(),
+    );
+    assert_eq!(saved.roots, served.roots);
+    assert_eq!(saved.blocks().unwrap(), served.blocks().unwrap());
+    assert!(!dir.join("repo.car.partial").exists());
+
+    let manifest: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(dir.join("manifest.json")).unwrap()).unwrap();
+    assert_eq!(manifest["did"], DID);
+    assert_eq!(manifest["rev"], "3lrevision222");
+    assert!(manifest["createdAt"].is_string());
+    assert!(manifest["since"].is_null());
+    assert_eq!(manifest["blobs"][&blob_cid], "image/png");
+
+    let preferences: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(dir.join("preferences.json")).unwrap()).unwrap();
+    assert_eq!(preferences["preferences"][0]["enabled"], false);
+
+    let requests = server.requests();
+    let preferences = requests
+        .iter()
+        .find(|request| request.route() == "/xrpc/app.bsky.actor.getPreferences")
+        .unwrap();
+    assert_eq!(
+        preferences.header("authorization"),
+        Some("Bearer old-access")
+    );
+}
+
+#[test]
+fn test_backup_create_is_incremental() {
+    let server = backup_pds();
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+    let dir = home.path().join("backup");
+
+    assert!(create_backup(home.path(), &dir).status.success());
+    let output = create_backup(home.path(), &dir);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Incremental since: 3lrevision222"));
+    assert!(stdout.contains("Records: 3"));
+    assert!(stdout.contains("Blobs: 1 (0 downloaded)"));
+
+    let requests = server.requests();
+    let get_repos: Vec<_> = requests
+        .iter()
+        .filter(|request| request.route() == "/xrpc/com.atproto.sync.getRepo")
+        .collect();
+    assert_eq!(get_repos.len(), 2);
+    assert!(get_repos[1].path.contains("since=3lrevision222"));
+
+    // The diff is merged with the earlier backup into a complete repository
+    let output = atp_command_with_home(home.path())
+        .args([
+            "car",
+            "records",
+            dir.join("repo.car").to_str().unwrap(),
+            "--output",
+            "json",
+        ])
+        .output()
+        .expect("Failed to execute car records");
+    assert!(output.status.success());
+    let records: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let texts: Vec<_> = records
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|record| record["value"]["text"].as_str().unwrap())
+        .collect();
+    assert_eq!(texts, ["first", "second", "third"]);
+}
+
+#[test]
+fn test_backup_restore_imports_repo_and_blobs() {
+    let source = backup_pds();
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&source.url));
+    let dir = home.path().join("backup");
+    assert!(create_backup(home.path(), &dir).status.success());
+
+    let blob_cid = Cid::for_block(RAW, BLOB).to_string();
+    let target = MockServer::start(move |request| match request.route() {
+        "/xrpc/com.atproto.repo.importRepo" => (200, "application/json", Vec::new()),
+        "/xrpc/com.atproto.repo.uploadBlob" => json_response(
+            200,
+            json!({
+                "blob": {
+                    "$type": "blob",
+                    "ref": { "$link": blob_cid },
+                    "mimeType": "image/png",
+                    "size": BLOB.len(),
+                }
+            }),
+        ),
+        "/xrpc/app.bsky.actor.putPreferences" => (200, "application/json", Vec::new()),
+        _ => json_response(404, json!({ "error": "MethodNotImplemented" })),
+    });
+    write_config(home.path(), &session_config(&target.url));
+
+    let output = atp_command_with_home(home.path())
+        .args(["backup", "restore", "--dir", dir.to_str().unwrap()])
+        .output()
+        .expect("Failed to execute backup restore");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Restored did:plc:test123 at rev 3lrevision222"));
+    assert!(stdout.contains("Blobs uploaded: 1"));
+
+    let requests = target.requests();
+    let routes: Vec<_> = requests.iter().map(|request| request.route()).collect();
+    assert_eq!(
+        routes,
+        [
+            "/xrpc/com.atproto.repo.importRepo",
+            "/xrpc/com.atproto.repo.uploadBlob",
+            "/xrpc/app.bsky.actor.putPreferences",
+        ]
+    );
+    assert_eq!(
+        requests[0].header("content-type"),
+        Some("application/vnd.ipld.car")
+    );
+    assert_eq!(
+        requests[0].body,
+        std::fs::read(dir.join("repo.car")).unwrap()
+    );
+    assert_eq!(requests[1].header("content-type"), Some("image/png"));
+    assert_eq!(requests[1].body, BLOB);
+    let preferences: serde_json::Value = serde_json::from_slice(&requests[2].body).unwrap();
+    assert_eq!(
+        preferences["preferences"][0]["$type"],
+        "app.bsky.actor.defs#adultContentPref"
+    );
+}
+
+#[test]
+fn test_backup_restore_rejects_other_account() {
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config("http://127.0.0.1:9"));
+    let dir = home.path().join("backup");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("manifest.json"),
+        json!({
+            "did": "did:plc:someoneelse",
+            "handle": "other.example.com",
+            "rev": "3lrevision222",
+            "commit": "bafyreib2rxk3rybk3aobmv5cjuql3bm2twh4jo5uxgf5gpqrpbbhhwhvq4",
+            "createdAt": "2026-01-01T00:00:00.000Z",
+            "since": null,
+            "blobs": {},
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let output = atp_command_with_home(home.path())
+        .args(["backup", "restore", "--dir", dir.to_str().unwrap()])
+        .output()
+        .expect("Failed to execute backup restore");
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Backup is of did:plc:someoneelse"));
+}
@@ -9,7 +9,7 @@ use common::atp_command;
 #[test]
 fn test_bsky_actor_profile_success() {
     let output = atp_command()
-        .args(&["bsky", "actor", "profile", "--actor", "bsky.app"])
+        .args(["bsky", "actor", "profile", "--actor", "bsky.app"])
         .output()
         .expect("Failed to execute profile");
 
@@ -27,7 +27,7 @@ fn test_bsky_actor_profile_success() {
 #[test]
 fn test_bsky_actor_profile_missing_actor() {
     let output = atp_command()
-        .args(&["bsky", "actor", "profile"])
+        .args(["bsky", "actor", "profile"])
         .output()
         .expect("Failed to execute profile");
 
@@ -45,7 +45,7 @@ fn test_bsky_actor_profile_missing_actor() {
 #[test]
 fn test_bsky_actor_search_success() {
     let output = atp_command()
-        .args(&["bsky", "actor", "search", "--query", "bsky", "--limit", "3"])
+        .args(["bsky", "actor", "search", "--query", "bsky", "--limit", "3"])
         .output()
         .expect("Failed to execute search");
 
@@ -61,7 +61,7 @@ fn test_bsky_actor_search_success() {
 #[test]
 fn test_bsky_actor_search_missing_query() {
     let output = atp_command()
-        .args(&["bsky", "actor", "search"])
+        .args(["bsky", "actor", "search"])
         .output()
         .expect("Failed to execute search");
 
@@ -79,7 +79,7 @@ fn test_bsky_actor_search_missing_query() {
 #[test]
 fn test_bsky_actor_suggestions_requires_auth() {
     let output = atp_command()
-        .args(&["bsky", "actor", "suggestions", "--limit", "5"])
+        .args(["bsky", "actor", "suggestions", "--limit", "5"])
         .output()
         .expect("Failed to execute suggestions");
 
@@ -99,7 +99,7 @@ fn test_bsky_actor_suggestions_requires_auth() {
 #[test]
 fn test_bsky_actor_profiles_success() {
     let output = atp_command()
-        .args(&[
+        .args([
             "bsky",
             "actor",
             "profiles",
@@ -121,7 +121,7 @@ fn test_bsky_actor_profiles_success() {
 #[test]
 fn test_bsky_actor_profiles_missing_actors() {
     let output = atp_command()
-        .args(&["bsky", "actor", "profiles"])
+        .args(["bsky", "actor", "profiles"])
         .output()
         .expect("Failed to execute profiles");
 
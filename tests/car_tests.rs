@@ -0,0 +1,229 @@
+mod common;
+
+use atp::{
+    cbor::{self, Value},
+    cid::{Cid, DAG_CBOR},
+};
+use common::{atp_command, car_file, repo_car};
+use serde_json::json;
+
+// =============================================================================
+// CAR TESTS - offline CAR file inspection
+// =============================================================================
+
+fn write_repo_car(dir: &std::path::Path) -> std::path::PathBuf {
+    let path = dir.join("repo.car");
+    let car = repo_car(
+        "did:plc:test123",
+        &[
+            (
+                "app.bsky.feed.post/3kpost1",
+                json!({"$type": "app.bsky.feed.post", "text": "first", "createdAt": "2025-01-01T00:00:00Z"}),
+            ),
+            (
+                "app.bsky.feed.post/3kpost2",
+                json!({"$type": "app.bsky.feed.post", "text": "second", "createdAt": "2025-01-02T00:00:00Z"}),
+            ),
+            (
+                "app.bsky.actor.profile/self",
+                json!({"$type": "app.bsky.actor.profile", "displayName": "Test"}),
+            ),
+        ],
+    );
+    std::fs::write(&path, car).unwrap();
+    path
+}
+
+#[test]
+fn test_car_ls_lists_blocks() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_repo_car(dir.path());
+
+    let output = atp_command()
+        .args(["car", "ls", path.to_str().unwrap(), "--output", "json"])
+        .output()
+        .expect("Failed to execute car ls");
+
+    assert!(output.status.success(), "Command should succeed");
+    let blocks: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let blocks = blocks.as_array().unwrap();
+    assert_eq!(blocks.len(), 5, "Three records, one MST node and a commit");
+    assert_eq!(
+        blocks.iter().filter(|block| block["root"] == true).count(),
+        1
+    );
+    assert!(blocks.iter().all(|block| block["codec"] == "dag-cbor"));
+}
+
+#[test]
+fn test_car_cat_prints_commit_as_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_repo_car(dir.path());
+    let ls = atp_command()
+        .args(["car", "ls", path.to_str().unwrap(), "--output", "json"])
+        .output()
+        .unwrap();
+    let blocks: serde_json::Value = serde_json::from_slice(&ls.stdout).unwrap();
+    let root = blocks
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|block| block["root"] == true)
+        .unwrap()["cid"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let output = atp_command()
+        .args(["car", "cat", path.to_str().unwrap(), &root])
+        .output()
+        .expect("Failed to execute car cat");
+
+    assert!(output.status.success(), "Command should succeed");
+    let commit: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(commit["did"], "did:plc:test123");
+    assert_eq!(commit["version"], 3);
+    assert!(
+        commit["data"]["$link"]
+            .as_str()
+            .unwrap()
+            .starts_with("bafyrei")
+    );
+    assert!(commit["sig"]["$bytes"].is_string());
+}
+
+#[test]
+fn test_car_cat_missing_block() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_repo_car(dir.path());
+
+    let output = atp_command()
+        .args([
+            "car",
+            "cat",
+            path.to_str().unwrap(),
+            "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku",
+        ])
+        .output()
+        .expect("Failed to execute car cat");
+
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("is not in"),
+        "Should say the block is missing"
+    );
+}
+
+#[test]
+fn test_car_records_walks_repo() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_repo_car(dir.path());
+
+    let output = atp_command()
+        .args(["car", "records", path.to_str().unwrap()])
+        .output()
+        .expect("Failed to execute car records");
+
+    assert!(output.status.success(), "Command should succeed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let keys: Vec<&str> = stdout
+        .lines()
+        .map(|line| line.split_once(':').unwrap().0)
+        .collect();
+    assert_eq!(
+        keys,
+        [
+            "app.bsky.actor.profile/self",
+            "app.bsky.feed.post/3kpost1",
+            "app.bsky.feed.post/3kpost2"
+        ]
+    );
+    assert!(stdout.contains(r#""text":"second""#));
+}
+
+#[test]
+fn test_car_records_filters_collection() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_repo_car(dir.path());
+
+    let output = atp_command()
+        .args([
+            "car",
+            "records",
+            path.to_str().unwrap(),
+            "--collection",
+            "app.bsky.actor.profile",
+            "--output",
+            "jsonl",
+        ])
+        .output()
+        .expect("Failed to execute car records");
+
+    assert!(output.status.success(), "Command should succeed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 1);
+    let record: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    assert_eq!(record["rkey"], "self");
+    assert_eq!(record["value"]["displayName"], "Test");
+}
+
+#[test]
+fn test_car_invalid_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("bad.car");
+    std::fs::write(&path, b"not a car file").unwrap();
+
+    let output = atp_command()
+        .args(["car", "ls", path.to_str().unwrap()])
+        .output()
+        .expect("Failed to execute car ls");
+
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Invalid CAR file"));
+}
+
+#[test]
+fn test_car_records_rejects_self_referencing_node() {
+    // Blocks aren't hashed while walking, so a node can claim any CID,
+    // including one it links to
+    let node_cid = Cid::for_block(DAG_CBOR, b"node");
+    let node = cbor::encode(&Value::Map(
+        [
+            ("e".to_string(), Value::Array(Vec::new())),
+            ("l".to_string(), Value::Link(node_cid.clone())),
+        ]
+        .into(),
+    ));
+    let commit = cbor::encode(&Value::Map(
+        [
+            (
+                "did".to_string(),
+                Value::String("did:plc:test123".to_string()),
+            ),
+            ("data".to_string(), Value::Link(node_cid.clone())),
+        ]
+        .into(),
+    ));
+    let commit_cid = Cid::for_block(DAG_CBOR, &commit);
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("loop.car");
+    std::fs::write(
+        &path,
+        car_file(
+            std::slice::from_ref(&commit_cid),
+            &[(commit_cid.clone(), commit), (node_cid, node)],
+        ),
+    )
+    .unwrap();
+
+    let output = atp_command()
+        .args(["car", "records", path.to_str().unwrap()])
+        .output()
+        .expect("Failed to execute car records");
+
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("MST is nested more than 128 nodes deep"));
+}
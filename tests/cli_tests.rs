@@ -34,7 +34,7 @@ fn test_version_flag() {
 #[test]
 fn test_atproto_server_describe_server() {
     let output = atp_command()
-        .args(&["atproto", "server", "describe-server"])
+        .args(["atproto", "server", "describe-server"])
         .output()
         .expect("Failed to execute atp atproto server describe-server");
 
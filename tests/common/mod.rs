@@ -29,17 +29,20 @@ pub fn run_atp_with_stdin(args: &[&str], input: &[u8]) -> std::process::Output {
 }
 
 /// Test account DID for authenticated tests
+#[allow(dead_code)]
 pub const TEST_ACCOUNT_DID: &str = "did:plc:bewcbyjd75m7kqc5ykdvtqny";
 
 /// Extract record key from AT URI
+#[allow(dead_code)]
 pub fn extract_rkey_from_uri(uri: &str) -> &str {
-    uri.split('/').last().unwrap()
+    uri.split('/').next_back().unwrap()
 }
 
 /// Clean up a test record by deleting it
+#[allow(dead_code)]
 pub fn cleanup_test_record(repo: &str, collection: &str, rkey: &str) {
     let _cleanup = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "delete-record",
@@ -52,3 +55,482 @@ pub fn cleanup_test_record(repo: &str, collection: &str, rkey: &str) {
         ])
         .output();
 }
+
+/// Create an ATP command whose config directory lives under `home`
+#[allow(dead_code)]
+pub fn atp_command_with_home(home: &std::path::Path) -> Command {
+    let mut cmd = atp_command();
+    cmd.env("HOME", home)
+        .env("XDG_CONFIG_HOME", home.join(".config"));
+    cmd
+}
+
+/// Write an ATP config file under `home`
+#[allow(dead_code)]
+pub fn write_config(home: &std::path::Path, contents: &str) {
+    let dir = home.join(".config").join("atp");
+    std::fs::create_dir_all(&dir).expect("Failed to create config dir");
+    std::fs::write(dir.join("config.toml"), contents).expect("Failed to write config");
+}
+
+/// A request received by [`MockServer`]
+#[allow(dead_code)]
+#[derive(Clone, Debug)]
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+#[allow(dead_code)]
+impl MockRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Path without the query string
+    pub fn route(&self) -> &str {
+        self.path.split('?').next().unwrap()
+    }
+}
+
+/// A minimal local HTTP server standing in for a PDS or directory
+#[allow(dead_code)]
+pub struct MockServer {
+    pub url: String,
+    pub requests: std::sync::Arc<std::sync::Mutex<Vec<MockRequest>>>,
+}
+
+#[allow(dead_code)]
+impl MockServer {
+    /// Serve requests on a background thread, answering each with the
+    /// `(status, content type, body)` returned by `handler`
+    pub fn start<F>(handler: F) -> Self
+    where
+        F: Fn(&MockRequest) -> (u16, &'static str, Vec<u8>) + Send + 'static,
+    {
+        use std::io::{BufRead, BufReader, Read};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = requests.clone();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut line = String::new();
+                if reader.read_line(&mut line).is_err() || line.is_empty() {
+                    continue;
+                }
+                let mut parts = line.split_whitespace();
+                let method = parts.next().unwrap_or_default().to_string();
+                let path = parts.next().unwrap_or_default().to_string();
+
+                let mut headers = Vec::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((key, value)) = line.split_once(':') {
+                        headers.push((key.trim().to_string(), value.trim().to_string()));
+                    }
+                }
+
+                let length = headers
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+                    .and_then(|(_, value)| value.parse().ok())
+                    .unwrap_or(0);
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+
+                let request = MockRequest {
+                    method,
+                    path,
+                    headers,
+                    body,
+                };
+                let (status, content_type, body) = handler(&request);
+                log.lock().unwrap().push(request);
+
+                let head = format!(
+                    "HTTP/1.1 {} Mock\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    content_type,
+                    body.len()
+                );
+                let _ = stream.write_all(head.as_bytes());
+                let _ = stream.write_all(&body);
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// Build a JSON mock response
+#[allow(dead_code)]
+pub fn json_response(status: u16, value: serde_json::Value) -> (u16, &'static str, Vec<u8>) {
+    (status, "application/json", value.to_string().into_bytes())
+}
+
+/// Config with a single logged-in account whose PDS is `pds`
+#[allow(dead_code)]
+pub fn session_config(pds: &str) -> String {
+    format!(
+        r#"
+current = "test"
+
+[accounts.test]
+pds = "{pds}"
+did = "did:plc:test123"
+handle = "test.example.com"
+accessJwt = "old-access"
+refreshJwt = "old-refresh"
+"#
+    )
+}
+
+/// Build a repository CAR file holding `records`, keyed by `collection/rkey`.
+/// All entries sit in a single MST node, and the commit is left unsigned.
+#[allow(dead_code)]
+pub fn repo_car(did: &str, records: &[(&str, serde_json::Value)]) -> Vec<u8> {
+    build_repo_car(did, records, |_| vec![0; 64])
+}
+
+/// Like [`repo_car`], with the commit signed by `key`. A valid MST needs
+/// every key to be on layer 0.
+#[allow(dead_code)]
+pub fn signed_repo_car(
+    did: &str,
+    records: &[(&str, serde_json::Value)],
+    key: &k256::ecdsa::SigningKey,
+) -> Vec<u8> {
+    use k256::ecdsa::{Signature, signature::Signer};
+
+    build_repo_car(did, records, |unsigned| {
+        let signature: Signature = key.sign(unsigned);
+        signature.to_bytes().to_vec()
+    })
+}
+
+/// A DID document naming `key` as the `#atproto` signing key
+#[allow(dead_code)]
+pub fn did_document(did: &str, key: &k256::ecdsa::SigningKey) -> serde_json::Value {
+    let point = key.verifying_key().to_encoded_point(true);
+    let multibase = format!(
+        "z{}",
+        bs58::encode([&[0xe7, 0x01], point.as_bytes()].concat()).into_string()
+    );
+    serde_json::json!({
+        "id": did,
+        "verificationMethod": [{
+            "id": format!("{did}#atproto"),
+            "type": "Multikey",
+            "controller": did,
+            "publicKeyMultibase": multibase,
+        }],
+    })
+}
+
+fn build_repo_car(
+    did: &str,
+    records: &[(&str, serde_json::Value)],
+    sign: impl Fn(&[u8]) -> Vec<u8>,
+) -> Vec<u8> {
+    use atp::{
+        cbor::{self, Value},
+        cid::{Cid, DAG_CBOR},
+    };
+
+    fn map(fields: Vec<(&str, Value)>) -> Value {
+        Value::Map(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    let mut blocks: Vec<(Cid, Vec<u8>)> = Vec::new();
+    let mut block = |value: Value| {
+        let bytes = cbor::encode(&value);
+        let cid = Cid::for_block(DAG_CBOR, &bytes);
+        blocks.push((cid.clone(), bytes));
+        cid
+    };
+
+    let mut records: Vec<_> = records.iter().collect();
+    records.sort_by_key(|(key, _)| *key);
+    let mut entries = Vec::new();
+    let mut previous: &[u8] = &[];
+    for (key, record) in records {
+        let cid = block(Value::from_json(record).unwrap());
+        let key = key.as_bytes();
+        let prefix = key.iter().zip(previous).take_while(|(a, b)| a == b).count();
+        entries.push(map(vec![
+            ("p", Value::Integer(prefix as i64)),
+            ("k", Value::Bytes(key[prefix..].to_vec())),
+            ("v", Value::Link(cid)),
+            ("t", Value::Null),
+        ]));
+        previous = key;
+    }
+    let data = block(map(vec![("l", Value::Null), ("e", Value::Array(entries))]));
+    let mut commit = map(vec![
+        ("did", Value::String(did.to_string())),
+        ("version", Value::Integer(3)),
+        ("data", Value::Link(data)),
+        ("rev", Value::String("3lrevision222".to_string())),
+        ("prev", Value::Null),
+    ]);
+    let sig = sign(&cbor::encode(&commit));
+    if let Value::Map(fields) = &mut commit {
+        fields.insert("sig".to_string(), Value::Bytes(sig));
+    }
+    let commit = block(commit);
+
+    blocks.reverse();
+    car_file(&[commit], &blocks)
+}
+
+/// Serialize a CAR v1 file from its roots and `(CID, block)` pairs
+#[allow(dead_code)]
+pub fn car_file(roots: &[atp::cid::Cid], blocks: &[(atp::cid::Cid, Vec<u8>)]) -> Vec<u8> {
+    use atp::{
+        cbor::{self, Value},
+        cid::write_varint,
+    };
+
+    let header = cbor::encode(&Value::Map(
+        [
+            ("version".to_string(), Value::Integer(1)),
+            (
+                "roots".to_string(),
+                Value::Array(roots.iter().cloned().map(Value::Link).collect()),
+            ),
+        ]
+        .into(),
+    ));
+    let mut car = Vec::new();
+    write_varint(&mut car, header.len() as u64);
+    car.extend_from_slice(&header);
+    for (cid, bytes) in blocks {
+        let cid = cid.to_bytes();
+        write_varint(&mut car, (cid.len() + bytes.len()) as u64);
+        car.extend_from_slice(&cid);
+        car.extend_from_slice(bytes);
+    }
+    car
+}
+
+/// A local stand-in for a relay's `subscribeRepos` WebSocket. It accepts a
+/// single connection, sends `frames` as binary messages and closes.
+#[allow(dead_code)]
+pub struct MockFirehose {
+    pub url: String,
+    pub paths: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+#[allow(dead_code)]
+impl MockFirehose {
+    // The handshake callback's error type is tungstenite's, not ours
+    #[allow(clippy::result_large_err)]
+    pub fn start(frames: Vec<Vec<u8>>) -> Self {
+        use futures_util::SinkExt;
+        use tokio_tungstenite::tungstenite::{Message, handshake::server};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let paths = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = paths.clone();
+
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            runtime.block_on(async move {
+                let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                let (stream, _) = listener.accept().await.unwrap();
+                let callback = |request: &server::Request, response: server::Response| {
+                    seen.lock().unwrap().push(request.uri().to_string());
+                    Ok(response)
+                };
+                let mut socket = tokio_tungstenite::accept_hdr_async(stream, callback)
+                    .await
+                    .unwrap();
+                for frame in frames {
+                    socket.send(Message::Binary(frame.into())).await.unwrap();
+                }
+                let _ = socket.close(None).await;
+            });
+        });
+
+        Self { url, paths }
+    }
+
+    pub fn paths(&self) -> Vec<String> {
+        self.paths.lock().unwrap().clone()
+    }
+}
+
+/// Encode a firehose frame: a `{op: 1, t}` header followed by the body
+#[allow(dead_code)]
+pub fn firehose_frame(kind: &str, body: atp::cbor::Value) -> Vec<u8> {
+    use atp::cbor::{self, Value};
+
+    let op = if kind == "error" { -1 } else { 1 };
+    let mut header = vec![("op".to_string(), Value::Integer(op))];
+    if op == 1 {
+        header.push(("t".to_string(), Value::String(kind.to_string())));
+    }
+    let mut frame = cbor::encode(&Value::Map(header.into_iter().collect()));
+    frame.extend(cbor::encode(&body));
+    frame
+}
+
+/// The `did:key` identifier for `key`
+#[allow(dead_code)]
+pub fn did_key(key: &k256::ecdsa::SigningKey) -> String {
+    atp::crypto::PublicKey::K256(*key.verifying_key()).to_did_key()
+}
+
+/// Sign a PLC operation with `key`, adding its `sig`
+#[allow(dead_code)]
+pub fn sign_plc_operation(
+    mut operation: serde_json::Value,
+    key: &k256::ecdsa::SigningKey,
+) -> serde_json::Value {
+    use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+    use k256::ecdsa::{Signature, signature::Signer};
+
+    let unsigned = atp::cbor::encode(&atp::cbor::Value::from_json(&operation).unwrap());
+    let signature: Signature = key.sign(&unsigned);
+    operation["sig"] = URL_SAFE_NO_PAD.encode(signature.to_bytes()).into();
+    operation
+}
+
+/// A PLC directory audit log entry for a signed operation
+#[allow(dead_code)]
+pub fn plc_log_entry(
+    did: &str,
+    operation: &serde_json::Value,
+    created_at: &str,
+) -> serde_json::Value {
+    serde_json::json!({
+        "did": did,
+        "operation": operation,
+        "cid": atp::plc::operation_cid(operation).unwrap().to_string(),
+        "nullified": false,
+        "createdAt": created_at,
+    })
+}
+
+/// Add an `#atproto_pds` service pointing at `pds` and an `at://` handle
+/// alias to a DID document
+#[allow(dead_code)]
+pub fn with_pds(mut did_doc: serde_json::Value, handle: &str, pds: &str) -> serde_json::Value {
+    did_doc["alsoKnownAs"] = serde_json::json!([format!("at://{handle}")]);
+    did_doc["service"] = serde_json::json!([{
+        "id": "#atproto_pds",
+        "type": "AtprotoPersonalDataServer",
+        "serviceEndpoint": pds,
+    }]);
+    did_doc
+}
+
+/// A local PLC directory serving `documents` at `/<did>`
+#[allow(dead_code)]
+pub fn mock_plc_directory(documents: Vec<serde_json::Value>) -> MockServer {
+    MockServer::start(move |request| {
+        let did = request.route().trim_start_matches('/');
+        match documents.iter().find(|doc| doc["id"] == did) {
+            Some(doc) => json_response(200, doc.clone()),
+            None => json_response(404, serde_json::json!({ "message": "DID not registered" })),
+        }
+    })
+}
+
+/// A local DNS server answering TXT queries over UDP from `records`, keyed
+/// by name without the trailing dot. Other names get NXDOMAIN.
+#[allow(dead_code)]
+pub struct MockDns {
+    pub addr: std::net::SocketAddr,
+    pub queries: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+#[allow(dead_code)]
+impl MockDns {
+    pub fn start(records: Vec<(&str, &str)>) -> Self {
+        let records: Vec<(String, String)> = records
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap();
+        let queries = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = queries.clone();
+
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 512];
+            loop {
+                let Ok((len, peer)) = socket.recv_from(&mut buf) else {
+                    continue;
+                };
+                let query = &buf[..len];
+                // The question starts after the 12-byte header
+                let mut pos = 12;
+                let mut labels = Vec::new();
+                while pos < len && query[pos] != 0 {
+                    let size = query[pos] as usize;
+                    labels.push(
+                        String::from_utf8_lossy(&query[pos + 1..pos + 1 + size]).into_owned(),
+                    );
+                    pos += size + 1;
+                }
+                let question_end = pos + 5;
+                let name = labels.join(".").to_ascii_lowercase();
+                log.lock().unwrap().push(name.clone());
+
+                let answers: Vec<&String> = records
+                    .iter()
+                    .filter(|(record, _)| *record == name)
+                    .map(|(_, value)| value)
+                    .collect();
+                let mut response = query[..2].to_vec();
+                let rcode = if answers.is_empty() { 3 } else { 0 };
+                response.extend_from_slice(&[0x81, 0x80 | rcode, 0, 1]);
+                response.extend_from_slice(&(answers.len() as u16).to_be_bytes());
+                response.extend_from_slice(&[0, 0, 0, 0]);
+                response.extend_from_slice(&query[12..question_end]);
+                for value in answers {
+                    // Name pointer to the question, TXT, IN, TTL 60
+                    response.extend_from_slice(&[0xc0, 0x0c, 0, 16, 0, 1, 0, 0, 0, 60]);
+                    response.extend_from_slice(&((value.len() + 1) as u16).to_be_bytes());
+                    response.push(value.len() as u8);
+                    response.extend_from_slice(value.as_bytes());
+                }
+                let _ = socket.send_to(&response, peer);
+            }
+        });
+
+        Self { addr, queries }
+    }
+
+    pub fn queries(&self) -> Vec<String> {
+        self.queries.lock().unwrap().clone()
+    }
+}
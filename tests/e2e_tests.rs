@@ -13,7 +13,7 @@ fn test_authenticated_create_get_delete_cycle() {
 
     // Step 1: Create a test record
     let create_output = atp_command()
-        .args(&[
+        .args([
             "atproto", "repo", "create-record",
             "--repo", TEST_ACCOUNT_DID,
             "--collection", "app.bsky.feed.post",
@@ -46,7 +46,7 @@ fn test_authenticated_create_get_delete_cycle() {
 
     // Step 2: Retrieve the record we just created
     let get_output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "get-record",
@@ -76,7 +76,7 @@ fn test_authenticated_create_get_delete_cycle() {
 
     // Step 3: Verify it appears in list-records
     let list_output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "list-records",
@@ -102,7 +102,7 @@ fn test_authenticated_create_get_delete_cycle() {
 
     // Step 4: Clean up - delete the record
     let delete_output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "delete-record",
@@ -128,7 +128,7 @@ fn test_authenticated_create_get_delete_cycle() {
 
     // Step 5: Verify the record is gone
     let verify_output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "get-record",
@@ -162,7 +162,7 @@ fn test_authenticated_batch_operations() {
     // Create multiple test records
     for i in 1..=3 {
         let create_output = atp_command()
-            .args(&[
+            .args([
                 "atproto",
                 "repo",
                 "create-record",
@@ -199,7 +199,7 @@ fn test_authenticated_batch_operations() {
 
     // Verify all records exist in list
     let list_output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "list-records",
@@ -230,7 +230,7 @@ fn test_authenticated_batch_operations() {
     // Clean up all created records
     for rkey in created_rkeys {
         let delete_output = atp_command()
-            .args(&[
+            .args([
                 "atproto",
                 "repo",
                 "delete-record",
@@ -1,6 +1,12 @@
 mod common;
 
-use common::atp_command;
+use std::process::Output;
+
+use common::{
+    MockDns, MockServer, atp_command, atp_command_with_home, did_document, did_key, json_response,
+    mock_plc_directory, plc_log_entry, session_config, sign_plc_operation, with_pds, write_config,
+};
+use serde_json::json;
 
 // =============================================================================
 // IDENTITY TESTS - com.atproto.identity.*
@@ -9,7 +15,7 @@ use common::atp_command;
 #[test]
 fn test_identity_resolve_handle_success() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "identity",
             "resolve-handle",
@@ -30,7 +36,7 @@ fn test_identity_resolve_handle_success() {
 #[test]
 fn test_identity_resolve_handle_missing_handle() {
     let output = atp_command()
-        .args(&["atproto", "identity", "resolve-handle"])
+        .args(["atproto", "identity", "resolve-handle"])
         .output()
         .expect("Failed to execute resolve-handle");
 
@@ -48,7 +54,7 @@ fn test_identity_resolve_handle_missing_handle() {
 #[test]
 fn test_identity_resolve_handle_nonexistent() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "identity",
             "resolve-handle",
@@ -72,7 +78,7 @@ fn test_identity_resolve_handle_nonexistent() {
 #[test]
 fn test_identity_resolve_did_missing_did() {
     let output = atp_command()
-        .args(&["atproto", "identity", "resolve-did"])
+        .args(["atproto", "identity", "resolve-did"])
         .output()
         .expect("Failed to execute resolve-did");
 
@@ -87,7 +93,7 @@ fn test_identity_resolve_did_missing_did() {
 #[test]
 fn test_identity_resolve_did_invalid() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "identity",
             "resolve-did",
@@ -112,7 +118,7 @@ fn test_identity_resolve_did_invalid() {
 fn test_identity_resolve_did_requires_auth() {
     // First get a real DID to test with
     let resolve_output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "identity",
             "resolve-handle",
@@ -124,11 +130,15 @@ fn test_identity_resolve_did_requires_auth() {
 
     assert!(resolve_output.status.success());
     let stdout = String::from_utf8(resolve_output.stdout).unwrap();
-    let did = stdout.trim().replace("DID: ", "");
+    let did = stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("DID: "))
+        .unwrap()
+        .to_string();
 
     // Now test resolving that DID - may fail with 404 for some DIDs
     let output = atp_command()
-        .args(&["atproto", "identity", "resolve-did", "--did", &did])
+        .args(["atproto", "identity", "resolve-did", "--did", &did])
         .output()
         .expect("Failed to execute resolve-did");
 
@@ -149,7 +159,7 @@ fn test_identity_resolve_did_requires_auth() {
 #[test]
 fn test_identity_update_handle_requires_auth() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "identity",
             "update-handle",
@@ -172,7 +182,7 @@ fn test_identity_update_handle_requires_auth() {
 #[test]
 fn test_identity_update_handle_missing_handle() {
     let output = atp_command()
-        .args(&["atproto", "identity", "update-handle"])
+        .args(["atproto", "identity", "update-handle"])
         .output()
         .expect("Failed to execute update-handle");
 
@@ -193,7 +203,7 @@ fn test_authenticated_identity_operations() {
 
     // Test resolve-did with authentication
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "identity",
             "resolve-did",
@@ -224,3 +234,677 @@ fn test_authenticated_identity_operations() {
         "Should contain the DID"
     );
 }
+
+/// A PDS that recommends credentials and signs whatever it is asked to
+fn plc_pds() -> MockServer {
+    MockServer::start(|request| match request.route() {
+        "/xrpc/com.atproto.identity.getRecommendedDidCredentials" => json_response(
+            200,
+            json!({
+                "rotationKeys": ["did:key:zQ3shrotation"],
+                "alsoKnownAs": ["at://test.example.com"],
+                "verificationMethods": { "atproto": "did:key:zQ3shsigning" },
+                "services": {
+                    "atproto_pds": {
+                        "type": "AtprotoPersonalDataServer",
+                        "endpoint": "https://pds.example.com",
+                    }
+                },
+            }),
+        ),
+        "/xrpc/com.atproto.identity.signPlcOperation" => {
+            let mut operation: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+            operation.as_object_mut().unwrap().remove("token");
+            operation["sig"] = "signed".into();
+            json_response(200, json!({ "operation": operation }))
+        }
+        "/xrpc/com.atproto.identity.requestPlcOperationSignature"
+        | "/xrpc/com.atproto.identity.submitPlcOperation" => (200, "application/json", Vec::new()),
+        _ => json_response(404, json!({ "error": "MethodNotImplemented" })),
+    })
+}
+
+#[test]
+fn test_identity_get_recommended_did_credentials() {
+    let server = plc_pds();
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+
+    let output = atp_command_with_home(home.path())
+        .args(["atproto", "identity", "get-recommended-did-credentials"])
+        .output()
+        .expect("Failed to execute get-recommended-did-credentials");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Rotation keys:\n  did:key:zQ3shrotation"));
+    assert!(stdout.contains("atproto: did:key:zQ3shsigning"));
+    assert!(stdout.contains("atproto_pds: AtprotoPersonalDataServer https://pds.example.com"));
+}
+
+#[test]
+fn test_identity_sign_plc_operation_overrides_credentials() {
+    let server = plc_pds();
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+    let credentials = json!({
+        "rotationKeys": ["did:key:zQ3shold"],
+        "alsoKnownAs": ["at://test.example.com"],
+    });
+
+    let output = atp_command_with_home(home.path())
+        .args([
+            "--output",
+            "json",
+            "atproto",
+            "identity",
+            "sign-plc-operation",
+            "--token",
+            "ABCDE-12345",
+            "--credentials",
+            &credentials.to_string(),
+            "--rotation-key",
+            "did:key:zQ3shfirst",
+            "--rotation-key",
+            "did:key:zQ3shsecond",
+            "--service",
+            "atproto_pds=https://new.example.com",
+        ])
+        .output()
+        .expect("Failed to execute sign-plc-operation");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let response: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(response["operation"]["sig"], "signed");
+
+    let requests = server.requests();
+    let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(
+        body,
+        json!({
+            "token": "ABCDE-12345",
+            "rotationKeys": ["did:key:zQ3shfirst", "did:key:zQ3shsecond"],
+            "alsoKnownAs": ["at://test.example.com"],
+            "services": {
+                "atproto_pds": {
+                    "type": "AtprotoPersonalDataServer",
+                    "endpoint": "https://new.example.com",
+                }
+            },
+        })
+    );
+}
+
+#[test]
+fn test_identity_sign_plc_operation_invalid_service() {
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config("http://127.0.0.1:9"));
+
+    let output = atp_command_with_home(home.path())
+        .args([
+            "atproto",
+            "identity",
+            "sign-plc-operation",
+            "--service",
+            "custom=https://example.com",
+        ])
+        .output()
+        .expect("Failed to execute sign-plc-operation");
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("expected NAME=TYPE=ENDPOINT"));
+}
+
+#[test]
+fn test_identity_submit_plc_operation_accepts_sign_output() {
+    let server = plc_pds();
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+    let signed = home.path().join("signed.json");
+    std::fs::write(
+        &signed,
+        json!({ "operation": { "type": "plc_operation", "sig": "signed" } }).to_string(),
+    )
+    .unwrap();
+
+    let output = atp_command_with_home(home.path())
+        .args([
+            "atproto",
+            "identity",
+            "submit-plc-operation",
+            "--operation",
+            &format!("@{}", signed.display()),
+        ])
+        .output()
+        .expect("Failed to execute submit-plc-operation");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("PLC operation submitted"));
+
+    let requests = server.requests();
+    assert_eq!(
+        requests[0].route(),
+        "/xrpc/com.atproto.identity.submitPlcOperation"
+    );
+    let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(
+        body,
+        json!({ "operation": { "type": "plc_operation", "sig": "signed" } })
+    );
+}
+
+/// A genesis operation and a handle and PDS change signed by its second
+/// rotation key, with the DID they belong to
+fn plc_history() -> (String, Vec<serde_json::Value>) {
+    let rotation = k256::ecdsa::SigningKey::from_slice(&[1; 32]).unwrap();
+    let recovery = k256::ecdsa::SigningKey::from_slice(&[2; 32]).unwrap();
+    let signing = k256::ecdsa::SigningKey::from_slice(&[3; 32]).unwrap();
+    let operation = |also_known_as: &str, pds: &str, prev: Option<String>| {
+        json!({
+            "type": "plc_operation",
+            "rotationKeys": [did_key(&rotation), did_key(&recovery)],
+            "verificationMethods": { "atproto": did_key(&signing) },
+            "alsoKnownAs": [also_known_as],
+            "services": {
+                "atproto_pds": { "type": "AtprotoPersonalDataServer", "endpoint": pds }
+            },
+            "prev": prev,
+        })
+    };
+
+    let genesis = sign_plc_operation(
+        operation("at://alice.example.com", "https://one.example.com", None),
+        &rotation,
+    );
+    let did = atp::plc::genesis_did(&genesis).unwrap();
+    let genesis_cid = atp::plc::operation_cid(&genesis).unwrap().to_string();
+    let update = sign_plc_operation(
+        operation(
+            "at://bob.example.com",
+            "https://two.example.com",
+            Some(genesis_cid),
+        ),
+        &recovery,
+    );
+    let log = vec![
+        plc_log_entry(&did, &genesis, "2024-01-01T00:00:00.000Z"),
+        plc_log_entry(&did, &update, "2024-06-01T00:00:00.000Z"),
+    ];
+    (did, log)
+}
+
+fn plc_directory(log: Vec<serde_json::Value>) -> MockServer {
+    MockServer::start(move |request| {
+        if request.path.ends_with("/log/audit") {
+            json_response(200, json!(log))
+        } else {
+            json_response(404, json!({ "message": "DID not registered" }))
+        }
+    })
+}
+
+#[test]
+fn test_identity_plc_log_shows_verified_timeline() {
+    let (did, log) = plc_history();
+    let directory = plc_directory(log);
+
+    let output = atp_command()
+        .args([
+            "atproto",
+            "identity",
+            "plc-log",
+            "--did",
+            &did,
+            "--plc-directory",
+            &directory.url,
+        ])
+        .output()
+        .expect("Failed to execute plc-log");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("2 operations, all signatures verified"));
+    assert!(stdout.contains("2024-01-01T00:00:00.000Z  create"));
+    assert!(stdout.contains("handle: alice.example.com\n"));
+    assert!(stdout.contains("handle: alice.example.com → bob.example.com"));
+    assert!(stdout.contains("pds: https://one.example.com → https://two.example.com"));
+    // Unchanged fields are only listed for the genesis operation
+    assert_eq!(stdout.matches("signing key:").count(), 1);
+    assert_eq!(directory.requests()[0].path, format!("/{did}/log/audit"));
+}
+
+#[test]
+fn test_identity_plc_log_json_names_signing_keys() {
+    let (did, log) = plc_history();
+    let recovery_key = log[1]["operation"]["rotationKeys"][1].clone();
+    let directory = plc_directory(log);
+
+    let output = atp_command()
+        .args([
+            "--output",
+            "json",
+            "atproto",
+            "identity",
+            "plc-log",
+            "--did",
+            &did,
+            "--plc-directory",
+            &directory.url,
+        ])
+        .output()
+        .expect("Failed to execute plc-log");
+    assert!(output.status.success());
+    let response: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(response["operations"][1]["type"], "update");
+    assert_eq!(response["operations"][1]["signedBy"], recovery_key);
+    assert_eq!(
+        response["operations"][1]["state"]["pds"],
+        "https://two.example.com"
+    );
+}
+
+#[test]
+fn test_identity_plc_log_rejects_forged_operation() {
+    let (did, mut log) = plc_history();
+    let intruder = k256::ecdsa::SigningKey::from_slice(&[9; 32]).unwrap();
+    let mut forged = log[1]["operation"].clone();
+    forged["alsoKnownAs"] = json!(["at://mallory.example.com"]);
+    let forged = sign_plc_operation(forged, &intruder);
+    log.push(plc_log_entry(&did, &forged, "2024-07-01T00:00:00.000Z"));
+    let forged_cid = log[2]["cid"].as_str().unwrap().to_string();
+    let directory = plc_directory(log);
+
+    let output = atp_command()
+        .args([
+            "atproto",
+            "identity",
+            "plc-log",
+            "--did",
+            &did,
+            "--plc-directory",
+            &directory.url,
+        ])
+        .output()
+        .expect("Failed to execute plc-log");
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(&format!("PLC operation {forged_cid} failed verification")));
+    assert!(stderr.contains("Not signed by any rotation key"));
+}
+
+fn test_did_document(pds: &str) -> serde_json::Value {
+    let key = k256::ecdsa::SigningKey::from_slice(&[7; 32]).unwrap();
+    with_pds(
+        did_document("did:plc:test123", &key),
+        "test.example.com",
+        pds,
+    )
+}
+
+#[test]
+fn test_identity_resolve_did_plc_without_session() {
+    let directory = mock_plc_directory(vec![test_did_document("https://pds.example.com")]);
+    let home = tempfile::tempdir().unwrap();
+
+    let output = atp_command_with_home(home.path())
+        .args([
+            "atproto",
+            "identity",
+            "resolve-did",
+            "--did",
+            "did:plc:test123",
+            "--plc-directory",
+            &directory.url,
+        ])
+        .output()
+        .expect("Failed to execute resolve-did");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Handle: test.example.com"));
+    assert!(stdout.contains("PDS: https://pds.example.com"));
+    assert!(stdout.contains("Signing key: z"));
+    assert_eq!(directory.requests()[0].path, "/did:plc:test123");
+}
+
+#[test]
+fn test_identity_resolve_did_uses_configured_directory() {
+    let directory = mock_plc_directory(vec![test_did_document("https://pds.example.com")]);
+    let home = tempfile::tempdir().unwrap();
+    write_config(
+        home.path(),
+        &format!("plc_directory = \"{}\"\n", directory.url),
+    );
+
+    let output = atp_command_with_home(home.path())
+        .args([
+            "--output",
+            "json",
+            "atproto",
+            "identity",
+            "resolve-did",
+            "--did",
+            "did:plc:test123",
+        ])
+        .output()
+        .expect("Failed to execute resolve-did");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let response: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        response["didDoc"]["alsoKnownAs"][0],
+        "at://test.example.com"
+    );
+    assert_eq!(
+        response["didDoc"]["service"][0]["serviceEndpoint"],
+        "https://pds.example.com"
+    );
+}
+
+#[test]
+fn test_identity_resolve_did_web() {
+    // The document names the DID it is served for, which includes the port
+    let server = MockServer::start(|request| match request.route() {
+        "/.well-known/did.json" => {
+            let host = request.header("host").unwrap().replace(':', "%3A");
+            json_response(
+                200,
+                with_pds(
+                    json!({ "id": format!("did:web:{host}") }),
+                    "web.example.com",
+                    "https://pds.example.com",
+                ),
+            )
+        }
+        _ => json_response(404, json!({})),
+    });
+    let did = format!(
+        "did:web:{}",
+        server.url.trim_start_matches("http://").replace(':', "%3A")
+    );
+
+    let output = atp_command()
+        .args(["atproto", "identity", "resolve-did", "--did", &did])
+        .output()
+        .expect("Failed to execute resolve-did");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(&format!("DID: {did}")));
+    assert!(stdout.contains("Handle: web.example.com"));
+}
+
+#[test]
+fn test_identity_resolve_did_not_registered() {
+    let directory = mock_plc_directory(Vec::new());
+
+    let output = atp_command()
+        .args([
+            "atproto",
+            "identity",
+            "resolve-did",
+            "--did",
+            "did:plc:unknown",
+            "--plc-directory",
+            &directory.url,
+        ])
+        .output()
+        .expect("Failed to execute resolve-did");
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Failed to resolve DID"));
+    assert!(stderr.contains("404"));
+}
+
+/// A PLC directory whose did:plc:test123 document claims `handle`
+fn directory_claiming(handle: &str) -> MockServer {
+    mock_plc_directory(vec![with_pds(
+        json!({ "id": "did:plc:test123" }),
+        handle,
+        "https://pds.example.com",
+    )])
+}
+
+fn resolve_handle(handle: &str, dns: &MockDns, http_base: &str, directory: &str) -> Output {
+    atp_command()
+        .args([
+            "atproto",
+            "identity",
+            "resolve-handle",
+            "--handle",
+            handle,
+            "--dns-server",
+            &dns.addr.to_string(),
+            "--http-base",
+            http_base,
+            "--plc-directory",
+            directory,
+        ])
+        .output()
+        .expect("Failed to execute resolve-handle")
+}
+
+#[test]
+fn test_identity_resolve_handle_through_dns() {
+    let dns = MockDns::start(vec![("_atproto.alice.example.com", "did=did:plc:test123")]);
+    let directory = directory_claiming("alice.example.com");
+
+    let output = resolve_handle(
+        "alice.example.com",
+        &dns,
+        "http://127.0.0.1:9",
+        &directory.url,
+    );
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("DID: did:plc:test123"));
+    assert!(stdout.contains("Method: DNS TXT record"));
+    assert!(stdout.contains("Valid: yes"));
+    assert!(
+        dns.queries()
+            .contains(&"_atproto.alice.example.com".to_string())
+    );
+}
+
+#[test]
+fn test_identity_resolve_handle_falls_back_to_https() {
+    let dns = MockDns::start(Vec::new());
+    let web = MockServer::start(|request| match request.route() {
+        "/.well-known/atproto-did" => (200, "text/plain", b"did:plc:test123\n".to_vec()),
+        _ => json_response(404, json!({})),
+    });
+    let directory = directory_claiming("alice.example.com");
+
+    let output = atp_command()
+        .args([
+            "--output",
+            "json",
+            "atproto",
+            "identity",
+            "resolve-handle",
+            "--handle",
+            "@Alice.Example.com",
+            "--dns-server",
+            &dns.addr.to_string(),
+            "--http-base",
+            &web.url,
+            "--plc-directory",
+            &directory.url,
+        ])
+        .output()
+        .expect("Failed to execute resolve-handle");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let response: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        response,
+        json!({
+            "handle": "alice.example.com",
+            "did": "did:plc:test123",
+            "method": "http",
+            "valid": true,
+        })
+    );
+}
+
+#[test]
+fn test_identity_resolve_handle_not_claimed_back() {
+    let dns = MockDns::start(vec![("_atproto.alice.example.com", "did=did:plc:test123")]);
+    let directory = directory_claiming("someone-else.example.com");
+
+    let output = resolve_handle(
+        "alice.example.com",
+        &dns,
+        "http://127.0.0.1:9",
+        &directory.url,
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Valid: no (the DID document does not list at://alice.example.com)"));
+}
+
+#[test]
+fn test_identity_resolve_handle_unresolvable() {
+    let dns = MockDns::start(Vec::new());
+    let web = MockServer::start(|_| json_response(404, json!({})));
+    let directory = directory_claiming("alice.example.com");
+
+    let output = resolve_handle("alice.example.com", &dns, &web.url, &directory.url);
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Failed to resolve handle alice.example.com"));
+    assert!(stderr.contains("DNS:"));
+    assert!(stderr.contains("HTTPS:"));
+}
+
+/// A PDS accepting updateHandle
+fn handle_pds() -> MockServer {
+    MockServer::start(|request| match request.route() {
+        "/xrpc/com.atproto.identity.updateHandle" => (200, "application/json", Vec::new()),
+        _ => json_response(404, json!({ "error": "MethodNotImplemented" })),
+    })
+}
+
+fn setup_handle(home: &std::path::Path, extra: &[&str]) -> Output {
+    atp_command_with_home(home)
+        .args([
+            "atproto",
+            "identity",
+            "setup-handle",
+            "--handle",
+            "alice.example.com",
+        ])
+        .args(extra)
+        .output()
+        .expect("Failed to execute setup-handle")
+}
+
+#[test]
+fn test_identity_setup_handle_waits_for_well_known_file() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let pds = handle_pds();
+    let dns = MockDns::start(Vec::new());
+    // The file only shows up on the second check
+    let checks = AtomicUsize::new(0);
+    let web = MockServer::start(move |request| match request.route() {
+        "/.well-known/atproto-did" if checks.fetch_add(1, Ordering::SeqCst) > 0 => {
+            (200, "text/plain", b"did:plc:test123".to_vec())
+        }
+        _ => json_response(404, json!({})),
+    });
+    let directory = directory_claiming("alice.example.com");
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&pds.url));
+
+    let output = setup_handle(
+        home.path(),
+        &[
+            "--interval",
+            "0",
+            "--dns-server",
+            &dns.addr.to_string(),
+            "--http-base",
+            &web.url,
+            "--plc-directory",
+            &directory.url,
+        ],
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(stderr.contains("Name:  _atproto.alice.example.com"));
+    assert!(stderr.contains("Value: did=did:plc:test123"));
+    assert!(stderr.contains("https://alice.example.com/.well-known/atproto-did"));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Handle updated to alice.example.com"));
+    assert!(stdout.contains("Verified through: HTTPS well-known file"));
+    assert!(!stdout.contains("does not list the handle"));
+
+    let requests = pds.requests();
+    assert_eq!(requests.len(), 1);
+    let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(body["handle"], "alice.example.com");
+}
+
+#[test]
+fn test_identity_setup_handle_rejects_conflicting_record() {
+    let pds = handle_pds();
+    let dns = MockDns::start(vec![(
+        "_atproto.alice.example.com",
+        "did=did:plc:someoneelse",
+    )]);
+    let web = MockServer::start(|request| match request.route() {
+        "/.well-known/atproto-did" => (200, "text/plain", b"did:plc:test123".to_vec()),
+        _ => json_response(404, json!({})),
+    });
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&pds.url));
+
+    let output = setup_handle(
+        home.path(),
+        &[
+            "--timeout",
+            "0",
+            "--dns-server",
+            &dns.addr.to_string(),
+            "--http-base",
+            &web.url,
+        ],
+    );
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Timed out waiting for alice.example.com"));
+    assert!(stderr.contains("DNS: points at did:plc:someoneelse instead"));
+    assert!(
+        pds.requests().is_empty(),
+        "The handle should not be updated"
+    );
+}
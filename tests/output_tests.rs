@@ -0,0 +1,135 @@
+mod common;
+
+use common::{MockServer, atp_command_with_home, json_response, session_config, write_config};
+use serde_json::json;
+
+// =============================================================================
+// OUTPUT FORMAT TESTS - global --output selector
+// =============================================================================
+
+fn run_with_output(format: &str, args: &[&str]) -> String {
+    let server = MockServer::start(|request| match request.route() {
+        "/xrpc/com.atproto.server.getSession" => json_response(
+            200,
+            json!({"did": "did:plc:test123", "handle": "test.example.com"}),
+        ),
+        "/xrpc/com.atproto.sync.listRepos" => json_response(
+            200,
+            json!({
+                "repos": [
+                    {"did": "did:plc:aaa", "head": "bafyreia", "rev": "3k1"},
+                    {"did": "did:plc:bbbbbb", "head": "bafyreib", "rev": "3k2"}
+                ],
+                "cursor": "next"
+            }),
+        ),
+        "/xrpc/app.bsky.actor.getProfile" => json_response(
+            200,
+            json!({
+                "did": "did:plc:test123",
+                "handle": "test.example.com",
+                "indexedAt": "2024-01-01T00:00:00.000Z",
+                "createdAt": "2023-04-01T00:00:00.000Z",
+                "pinnedPost": {"uri": "at://did:plc:test123/app.bsky.feed.post/3l6oveex3ii2l"},
+                "viewer": {"muted": false, "blockingByList": {"name": "spam"}},
+                "labels": [{"val": "!no-unauthenticated", "src": "did:plc:test123", "cts": "2024-01-01T00:00:00.000Z"}]
+            }),
+        ),
+        _ => json_response(404, json!({"error": "MethodNotImplemented"})),
+    });
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+
+    let output = atp_command_with_home(home.path())
+        .args(["--output", format])
+        .args(args)
+        .output()
+        .expect("Failed to execute atp");
+
+    assert!(
+        output.status.success(),
+        "Command should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_output_json() {
+    let stdout = run_with_output("json", &["atproto", "server", "get-session"]);
+
+    let value: serde_json::Value = serde_json::from_str(&stdout).expect("Should be valid JSON");
+    assert_eq!(value["handle"], "test.example.com");
+    assert_eq!(value["did"], "did:plc:test123");
+}
+
+#[test]
+fn test_output_json_keeps_unmodelled_profile_fields() {
+    let stdout = run_with_output(
+        "json",
+        &["bsky", "actor", "profile", "--actor", "test.example.com"],
+    );
+
+    let value: serde_json::Value = serde_json::from_str(&stdout).expect("Should be valid JSON");
+    assert_eq!(value["createdAt"], "2023-04-01T00:00:00.000Z");
+    assert_eq!(
+        value["pinnedPost"]["uri"],
+        "at://did:plc:test123/app.bsky.feed.post/3l6oveex3ii2l"
+    );
+    assert_eq!(value["viewer"]["blockingByList"]["name"], "spam");
+    assert_eq!(value["labels"][0]["cts"], "2024-01-01T00:00:00.000Z");
+    assert!(
+        value.get("displayName").is_none(),
+        "Should not invent fields the response lacks"
+    );
+}
+
+#[test]
+fn test_output_jsonl_emits_one_line_per_item() {
+    let stdout = run_with_output("jsonl", &["atproto", "sync", "list-repos"]);
+
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2, "Should print one line per repo");
+    let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+    assert_eq!(first["did"], "did:plc:aaa");
+}
+
+#[test]
+fn test_output_yaml() {
+    let stdout = run_with_output("yaml", &["atproto", "server", "get-session"]);
+
+    assert!(stdout.contains("handle: test.example.com"));
+    assert!(stdout.contains("did: did:plc:test123"));
+}
+
+#[test]
+fn test_output_table_aligns_columns() {
+    let stdout = run_with_output("table", &["atproto", "sync", "list-repos"]);
+
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines[0], "DID             HEAD      REV  ACTIVE  STATUS");
+    assert_eq!(lines[1], "did:plc:aaa     bafyreia  3k1");
+    assert_eq!(lines[2], "did:plc:bbbbbb  bafyreib  3k2");
+}
+
+#[test]
+fn test_output_json_for_auth_list() {
+    let stdout = run_with_output("json", &["auth", "list"]);
+
+    let value: serde_json::Value = serde_json::from_str(&stdout).expect("Should be valid JSON");
+    assert_eq!(value[0]["name"], "test");
+    assert_eq!(value[0]["active"], true);
+    assert!(!stdout.contains("access"), "Should not print tokens");
+}
+
+#[test]
+fn test_output_invalid_format() {
+    let output = common::atp_command()
+        .args(["--output", "xml", "auth", "list"])
+        .output()
+        .expect("Failed to execute atp");
+
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("invalid value 'xml'"));
+}
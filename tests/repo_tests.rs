@@ -1,6 +1,11 @@
 mod common;
 
-use common::{TEST_ACCOUNT_DID, atp_command, cleanup_test_record, extract_rkey_from_uri};
+use common::{
+    MockDns, MockServer, TEST_ACCOUNT_DID, atp_command, atp_command_with_home, cleanup_test_record,
+    extract_rkey_from_uri, json_response, mock_plc_directory, session_config, with_pds,
+    write_config,
+};
+use serde_json::json;
 
 // =============================================================================
 // REPOSITORY TESTS - com.atproto.repo.*
@@ -10,7 +15,7 @@ use common::{TEST_ACCOUNT_DID, atp_command, cleanup_test_record, extract_rkey_fr
 #[test]
 fn test_repo_create_record_requires_auth() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "create-record",
@@ -45,7 +50,7 @@ fn test_repo_create_record_requires_auth() {
 #[test]
 fn test_repo_create_record_missing_repo() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "create-record",
@@ -68,7 +73,7 @@ fn test_repo_create_record_missing_repo() {
 #[test]
 fn test_repo_create_record_missing_collection() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "create-record",
@@ -94,7 +99,7 @@ fn test_repo_create_record_missing_collection() {
 #[test]
 fn test_repo_create_record_missing_record() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "create-record",
@@ -120,7 +125,7 @@ fn test_repo_create_record_missing_record() {
 #[test]
 fn test_repo_create_record_invalid_json() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "create-record",
@@ -148,11 +153,100 @@ fn test_repo_create_record_invalid_json() {
     );
 }
 
+#[test]
+fn test_repo_create_record_from_file_fills_type_and_created_at() {
+    let server = MockServer::start(|_| {
+        json_response(
+            200,
+            json!({"uri": "at://did:plc:test123/app.bsky.feed.post/3k", "cid": "bafyreipost"}),
+        )
+    });
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+    let record = home.path().join("post.json");
+    std::fs::write(&record, r#"{"text": "From a file"}"#).unwrap();
+
+    let output = atp_command_with_home(home.path())
+        .args([
+            "atproto",
+            "repo",
+            "create-record",
+            "--repo",
+            "did:plc:test123",
+            "--collection",
+            "app.bsky.feed.post",
+            "--record",
+            &format!("@{}", record.display()),
+        ])
+        .output()
+        .expect("Failed to execute create-record");
+
+    assert!(output.status.success(), "Command should succeed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Created record: at://did:plc:test123/app.bsky.feed.post/3k"));
+    let body: serde_json::Value = serde_json::from_slice(&server.requests()[0].body).unwrap();
+    assert_eq!(body["record"]["text"], "From a file");
+    assert_eq!(body["record"]["$type"], "app.bsky.feed.post");
+    assert!(body["record"]["createdAt"].as_str().unwrap().ends_with('Z'));
+}
+
+#[test]
+fn test_repo_create_record_jsonl_from_stdin() {
+    use std::io::Write;
+
+    let server = MockServer::start(|request| {
+        let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        let text = body["record"]["text"].as_str().unwrap().to_string();
+        json_response(
+            200,
+            json!({"uri": format!("at://did:plc:test123/app.bsky.feed.post/{text}"), "cid": "bafyreipost"}),
+        )
+    });
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+
+    let mut child = atp_command_with_home(home.path())
+        .args([
+            "atproto",
+            "repo",
+            "create-record",
+            "--repo",
+            "did:plc:test123",
+            "--collection",
+            "app.bsky.feed.post",
+            "--record",
+            "-",
+        ])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to execute create-record");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(
+            b"{\"text\": \"one\", \"createdAt\": \"2025-01-01T00:00:00Z\"}\n{\"text\": \"two\"}\n",
+        )
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success(), "Command should succeed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Created 2 records:"));
+    assert!(stdout.contains("at://did:plc:test123/app.bsky.feed.post/two"));
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2, "One createRecord call per line");
+    let first: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(first["record"]["createdAt"], "2025-01-01T00:00:00Z");
+}
+
 // getRecord tests
 #[test]
 fn test_repo_get_record_success() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "get-record",
@@ -182,7 +276,7 @@ fn test_repo_get_record_success() {
 #[test]
 fn test_repo_get_record_missing_repo() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "get-record",
@@ -205,7 +299,7 @@ fn test_repo_get_record_missing_repo() {
 #[test]
 fn test_repo_get_record_missing_collection() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "get-record",
@@ -231,7 +325,7 @@ fn test_repo_get_record_missing_collection() {
 #[test]
 fn test_repo_get_record_missing_rkey() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "get-record",
@@ -254,7 +348,7 @@ fn test_repo_get_record_missing_rkey() {
 #[test]
 fn test_repo_get_record_nonexistent() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "get-record",
@@ -283,7 +377,7 @@ fn test_repo_get_record_nonexistent() {
 #[test]
 fn test_repo_list_records_success() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "list-records",
@@ -310,7 +404,7 @@ fn test_repo_list_records_success() {
 #[test]
 fn test_repo_list_records_missing_repo() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "list-records",
@@ -331,7 +425,7 @@ fn test_repo_list_records_missing_repo() {
 #[test]
 fn test_repo_list_records_missing_collection() {
     let output = atp_command()
-        .args(&["atproto", "repo", "list-records", "--repo", "bsky.app"])
+        .args(["atproto", "repo", "list-records", "--repo", "bsky.app"])
         .output()
         .expect("Failed to execute list-records");
 
@@ -349,7 +443,7 @@ fn test_repo_list_records_missing_collection() {
 #[test]
 fn test_repo_list_records_empty_collection() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "list-records",
@@ -375,7 +469,7 @@ fn test_repo_list_records_empty_collection() {
 #[test]
 fn test_repo_list_records_with_limit() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "list-records",
@@ -405,7 +499,7 @@ fn test_repo_list_records_with_limit() {
 fn test_repo_delete_record_requires_auth() {
     // First create a record to delete
     let create_output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "create-record",
@@ -429,7 +523,7 @@ fn test_repo_delete_record_requires_auth() {
 
     // Now test deleting it
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "delete-record",
@@ -458,7 +552,7 @@ fn test_repo_delete_record_requires_auth() {
 #[test]
 fn test_repo_delete_record_missing_repo() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "delete-record",
@@ -481,7 +575,7 @@ fn test_repo_delete_record_missing_repo() {
 #[test]
 fn test_repo_delete_record_missing_collection() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "delete-record",
@@ -507,7 +601,7 @@ fn test_repo_delete_record_missing_collection() {
 #[test]
 fn test_repo_delete_record_missing_rkey() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "delete-record",
@@ -530,7 +624,7 @@ fn test_repo_delete_record_missing_rkey() {
 #[test]
 fn test_repo_delete_record_nonexistent() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "delete-record",
@@ -562,7 +656,7 @@ fn test_repo_delete_record_auth_flow_validation() {
 
     // First create a record to delete
     let create_output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "create-record",
@@ -586,7 +680,7 @@ fn test_repo_delete_record_auth_flow_validation() {
 
     // Test the authenticated delete flow
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "delete-record",
@@ -620,7 +714,7 @@ fn test_repo_upload_blob_requires_auth() {
     std::fs::write(&temp_file, test_content).expect("Failed to create test file");
 
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "upload-blob",
@@ -657,7 +751,7 @@ fn test_repo_upload_blob_requires_auth() {
 #[test]
 fn test_repo_upload_blob_missing_file() {
     let output = atp_command()
-        .args(&["atproto", "repo", "upload-blob"])
+        .args(["atproto", "repo", "upload-blob"])
         .output()
         .expect("Failed to execute upload-blob");
 
@@ -672,7 +766,7 @@ fn test_repo_upload_blob_missing_file() {
 #[test]
 fn test_repo_upload_blob_nonexistent_file() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "upload-blob",
@@ -714,7 +808,7 @@ fn test_repo_upload_blob_image_file() {
     std::fs::write(&temp_file, png_data).expect("Failed to create test PNG file");
 
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "upload-blob",
@@ -748,7 +842,7 @@ fn test_repo_upload_blob_auth_flow_validation() {
     std::fs::write(&temp_file, test_content).expect("Failed to create test file");
 
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "upload-blob",
@@ -788,7 +882,7 @@ fn test_repo_upload_blob_auth_flow_validation() {
 #[test]
 fn test_repo_describe_repo_success() {
     let output = atp_command()
-        .args(&["atproto", "repo", "describe-repo", "--repo", "bsky.app"])
+        .args(["atproto", "repo", "describe-repo", "--repo", "bsky.app"])
         .output()
         .expect("Failed to execute describe-repo");
 
@@ -812,7 +906,7 @@ fn test_repo_describe_repo_success() {
 #[test]
 fn test_repo_describe_repo_missing_repo() {
     let output = atp_command()
-        .args(&["atproto", "repo", "describe-repo"])
+        .args(["atproto", "repo", "describe-repo"])
         .output()
         .expect("Failed to execute describe-repo");
 
@@ -827,7 +921,7 @@ fn test_repo_describe_repo_missing_repo() {
 #[test]
 fn test_repo_describe_repo_nonexistent() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "describe-repo",
@@ -851,7 +945,7 @@ fn test_repo_describe_repo_nonexistent() {
 #[test]
 fn test_repo_describe_repo_with_did() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "describe-repo",
@@ -878,7 +972,7 @@ fn test_repo_describe_repo_with_did() {
 fn test_repo_describe_repo_public_endpoint() {
     // Test that describe-repo works without authentication (public endpoint)
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "repo",
             "describe-repo",
@@ -897,3 +991,591 @@ fn test_repo_describe_repo_public_endpoint() {
         "Should show the test account DID"
     );
 }
+
+// putRecord tests
+#[test]
+fn test_repo_put_record_missing_rkey() {
+    let output = atp_command()
+        .args([
+            "atproto",
+            "repo",
+            "put-record",
+            "--repo",
+            TEST_ACCOUNT_DID,
+            "--collection",
+            "app.bsky.actor.profile",
+            "--record",
+            r#"{"displayName": "Test"}"#,
+        ])
+        .output()
+        .expect("Failed to execute put-record");
+
+    assert!(!output.status.success(), "Command should fail without rkey");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("rkey") || stderr.contains("required"),
+        "Should show missing rkey error"
+    );
+}
+
+#[test]
+fn test_repo_put_record_sends_swap_guards() {
+    let server = MockServer::start(|_| {
+        json_response(
+            200,
+            json!({
+                "uri": "at://did:plc:test123/app.bsky.actor.profile/self",
+                "cid": "bafyreinew",
+                "validationStatus": "unknown"
+            }),
+        )
+    });
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+
+    let output = atp_command_with_home(home.path())
+        .args([
+            "atproto",
+            "repo",
+            "put-record",
+            "--repo",
+            "did:plc:test123",
+            "--collection",
+            "app.bsky.actor.profile",
+            "--rkey",
+            "self",
+            "--record",
+            r#"{"displayName": "Test"}"#,
+            "--swap-record",
+            "bafyreidqz2dr7cr5h62etpb4hlhgkr6o6aw7y5h74sgzcjjsu4sl7w7fxe",
+            "--swap-commit",
+            "bafyreievaxfmw7drb3ixcjp4y3ftm2pi3xfgzdgyv5vdd5vtzvsgatbqta",
+            "--validate",
+            "false",
+        ])
+        .output()
+        .expect("Failed to execute put-record");
+
+    assert!(output.status.success(), "Command should succeed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Updated record: at://did:plc:test123/app.bsky.actor.profile/self"));
+    let requests = server.requests();
+    assert_eq!(requests[0].route(), "/xrpc/com.atproto.repo.putRecord");
+    let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(body["rkey"], "self");
+    assert_eq!(
+        body["swapRecord"],
+        "bafyreidqz2dr7cr5h62etpb4hlhgkr6o6aw7y5h74sgzcjjsu4sl7w7fxe"
+    );
+    assert_eq!(
+        body["swapCommit"],
+        "bafyreievaxfmw7drb3ixcjp4y3ftm2pi3xfgzdgyv5vdd5vtzvsgatbqta"
+    );
+    assert_eq!(body["validate"], false);
+}
+
+#[test]
+fn test_repo_put_record_from_file() {
+    let server = MockServer::start(|_| {
+        json_response(
+            200,
+            json!({
+                "uri": "at://did:plc:test123/app.bsky.actor.profile/self",
+                "cid": "bafyreinew",
+            }),
+        )
+    });
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+    let record = home.path().join("profile.json");
+    std::fs::write(&record, r#"{"displayName": "From file"}"#).unwrap();
+
+    let output = atp_command_with_home(home.path())
+        .args([
+            "atproto",
+            "repo",
+            "put-record",
+            "at://did:plc:test123/app.bsky.actor.profile/self",
+            "--record",
+            &format!("@{}", record.display()),
+        ])
+        .output()
+        .expect("Failed to execute put-record");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let body: serde_json::Value = serde_json::from_slice(&server.requests()[0].body).unwrap();
+    assert_eq!(body["record"]["displayName"], "From file");
+}
+
+#[test]
+fn test_repo_put_record_swap_mismatch() {
+    let server = MockServer::start(|_| {
+        json_response(
+            400,
+            json!({"error": "InvalidSwap", "message": "Record was at bafyreiother"}),
+        )
+    });
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+
+    let output = atp_command_with_home(home.path())
+        .args([
+            "atproto",
+            "repo",
+            "put-record",
+            "--repo",
+            "did:plc:test123",
+            "--collection",
+            "app.bsky.actor.profile",
+            "--rkey",
+            "self",
+            "--record",
+            "{}",
+            "--swap-record",
+            "bafyreidqz2dr7cr5h62etpb4hlhgkr6o6aw7y5h74sgzcjjsu4sl7w7fxe",
+        ])
+        .output()
+        .expect("Failed to execute put-record");
+
+    assert_eq!(
+        output.status.code(),
+        Some(7),
+        "Should exit with InvalidSwap"
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Failed to put record"));
+    assert!(stderr.contains("Record was at bafyreiother"));
+}
+
+// applyWrites tests
+#[test]
+fn test_repo_apply_writes_batches_and_chains_swap_commit() {
+    let server = MockServer::start(|request| {
+        let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        let writes = body["writes"].as_array().unwrap();
+        let commit = if body["swapCommit"]
+            == "bafyreigm5uumnxb7thbds2s6vlltfp3lfakcgnmjfmonbzvpntnvh5om7i"
+        {
+            "bafyreifhsn5wjogkuwhqg4q3w25m6xdyzmrv727a44frxbgntfkbiynary"
+        } else {
+            "bafyreiawgz5kznt2jiaxzdnivokwqlglheegg6apoekn3ifa4dcvmrghyq"
+        };
+        let results: Vec<_> = writes
+            .iter()
+            .map(|write| match write["$type"].as_str().unwrap() {
+                "com.atproto.repo.applyWrites#delete" => {
+                    json!({"$type": "com.atproto.repo.applyWrites#deleteResult"})
+                }
+                _ => json!({
+                    "$type": "com.atproto.repo.applyWrites#createResult",
+                    "uri": format!("at://did:plc:test123/app.bsky.feed.post/{}", write["rkey"].as_str().unwrap()),
+                    "cid": "bafyreidqz2dr7cr5h62etpb4hlhgkr6o6aw7y5h74sgzcjjsu4sl7w7fxe",
+                }),
+            })
+            .collect();
+        json_response(
+            200,
+            json!({"commit": {"cid": commit, "rev": "3l"}, "results": results}),
+        )
+    });
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+    let writes = home.path().join("writes.jsonl");
+    std::fs::write(
+        &writes,
+        concat!(
+            r#"{"action": "create", "collection": "app.bsky.feed.post", "rkey": "a", "value": {"text": "one"}}"#,
+            "\n\n",
+            r#"{"action": "update", "collection": "app.bsky.feed.post", "rkey": "b", "value": {"text": "two"}}"#,
+            "\n",
+            r#"{"action": "delete", "collection": "app.bsky.feed.post", "rkey": "c"}"#,
+            "\n",
+        ),
+    )
+    .unwrap();
+
+    let output = atp_command_with_home(home.path())
+        .args([
+            "atproto",
+            "repo",
+            "apply-writes",
+            "--repo",
+            "did:plc:test123",
+            "--file",
+            writes.to_str().unwrap(),
+            "--swap-commit",
+            "bafyreigm5uumnxb7thbds2s6vlltfp3lfakcgnmjfmonbzvpntnvh5om7i",
+            "--batch-size",
+            "2",
+        ])
+        .output()
+        .expect("Failed to execute apply-writes");
+
+    assert!(
+        output.status.success(),
+        "Command should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2, "Three writes in batches of two");
+    let first: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    let second: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+    assert_eq!(first["writes"].as_array().unwrap().len(), 2);
+    assert_eq!(
+        first["swapCommit"],
+        "bafyreigm5uumnxb7thbds2s6vlltfp3lfakcgnmjfmonbzvpntnvh5om7i"
+    );
+    assert_eq!(
+        second["swapCommit"],
+        "bafyreifhsn5wjogkuwhqg4q3w25m6xdyzmrv727a44frxbgntfkbiynary"
+    );
+    assert_eq!(
+        second["writes"][0]["$type"],
+        "com.atproto.repo.applyWrites#delete"
+    );
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Applied 3 writes:"));
+    assert!(stdout.contains("create at://did:plc:test123/app.bsky.feed.post/a: bafyreidqz2dr7cr5h62etpb4hlhgkr6o6aw7y5h74sgzcjjsu4sl7w7fxe"));
+    assert!(stdout.contains("delete at://did:plc:test123/app.bsky.feed.post/c"));
+    assert!(
+        stdout.contains(
+            "Commit: bafyreiawgz5kznt2jiaxzdnivokwqlglheegg6apoekn3ifa4dcvmrghyq (rev 3l)"
+        )
+    );
+}
+
+#[test]
+fn test_repo_apply_writes_rejects_invalid_line() {
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config("http://127.0.0.1:1"));
+    let writes = home.path().join("writes.jsonl");
+    std::fs::write(
+        &writes,
+        "{\"action\": \"delete\", \"collection\": \"app.bsky.feed.post\"}\n",
+    )
+    .unwrap();
+
+    let output = atp_command_with_home(home.path())
+        .args([
+            "atproto",
+            "repo",
+            "apply-writes",
+            "--repo",
+            "did:plc:test123",
+            "--file",
+            writes.to_str().unwrap(),
+        ])
+        .output()
+        .expect("Failed to execute apply-writes");
+
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("line 1 is missing an rkey"));
+}
+
+#[test]
+fn test_repo_apply_writes_checks_identifiers_locally() {
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config("http://127.0.0.1:1"));
+    let writes = home.path().join("writes.jsonl");
+    std::fs::write(
+        &writes,
+        concat!(
+            "{\"action\": \"delete\", \"collection\": \"app.bsky.feed.post\", \"rkey\": \"3kpost1\"}\n",
+            "{\"action\": \"delete\", \"collection\": \"posts\", \"rkey\": \"3kpost2\"}\n",
+        ),
+    )
+    .unwrap();
+
+    let output = atp_command_with_home(home.path())
+        .args([
+            "atproto",
+            "repo",
+            "apply-writes",
+            "--repo",
+            "did:plc:test123",
+            "--file",
+            writes.to_str().unwrap(),
+        ])
+        .output()
+        .expect("Failed to execute apply-writes");
+
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Invalid write on line 2"), "{stderr}");
+    assert!(stderr.contains("Invalid NSID posts"), "{stderr}");
+}
+
+#[test]
+fn test_repo_list_records_from_resolved_pds() {
+    let pds = MockServer::start(|request| match request.route() {
+        "/xrpc/com.atproto.repo.listRecords" => json_response(
+            200,
+            json!({
+                "records": [{
+                    "uri": "at://did:plc:test123/app.bsky.feed.post/3kpost1",
+                    "cid": "bafyreib2rxk3rybk3aobmv5cjuql3bm2twh4jo5uxgf5gpqrpbbhhwhvq4",
+                    "value": { "text": "hello" },
+                }],
+            }),
+        ),
+        _ => json_response(404, json!({ "error": "MethodNotImplemented" })),
+    });
+    let directory = mock_plc_directory(vec![with_pds(
+        json!({ "id": "did:plc:test123" }),
+        "test.example.com",
+        &pds.url,
+    )]);
+
+    let output = atp_command()
+        .args([
+            "atproto",
+            "repo",
+            "list-records",
+            "--repo",
+            "did:plc:test123",
+            "--collection",
+            "app.bsky.feed.post",
+            "--plc-directory",
+            &directory.url,
+        ])
+        .output()
+        .expect("Failed to execute list-records");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Found 1 records"));
+    assert!(stdout.contains("at://did:plc:test123/app.bsky.feed.post/3kpost1"));
+    let requests = pds.requests();
+    assert!(requests[0].path.contains("repo=did%3Aplc%3Atest123"));
+    assert!(requests[0].path.contains("collection=app.bsky.feed.post"));
+}
+
+#[test]
+fn test_repo_get_record_from_at_uri() {
+    let pds = MockServer::start(|request| match request.route() {
+        "/xrpc/com.atproto.repo.getRecord" => json_response(
+            200,
+            json!({
+                "uri": "at://did:plc:test123/app.bsky.feed.post/3kpost1",
+                "cid": "bafyreib2rxk3rybk3aobmv5cjuql3bm2twh4jo5uxgf5gpqrpbbhhwhvq4",
+                "value": { "$type": "app.bsky.feed.post", "text": "hello" },
+            }),
+        ),
+        _ => json_response(404, json!({ "error": "MethodNotImplemented" })),
+    });
+    let directory = mock_plc_directory(vec![with_pds(
+        json!({ "id": "did:plc:test123" }),
+        "test.example.com",
+        &pds.url,
+    )]);
+
+    let output = atp_command()
+        .args([
+            "atproto",
+            "repo",
+            "get-record",
+            "at://did:plc:test123/app.bsky.feed.post/3kpost1",
+            "--plc-directory",
+            &directory.url,
+        ])
+        .output()
+        .expect("Failed to execute get-record");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("hello"));
+    let requests = pds.requests();
+    assert!(requests[0].path.contains("repo=did%3Aplc%3Atest123"));
+    assert!(requests[0].path.contains("collection=app.bsky.feed.post"));
+    assert!(requests[0].path.contains("rkey=3kpost1"));
+}
+
+#[test]
+fn test_repo_delete_record_from_bsky_app_link() {
+    let server = MockServer::start(|request| match request.route() {
+        "/xrpc/com.atproto.repo.deleteRecord" => json_response(200, json!({})),
+        _ => json_response(404, json!({ "error": "MethodNotImplemented" })),
+    });
+    let dns = MockDns::start(vec![("_atproto.test.example.com", "did=did:plc:test123")]);
+    let directory = mock_plc_directory(vec![with_pds(
+        json!({ "id": "did:plc:test123" }),
+        "test.example.com",
+        &server.url,
+    )]);
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+
+    let output = atp_command_with_home(home.path())
+        .args([
+            "atproto",
+            "repo",
+            "delete-record",
+            "https://bsky.app/profile/Test.Example.com/post/3kpost1",
+            "--dns-server",
+            &dns.addr.to_string(),
+            "--http-base",
+            "http://127.0.0.1:9",
+            "--plc-directory",
+            &directory.url,
+        ])
+        .output()
+        .expect("Failed to execute delete-record");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // The handle is resolved on the client, not by the PDS
+    assert!(
+        dns.queries()
+            .contains(&"_atproto.test.example.com".to_string())
+    );
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].route(), "/xrpc/com.atproto.repo.deleteRecord");
+    let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(
+        body,
+        json!({
+            "repo": "did:plc:test123",
+            "collection": "app.bsky.feed.post",
+            "rkey": "3kpost1",
+        })
+    );
+}
+
+#[test]
+fn test_repo_put_record_from_at_uri() {
+    let server = MockServer::start(|_| {
+        json_response(
+            200,
+            json!({
+                "uri": "at://did:plc:test123/app.bsky.actor.profile/self",
+                "cid": "bafyreinew",
+            }),
+        )
+    });
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+
+    let output = atp_command_with_home(home.path())
+        .args([
+            "atproto",
+            "repo",
+            "put-record",
+            "at://did:plc:test123/app.bsky.actor.profile/self",
+            "--record",
+            r#"{"displayName": "Test"}"#,
+        ])
+        .output()
+        .expect("Failed to execute put-record");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Updated record: at://did:plc:test123/app.bsky.actor.profile/self"));
+    let body: serde_json::Value = serde_json::from_slice(&server.requests()[0].body).unwrap();
+    assert_eq!(body["repo"], "did:plc:test123");
+    assert_eq!(body["collection"], "app.bsky.actor.profile");
+    assert_eq!(body["rkey"], "self");
+}
+
+#[test]
+fn test_repo_record_uri_rejects_partial_uris_and_flags() {
+    let output = atp_command()
+        .args([
+            "atproto",
+            "repo",
+            "get-record",
+            "at://did:plc:test123/app.bsky.feed.post",
+        ])
+        .output()
+        .expect("Failed to execute get-record");
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("expected at://<repo>/<collection>/<rkey>"));
+
+    let output = atp_command()
+        .args([
+            "atproto",
+            "repo",
+            "get-record",
+            "at://did:plc:test123/app.bsky.feed.post/3kpost1",
+            "--rkey",
+            "3kpost2",
+        ])
+        .output()
+        .expect("Failed to execute get-record");
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("cannot be used with"));
+}
+
+#[test]
+fn test_repo_record_uri_rejects_unclaimed_handle() {
+    let dns = MockDns::start(vec![("_atproto.test.example.com", "did=did:plc:test123")]);
+    let directory = mock_plc_directory(vec![with_pds(
+        json!({ "id": "did:plc:test123" }),
+        "someone-else.example.com",
+        "http://127.0.0.1:9",
+    )]);
+
+    let output = atp_command()
+        .args([
+            "atproto",
+            "repo",
+            "get-record",
+            "at://test.example.com/app.bsky.feed.post/3kpost1",
+            "--dns-server",
+            &dns.addr.to_string(),
+            "--http-base",
+            "http://127.0.0.1:9",
+            "--plc-directory",
+            &directory.url,
+        ])
+        .output()
+        .expect("Failed to execute get-record");
+
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("does not list at://test.example.com"));
+}
+
+#[test]
+fn test_repo_put_record_invalid_json() {
+    let server = MockServer::start(|_| json_response(500, json!({ "error": "Unexpected" })));
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+
+    let output = atp_command_with_home(home.path())
+        .args([
+            "atproto",
+            "repo",
+            "put-record",
+            "at://did:plc:test123/app.bsky.actor.profile/self",
+            "--record",
+            "invalid json",
+        ])
+        .output()
+        .expect("Failed to execute put-record");
+
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Invalid record JSON"));
+    assert!(server.requests().is_empty(), "Nothing should be sent");
+}
@@ -1,6 +1,6 @@
 mod common;
 
-use common::{TEST_ACCOUNT_DID, atp_command};
+use common::atp_command;
 
 // Test account credentials for server tests
 const TEST_ACCOUNT_HANDLE: &str = "atp-test-bot.bsky.social";
@@ -13,7 +13,7 @@ const TEST_ACCOUNT_PASSWORD: &str = "vompa5-riqsah-fovgoS";
 #[test]
 fn test_server_describe_server_success() {
     let output = atp_command()
-        .args(&["atproto", "server", "describe-server"])
+        .args(["atproto", "server", "describe-server"])
         .output()
         .expect("Failed to execute describe-server");
 
@@ -39,7 +39,7 @@ fn test_server_describe_server_success() {
 fn test_server_describe_server_public_endpoint() {
     // Test that describe-server works without authentication (public endpoint)
     let output = atp_command()
-        .args(&["atproto", "server", "describe-server"])
+        .args(["atproto", "server", "describe-server"])
         .output()
         .expect("Failed to execute describe-server");
 
@@ -54,7 +54,7 @@ fn test_server_describe_server_public_endpoint() {
 #[test]
 fn test_server_create_session_success() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "server",
             "create-session",
@@ -84,7 +84,7 @@ fn test_server_create_session_success() {
 #[test]
 fn test_server_create_session_missing_identifier() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "server",
             "create-session",
@@ -108,7 +108,7 @@ fn test_server_create_session_missing_identifier() {
 #[test]
 fn test_server_create_session_missing_password() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "server",
             "create-session",
@@ -132,7 +132,7 @@ fn test_server_create_session_missing_password() {
 #[test]
 fn test_server_create_session_invalid_credentials() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "server",
             "create-session",
@@ -159,7 +159,7 @@ fn test_server_create_session_invalid_credentials() {
 fn test_server_create_session_public_endpoint() {
     // Test that create-session doesn't require existing authentication
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "server",
             "create-session",
@@ -185,7 +185,7 @@ fn test_server_create_session_public_endpoint() {
 #[test]
 fn test_server_get_session_requires_auth() {
     let output = atp_command()
-        .args(&["atproto", "server", "get-session"])
+        .args(["atproto", "server", "get-session"])
         .output()
         .expect("Failed to execute get-session");
 
@@ -207,7 +207,7 @@ fn test_server_get_session_requires_auth() {
 fn test_server_get_session_auth_flow_validation() {
     // Test that get-session properly validates authentication
     let output = atp_command()
-        .args(&["atproto", "server", "get-session"])
+        .args(["atproto", "server", "get-session"])
         .output()
         .expect("Failed to execute get-session");
 
@@ -226,7 +226,7 @@ fn test_server_get_session_auth_flow_validation() {
 #[test]
 fn test_server_refresh_session_requires_auth() {
     let output = atp_command()
-        .args(&["atproto", "server", "refresh-session"])
+        .args(["atproto", "server", "refresh-session"])
         .output()
         .expect("Failed to execute refresh-session");
 
@@ -246,7 +246,7 @@ fn test_server_refresh_session_requires_auth() {
 fn test_server_refresh_session_auth_flow_validation() {
     // Test that refresh-session properly handles refresh tokens
     let output = atp_command()
-        .args(&["atproto", "server", "refresh-session"])
+        .args(["atproto", "server", "refresh-session"])
         .output()
         .expect("Failed to execute refresh-session");
 
@@ -269,7 +269,7 @@ fn test_server_delete_session_requires_auth() {
 
     // Test that the command exists and shows proper help when missing auth
     let output = atp_command()
-        .args(&["atproto", "server", "delete-session", "--help"])
+        .args(["atproto", "server", "delete-session", "--help"])
         .output()
         .expect("Failed to execute delete-session help");
 
@@ -287,7 +287,7 @@ fn test_server_delete_session_auth_flow_validation() {
     // We can't actually delete our session as it would break other tests
 
     let output = atp_command()
-        .args(&["atproto", "server", "delete-session", "--help"])
+        .args(["atproto", "server", "delete-session", "--help"])
         .output()
         .expect("Failed to execute delete-session help");
 
@@ -306,7 +306,7 @@ fn test_server_session_lifecycle() {
 
     // 1. Create session
     let create_output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "server",
             "create-session",
@@ -327,7 +327,7 @@ fn test_server_session_lifecycle() {
 
     // 2. Get session info
     let get_output = atp_command()
-        .args(&["atproto", "server", "get-session"])
+        .args(["atproto", "server", "get-session"])
         .output()
         .expect("Failed to get session");
 
@@ -337,7 +337,7 @@ fn test_server_session_lifecycle() {
 
     // 3. Refresh session
     let refresh_output = atp_command()
-        .args(&["atproto", "server", "refresh-session"])
+        .args(["atproto", "server", "refresh-session"])
         .output()
         .expect("Failed to refresh session");
 
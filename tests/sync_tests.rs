@@ -1,6 +1,15 @@
 mod common;
 
-use common::{TEST_ACCOUNT_DID, atp_command};
+use atp::{
+    cbor::{self, Value},
+    cid::{Cid, DAG_CBOR},
+};
+use common::{
+    MockFirehose, MockServer, TEST_ACCOUNT_DID, atp_command, atp_command_with_home, car_file,
+    did_document, firehose_frame, json_response, mock_plc_directory, session_config,
+    signed_repo_car, with_pds, write_config,
+};
+use serde_json::json;
 
 // =============================================================================
 // SYNC TESTS - com.atproto.sync.*
@@ -10,7 +19,7 @@ use common::{TEST_ACCOUNT_DID, atp_command};
 fn test_sync_get_blob_success() {
     // Test with a known blob CID from a public repository
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "sync",
             "get-blob",
@@ -38,10 +47,150 @@ fn test_sync_get_blob_success() {
     }
 }
 
+#[test]
+fn test_sync_get_blob_saves_to_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("blob.bin");
+    let output = atp_command()
+        .args([
+            "atproto",
+            "sync",
+            "get-blob",
+            "--did",
+            "did:plc:z72i7hdynmk6r22z27h6tvur", // bsky.app
+            "--cid",
+            "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku",
+            "--out",
+            out.to_str().unwrap(),
+        ])
+        .output()
+        .expect("Failed to execute get-blob");
+
+    // As above, the blob may be gone; if it downloads, it must be verified
+    if output.status.success() {
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(
+            stdout.contains("Saved blob"),
+            "Should report the saved blob"
+        );
+        assert!(out.exists(), "Should write the blob to --out");
+    } else {
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("Failed to get blob") || stderr.contains("error"));
+    }
+}
+
+#[test]
+fn test_sync_get_blob_invalid_cid() {
+    let output = atp_command()
+        .args([
+            "atproto",
+            "sync",
+            "get-blob",
+            "--did",
+            "did:plc:z72i7hdynmk6r22z27h6tvur",
+            "--cid",
+            "not-a-cid",
+        ])
+        .output()
+        .expect("Failed to execute get-blob");
+
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Invalid CID not-a-cid"));
+}
+
+#[test]
+fn test_sync_list_blobs_success() {
+    let output = atp_command()
+        .args([
+            "atproto",
+            "sync",
+            "list-blobs",
+            "--did",
+            "did:plc:z72i7hdynmk6r22z27h6tvur", // bsky.app
+            "--limit",
+            "1000",
+        ])
+        .output()
+        .expect("Failed to execute list-blobs");
+
+    if output.status.success() {
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(stdout.contains("Found"), "Should show the blob count");
+    } else {
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("Failed to list blobs") || stderr.contains("error"));
+    }
+}
+
+#[test]
+fn test_sync_list_blobs_download_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let blobs = dir.path().join("blobs");
+    let output = atp_command()
+        .args([
+            "atproto",
+            "sync",
+            "list-blobs",
+            "--did",
+            "did:plc:z72i7hdynmk6r22z27h6tvur", // bsky.app
+            "--since",
+            "3zzzzzzzzzzzz", // Past any real revision, so nothing to fetch
+            "--download-dir",
+            blobs.to_str().unwrap(),
+            "--output",
+            "json",
+        ])
+        .output()
+        .expect("Failed to execute list-blobs");
+
+    if output.status.success() {
+        let response: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert!(response["downloads"].is_array(), "Should report downloads");
+        assert!(blobs.is_dir(), "Should create the download directory");
+    } else {
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("Failed to list blobs") || stderr.contains("error"));
+    }
+}
+
+#[test]
+fn test_sync_list_blobs_missing_did() {
+    let output = atp_command()
+        .args(["atproto", "sync", "list-blobs"])
+        .output()
+        .expect("Failed to execute list-blobs");
+
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("--did") || stderr.contains("required"));
+}
+
+#[test]
+fn test_sync_list_blobs_invalid_concurrency() {
+    let output = atp_command()
+        .args([
+            "atproto",
+            "sync",
+            "list-blobs",
+            "--did",
+            "did:plc:z72i7hdynmk6r22z27h6tvur",
+            "--concurrency",
+            "0",
+        ])
+        .output()
+        .expect("Failed to execute list-blobs");
+
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("concurrency"));
+}
+
 #[test]
 fn test_sync_get_blob_missing_did() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "sync",
             "get-blob",
@@ -62,7 +211,7 @@ fn test_sync_get_blob_missing_did() {
 #[test]
 fn test_sync_get_blob_missing_cid() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "sync",
             "get-blob",
@@ -83,7 +232,7 @@ fn test_sync_get_blob_missing_cid() {
 #[test]
 fn test_sync_get_blob_invalid_did() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "sync",
             "get-blob",
@@ -110,7 +259,7 @@ fn test_sync_get_blob_invalid_did() {
 fn test_sync_get_blob_public_endpoint() {
     // Test that get-blob works without authentication (public endpoint)
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "sync",
             "get-blob",
@@ -132,10 +281,62 @@ fn test_sync_get_blob_public_endpoint() {
     }
 }
 
+#[test]
+fn test_sync_get_repo_success() {
+    let (_pds, directory) = resolved_pds(BLOB);
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("repo.car");
+    let output = atp_command()
+        .args([
+            "atproto",
+            "sync",
+            "get-repo",
+            "--did",
+            "did:plc:test123",
+            "--out",
+            out.to_str().unwrap(),
+            "--plc-directory",
+            &directory.url,
+        ])
+        .output()
+        .expect("Failed to execute get-repo");
+
+    assert!(
+        output.status.success(),
+        "Command should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Commit: bafyrei"), "Should show commit CID");
+    assert!(stdout.contains("Rev:"), "Should show revision");
+    assert!(
+        out.metadata().unwrap().len() > 0,
+        "Should write the CAR file"
+    );
+}
+
+#[test]
+fn test_sync_get_repo_missing_out() {
+    let output = atp_command()
+        .args(["atproto", "sync", "get-repo", "--did", TEST_ACCOUNT_DID])
+        .output()
+        .expect("Failed to execute get-repo");
+
+    assert!(
+        !output.status.success(),
+        "Command should fail without --out"
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("out") || stderr.contains("required"),
+        "Should show missing output path error"
+    );
+}
+
 #[test]
 fn test_sync_get_head_success() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "sync",
             "get-head",
@@ -154,7 +355,7 @@ fn test_sync_get_head_success() {
 #[test]
 fn test_sync_get_head_missing_did() {
     let output = atp_command()
-        .args(&["atproto", "sync", "get-head"])
+        .args(["atproto", "sync", "get-head"])
         .output()
         .expect("Failed to execute get-head");
 
@@ -169,7 +370,7 @@ fn test_sync_get_head_missing_did() {
 #[test]
 fn test_sync_get_head_invalid_did() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "sync",
             "get-head",
@@ -194,7 +395,7 @@ fn test_sync_get_head_invalid_did() {
 fn test_sync_get_head_public_endpoint() {
     // Test that get-head works without authentication (public endpoint)
     let output = atp_command()
-        .args(&["atproto", "sync", "get-head", "--did", TEST_ACCOUNT_DID])
+        .args(["atproto", "sync", "get-head", "--did", TEST_ACCOUNT_DID])
         .output()
         .expect("Failed to execute get-head");
 
@@ -206,7 +407,7 @@ fn test_sync_get_head_public_endpoint() {
 #[test]
 fn test_sync_get_latest_commit_success() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "sync",
             "get-latest-commit",
@@ -229,7 +430,7 @@ fn test_sync_get_latest_commit_success() {
 #[test]
 fn test_sync_get_latest_commit_missing_did() {
     let output = atp_command()
-        .args(&["atproto", "sync", "get-latest-commit"])
+        .args(["atproto", "sync", "get-latest-commit"])
         .output()
         .expect("Failed to execute get-latest-commit");
 
@@ -244,7 +445,7 @@ fn test_sync_get_latest_commit_missing_did() {
 #[test]
 fn test_sync_get_latest_commit_invalid_did() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "sync",
             "get-latest-commit",
@@ -269,7 +470,7 @@ fn test_sync_get_latest_commit_invalid_did() {
 fn test_sync_get_latest_commit_public_endpoint() {
     // Test that get-latest-commit works without authentication (public endpoint)
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "sync",
             "get-latest-commit",
@@ -290,7 +491,7 @@ fn test_sync_get_latest_commit_public_endpoint() {
 #[test]
 fn test_sync_get_repo_status_success() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "sync",
             "get-repo-status",
@@ -310,7 +511,7 @@ fn test_sync_get_repo_status_success() {
 #[test]
 fn test_sync_get_repo_status_missing_did() {
     let output = atp_command()
-        .args(&["atproto", "sync", "get-repo-status"])
+        .args(["atproto", "sync", "get-repo-status"])
         .output()
         .expect("Failed to execute get-repo-status");
 
@@ -325,7 +526,7 @@ fn test_sync_get_repo_status_missing_did() {
 #[test]
 fn test_sync_get_repo_status_invalid_did() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "sync",
             "get-repo-status",
@@ -350,7 +551,7 @@ fn test_sync_get_repo_status_invalid_did() {
 fn test_sync_get_repo_status_public_endpoint() {
     // Test that get-repo-status works without authentication (public endpoint)
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "sync",
             "get-repo-status",
@@ -369,7 +570,7 @@ fn test_sync_get_repo_status_public_endpoint() {
 #[test]
 fn test_sync_list_repos_success() {
     let output = atp_command()
-        .args(&["atproto", "sync", "list-repos", "--limit", "5"])
+        .args(["atproto", "sync", "list-repos", "--limit", "5"])
         .output()
         .expect("Failed to execute list-repos");
 
@@ -386,7 +587,7 @@ fn test_sync_list_repos_success() {
 #[test]
 fn test_sync_list_repos_with_limit() {
     let output = atp_command()
-        .args(&["atproto", "sync", "list-repos", "--limit", "2"])
+        .args(["atproto", "sync", "list-repos", "--limit", "2"])
         .output()
         .expect("Failed to execute list-repos");
 
@@ -407,7 +608,7 @@ fn test_sync_list_repos_with_limit() {
 #[test]
 fn test_sync_list_repos_with_cursor() {
     let output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "sync",
             "list-repos",
@@ -431,7 +632,7 @@ fn test_sync_list_repos_with_cursor() {
 #[test]
 fn test_sync_list_repos_default_limit() {
     let output = atp_command()
-        .args(&["atproto", "sync", "list-repos"])
+        .args(["atproto", "sync", "list-repos"])
         .output()
         .expect("Failed to execute list-repos");
 
@@ -451,7 +652,7 @@ fn test_sync_list_repos_default_limit() {
 fn test_sync_list_repos_public_endpoint() {
     // Test that list-repos works without authentication (public endpoint)
     let output = atp_command()
-        .args(&["atproto", "sync", "list-repos", "--limit", "3"])
+        .args(["atproto", "sync", "list-repos", "--limit", "3"])
         .output()
         .expect("Failed to execute list-repos");
 
@@ -460,13 +661,83 @@ fn test_sync_list_repos_public_endpoint() {
     assert!(stdout.contains("Found"), "Should show found count");
 }
 
+fn repo_page(dids: &[&str], cursor: Option<&str>) -> (u16, &'static str, Vec<u8>) {
+    let repos: Vec<_> = dids
+        .iter()
+        .map(|did| json!({"did": did, "head": "bafyreihead", "rev": "3l"}))
+        .collect();
+    json_response(200, json!({"repos": repos, "cursor": cursor}))
+}
+
+#[test]
+fn test_sync_list_repos_all_follows_cursor() {
+    let server = MockServer::start(|request| {
+        if request.path.contains("cursor=page2") {
+            repo_page(&["did:plc:c"], None)
+        } else {
+            repo_page(&["did:plc:a", "did:plc:b"], Some("page2"))
+        }
+    });
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+
+    let output = atp_command_with_home(home.path())
+        .args(["atproto", "sync", "list-repos", "--all", "--limit", "2"])
+        .output()
+        .expect("Failed to execute list-repos");
+
+    assert!(output.status.success(), "Command should succeed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let dids: Vec<String> = stdout
+        .lines()
+        .map(|line| {
+            serde_json::from_str::<serde_json::Value>(line).unwrap()["did"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    assert_eq!(dids, ["did:plc:a", "did:plc:b", "did:plc:c"]);
+    assert_eq!(server.requests().len(), 2, "Should fetch both pages");
+}
+
+#[test]
+fn test_sync_list_repos_max_stops_early() {
+    let server = MockServer::start(|_| repo_page(&["did:plc:a", "did:plc:b"], Some("more")));
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+
+    let output = atp_command_with_home(home.path())
+        .args([
+            "atproto",
+            "sync",
+            "list-repos",
+            "--max",
+            "3",
+            "--limit",
+            "2",
+        ])
+        .output()
+        .expect("Failed to execute list-repos");
+
+    assert!(output.status.success(), "Command should succeed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 3, "Should stop after --max repos");
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(
+        requests[1].path.contains("limit=1"),
+        "Last page should only ask for what is left"
+    );
+}
+
 #[test]
 fn test_sync_operations_integration() {
     // Test integration between different sync operations
 
     // 1. Get repository status
     let status_output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "sync",
             "get-repo-status",
@@ -485,7 +756,7 @@ fn test_sync_operations_integration() {
 
     // 2. Get repository head
     let head_output = atp_command()
-        .args(&["atproto", "sync", "get-head", "--did", TEST_ACCOUNT_DID])
+        .args(["atproto", "sync", "get-head", "--did", TEST_ACCOUNT_DID])
         .output()
         .expect("Failed to execute get-head");
 
@@ -498,7 +769,7 @@ fn test_sync_operations_integration() {
 
     // 3. Get latest commit
     let commit_output = atp_command()
-        .args(&[
+        .args([
             "atproto",
             "sync",
             "get-latest-commit",
@@ -515,3 +786,448 @@ fn test_sync_operations_integration() {
         "Should show commit info"
     );
 }
+
+// verify-repo tests (offline, against CARs built by common::signed_repo_car)
+fn verify_fixture(
+    dir: &std::path::Path,
+    records: &[(&str, serde_json::Value)],
+) -> (std::path::PathBuf, std::path::PathBuf) {
+    let key = k256::ecdsa::SigningKey::from_slice(&[7; 32]).unwrap();
+    let car = dir.join("repo.car");
+    std::fs::write(&car, signed_repo_car("did:plc:test123", records, &key)).unwrap();
+    let did_doc = dir.join("did.json");
+    std::fs::write(&did_doc, did_document("did:plc:test123", &key).to_string()).unwrap();
+    (car, did_doc)
+}
+
+fn layer_zero_records() -> Vec<(&'static str, serde_json::Value)> {
+    vec![
+        (
+            "app.bsky.feed.like/3klike2",
+            json!({"$type": "app.bsky.feed.like"}),
+        ),
+        ("app.bsky.feed.post/3kpost1", json!({"text": "first"})),
+        ("app.bsky.feed.post/3kpost2", json!({"text": "second"})),
+    ]
+}
+
+fn verify_repo(car: &std::path::Path, did_doc: &std::path::Path) -> std::process::Output {
+    atp_command()
+        .args([
+            "atproto",
+            "sync",
+            "verify-repo",
+            "--car",
+            car.to_str().unwrap(),
+            "--did-doc",
+            did_doc.to_str().unwrap(),
+        ])
+        .output()
+        .expect("Failed to execute verify-repo")
+}
+
+#[test]
+fn test_sync_verify_repo_valid() {
+    let dir = tempfile::tempdir().unwrap();
+    let (car, did_doc) = verify_fixture(dir.path(), &layer_zero_records());
+
+    let output = verify_repo(&car, &did_doc);
+
+    assert!(
+        output.status.success(),
+        "Command should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Verified repository did:plc:test123 at rev 3lrevision222"));
+    assert!(stdout.contains("MST: 1 nodes, 3 records"));
+    assert!(stdout.contains("Signed by: did:key:zQ3s"));
+}
+
+#[test]
+fn test_sync_verify_repo_wrong_key() {
+    let dir = tempfile::tempdir().unwrap();
+    let (car, did_doc) = verify_fixture(dir.path(), &layer_zero_records());
+    let other = k256::ecdsa::SigningKey::from_slice(&[9; 32]).unwrap();
+    std::fs::write(
+        &did_doc,
+        did_document("did:plc:test123", &other).to_string(),
+    )
+    .unwrap();
+
+    let output = verify_repo(&car, &did_doc);
+
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Commit signature verification failed"));
+}
+
+#[test]
+fn test_sync_verify_repo_tampered_record() {
+    let dir = tempfile::tempdir().unwrap();
+    let (car, did_doc) = verify_fixture(dir.path(), &layer_zero_records());
+    let mut bytes = std::fs::read(&car).unwrap();
+    let text = bytes
+        .windows(6)
+        .position(|window| window == b"second")
+        .unwrap();
+    bytes[text] = b'S';
+    std::fs::write(&car, bytes).unwrap();
+
+    let output = verify_repo(&car, &did_doc);
+
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("MST verification failed"));
+    assert!(stderr.contains("does not match its hash"));
+}
+
+#[test]
+fn test_sync_verify_repo_misplaced_key() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut records = layer_zero_records();
+    // Hashes onto layer 1, so it can't share a node with layer 0 keys
+    records.push((
+        "app.bsky.actor.profile/self",
+        json!({"displayName": "Test"}),
+    ));
+    let (car, did_doc) = verify_fixture(dir.path(), &records);
+
+    let output = verify_repo(&car, &did_doc);
+
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("wrong layer"));
+}
+
+// subscribe tests (against common::MockFirehose)
+fn commit_frame(seq: i64, path: &str, record: serde_json::Value) -> Vec<u8> {
+    let record = cbor::encode(&Value::from_json(&record).unwrap());
+    let record_cid = Cid::for_block(DAG_CBOR, &record);
+    let commit_cid = Cid::for_block(DAG_CBOR, b"commit");
+    let blocks = car_file(
+        std::slice::from_ref(&commit_cid),
+        &[(record_cid.clone(), record)],
+    );
+    let mut body = Value::from_json(&json!({
+        "seq": seq,
+        "repo": "did:plc:test123",
+        "rev": "3lrev",
+        "since": null,
+        "commit": {"$link": commit_cid.to_string()},
+        "ops": [{"action": "create", "path": path, "cid": {"$link": record_cid.to_string()}}],
+        "time": "2025-01-01T00:00:00Z",
+    }))
+    .unwrap();
+    if let Value::Map(fields) = &mut body {
+        fields.insert("blocks".to_string(), Value::Bytes(blocks));
+    }
+    firehose_frame("#commit", body)
+}
+
+#[test]
+fn test_sync_subscribe_prints_events_as_jsonl() {
+    let firehose = MockFirehose::start(vec![
+        commit_frame(
+            10,
+            "app.bsky.feed.post/3kpost",
+            json!({"$type": "app.bsky.feed.post", "text": "hello"}),
+        ),
+        firehose_frame(
+            "#identity",
+            Value::from_json(&json!({
+                "seq": 11,
+                "did": "did:plc:test123",
+                "handle": "test.example.com",
+                "time": "2025-01-01T00:00:01Z",
+            }))
+            .unwrap(),
+        ),
+        firehose_frame(
+            "#account",
+            Value::from_json(&json!({
+                "seq": 12,
+                "did": "did:plc:test123",
+                "active": false,
+                "status": "takendown",
+                "time": "2025-01-01T00:00:02Z",
+            }))
+            .unwrap(),
+        ),
+    ]);
+
+    let output = atp_command()
+        .args([
+            "atproto",
+            "sync",
+            "subscribe",
+            "--relay",
+            &firehose.url,
+            "--cursor",
+            "9",
+        ])
+        .output()
+        .expect("Failed to execute subscribe");
+
+    assert!(
+        output.status.success(),
+        "Command should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let events: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[0]["$type"], "#commit");
+    assert_eq!(events[0]["ops"][0]["record"]["text"], "hello");
+    assert!(events[0].get("blocks").is_none(), "Raw blocks are dropped");
+    assert_eq!(events[1]["$type"], "#identity");
+    assert_eq!(events[1]["handle"], "test.example.com");
+    assert_eq!(events[2]["$type"], "#account");
+    assert_eq!(events[2]["status"], "takendown");
+    assert_eq!(
+        firehose.paths(),
+        ["/xrpc/com.atproto.sync.subscribeRepos?cursor=9"]
+    );
+}
+
+#[test]
+fn test_sync_subscribe_filters_collections() {
+    let firehose = MockFirehose::start(vec![
+        commit_frame(
+            1,
+            "app.bsky.feed.like/3klike",
+            json!({"$type": "app.bsky.feed.like"}),
+        ),
+        commit_frame(2, "app.bsky.feed.post/3kpost", json!({"text": "kept"})),
+    ]);
+
+    let output = atp_command()
+        .args([
+            "atproto",
+            "sync",
+            "subscribe",
+            "--relay",
+            &firehose.url,
+            "--collection",
+            "app.bsky.feed.post",
+        ])
+        .output()
+        .expect("Failed to execute subscribe");
+
+    assert!(output.status.success(), "Command should succeed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 1, "Only the post commit is kept");
+    assert!(stdout.contains(r#""seq":2"#));
+}
+
+#[test]
+fn test_sync_subscribe_error_frame() {
+    let firehose = MockFirehose::start(vec![firehose_frame(
+        "error",
+        Value::from_json(&json!({"error": "FutureCursor", "message": "Cursor in the future"}))
+            .unwrap(),
+    )]);
+
+    let output = atp_command()
+        .args(["atproto", "sync", "subscribe", "--relay", &firehose.url])
+        .output()
+        .expect("Failed to execute subscribe");
+
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("FutureCursor: Cursor in the future"));
+}
+
+#[test]
+fn test_sync_subscribe_skips_malformed_frames() {
+    let mut truncated = commit_frame(1, "app.bsky.feed.post/3kfirst", json!({"text": "lost"}));
+    truncated.truncate(truncated.len() / 2);
+    let firehose = MockFirehose::start(vec![
+        truncated,
+        commit_frame(2, "app.bsky.feed.post/3ksecond", json!({"text": "kept"})),
+    ]);
+
+    let output = atp_command()
+        .args(["atproto", "sync", "subscribe", "--relay", &firehose.url])
+        .output()
+        .expect("Failed to execute subscribe");
+
+    assert!(
+        output.status.success(),
+        "Command should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        stdout.lines().count(),
+        1,
+        "Only the intact frame is printed"
+    );
+    assert!(stdout.contains(r#""seq":2"#));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Skipping malformed frame"));
+}
+
+// Tests against a local PDS, found through a local PLC directory
+const BLOB: &[u8] = b"\x89PNG not really an image";
+
+/// A PDS serving a signed repository and `blob` for did:plc:test123, and a
+/// PLC directory pointing at it
+fn resolved_pds(blob: &'static [u8]) -> (MockServer, MockServer) {
+    let key = k256::ecdsa::SigningKey::from_slice(&[7; 32]).unwrap();
+    let car = signed_repo_car("did:plc:test123", &layer_zero_records(), &key);
+    let pds = MockServer::start(move |request| match request.route() {
+        "/xrpc/com.atproto.sync.getRepo" => (200, "application/vnd.ipld.car", car.clone()),
+        "/xrpc/com.atproto.sync.getBlob" => (200, "image/png", blob.to_vec()),
+        _ => json_response(404, json!({ "error": "MethodNotImplemented" })),
+    });
+    let directory = mock_plc_directory(vec![with_pds(
+        did_document("did:plc:test123", &key),
+        "test.example.com",
+        &pds.url,
+    )]);
+    (pds, directory)
+}
+
+#[test]
+fn test_sync_get_blob_from_resolved_pds() {
+    let (pds, directory) = resolved_pds(BLOB);
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("blob.png");
+    let cid = Cid::for_block(atp::cid::RAW, BLOB).to_string();
+
+    let output = atp_command()
+        .args([
+            "atproto",
+            "sync",
+            "get-blob",
+            "--did",
+            "did:plc:test123",
+            "--cid",
+            &cid,
+            "--out",
+            out.to_str().unwrap(),
+            "--plc-directory",
+            &directory.url,
+        ])
+        .output()
+        .expect("Failed to execute get-blob");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(std::fs::read(&out).unwrap(), BLOB);
+    assert!(pds.requests()[0].path.contains(&format!("cid={cid}")));
+}
+
+#[test]
+fn test_sync_get_blob_rejects_cid_mismatch() {
+    let (_pds, directory) = resolved_pds(b"something else entirely");
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("blob.png");
+    let cid = Cid::for_block(atp::cid::RAW, BLOB).to_string();
+
+    let output = atp_command()
+        .args([
+            "atproto",
+            "sync",
+            "get-blob",
+            "--did",
+            "did:plc:test123",
+            "--cid",
+            &cid,
+            "--out",
+            out.to_str().unwrap(),
+            "--plc-directory",
+            &directory.url,
+        ])
+        .output()
+        .expect("Failed to execute get-blob");
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Blob does not match its CID"));
+    assert!(!out.exists(), "A mismatched blob should not be saved");
+}
+
+#[test]
+fn test_sync_get_blob_to_stdout_withholds_mismatched_bytes() {
+    let (_pds, directory) = resolved_pds(b"something else entirely");
+    let cid = Cid::for_block(atp::cid::RAW, BLOB).to_string();
+
+    let output = atp_command()
+        .args([
+            "atproto",
+            "sync",
+            "get-blob",
+            "--did",
+            "did:plc:test123",
+            "--cid",
+            &cid,
+            "--out",
+            "-",
+            "--plc-directory",
+            &directory.url,
+        ])
+        .output()
+        .expect("Failed to execute get-blob");
+    assert!(!output.status.success(), "Command should fail");
+    assert!(
+        output.stdout.is_empty(),
+        "A mismatched blob should not be printed"
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Blob does not match its CID"));
+}
+
+#[test]
+fn test_sync_get_repo_then_verify_with_resolved_did() {
+    let (_pds, directory) = resolved_pds(BLOB);
+    let dir = tempfile::tempdir().unwrap();
+    let car = dir.path().join("repo.car");
+
+    let output = atp_command()
+        .args([
+            "atproto",
+            "sync",
+            "get-repo",
+            "--did",
+            "did:plc:test123",
+            "--out",
+            car.to_str().unwrap(),
+            "--plc-directory",
+            &directory.url,
+        ])
+        .output()
+        .expect("Failed to execute get-repo");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Rev: 3lrevision222"));
+
+    // Without --did-doc the signing key comes from the resolved document
+    let output = atp_command()
+        .args([
+            "atproto",
+            "sync",
+            "verify-repo",
+            "--car",
+            car.to_str().unwrap(),
+            "--plc-directory",
+            &directory.url,
+        ])
+        .output()
+        .expect("Failed to execute verify-repo");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("did:plc:test123"));
+}
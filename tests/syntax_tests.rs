@@ -0,0 +1,118 @@
+mod common;
+
+use common::atp_command;
+
+// =============================================================================
+// SYNTAX TESTS - offline identifier validation
+// =============================================================================
+
+fn check(args: &[&str]) -> std::process::Output {
+    atp_command()
+        .args(["syntax", "check"])
+        .args(args)
+        .output()
+        .expect("Failed to execute syntax check")
+}
+
+#[test]
+fn test_syntax_check_lists_matching_kinds() {
+    let output = check(&["alice.bsky.social"]);
+
+    assert!(output.status.success(), "Command should succeed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("alice.bsky.social is a valid handle, NSID, record key"));
+}
+
+#[test]
+fn test_syntax_check_accepts_raw_cid() {
+    let output = check(&[
+        "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku",
+        "--kind",
+        "cid",
+        "--output",
+        "json",
+    ]);
+
+    assert!(output.status.success(), "Command should succeed");
+    let response: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(response["kinds"], serde_json::json!(["cid"]));
+}
+
+#[test]
+fn test_syntax_check_at_uri() {
+    let output = check(&["at://did:plc:ewvi7nxzyoun6zhxrhs64oiz/app.bsky.feed.post/3l6oveex3ii2l"]);
+    assert!(output.status.success(), "Command should succeed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("is a valid AT-URI"));
+
+    let output = check(&["at://did:plc:ewvi7nxzyoun6zhxrhs64oiz/app.bsky.feed.post/a/b"]);
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("has more than a repository, collection and record key"));
+}
+
+#[test]
+fn test_syntax_check_explains_invalid_kind() {
+    let output = check(&["alice-.example.com", "--kind", "handle"]);
+
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("alice-.example.com is not a valid handle"));
+    assert!(stderr.contains("segment 1 'alice-' starts or ends with a hyphen"));
+}
+
+#[test]
+fn test_syntax_check_explains_every_candidate() {
+    let output = check(&["not valid"]);
+
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("not valid is not a valid identifier"));
+    assert!(stderr.contains("handle: needs at least two dot-separated segments"));
+    assert!(stderr.contains("TID: must be 13 characters, not 9"));
+    assert!(stderr.contains("record key: contains ' '"));
+}
+
+#[test]
+fn test_syntax_check_tid() {
+    let output = check(&["3l6oveex3ii2l", "--kind", "tid"]);
+    assert!(output.status.success(), "Command should succeed");
+
+    let output = check(&["zl6oveex3ii2l", "--kind", "tid"]);
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("first character must be 2-7 or a-j"));
+}
+
+#[test]
+fn test_invalid_identifier_rejected_before_any_request() {
+    let output = atp_command()
+        .args([
+            "atproto",
+            "repo",
+            "get-record",
+            "--repo",
+            "did:plc:test123",
+            "--collection",
+            "posts",
+            "--rkey",
+            "self",
+        ])
+        .output()
+        .expect("Failed to execute get-record");
+
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("error"));
+    assert!(stderr.contains("Invalid NSID posts"));
+}
+
+#[test]
+fn test_syntax_check_cid_with_oversized_digest_length() {
+    // A digest length of u64::MAX must not overflow the end offset
+    let output = check(&["bafyrf77777777777777qcylcmm", "--kind", "cid"]);
+
+    assert_eq!(output.status.code(), Some(1), "Command should fail cleanly");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("CID digest is truncated"));
+}
@@ -0,0 +1,334 @@
+mod common;
+
+use common::{MockServer, atp_command_with_home, json_response, session_config, write_config};
+use serde_json::json;
+
+// =============================================================================
+// XRPC REQUEST LAYER TESTS - against a local PDS stand-in
+// =============================================================================
+
+#[test]
+fn test_expired_token_is_refreshed_and_retried() {
+    let server = MockServer::start(|request| match request.route() {
+        "/xrpc/com.atproto.server.getSession" => {
+            if request.header("authorization") == Some("Bearer new-access") {
+                json_response(
+                    200,
+                    json!({"did": "did:plc:test123", "handle": "test.example.com"}),
+                )
+            } else {
+                json_response(
+                    400,
+                    json!({"error": "ExpiredToken", "message": "Token has expired"}),
+                )
+            }
+        }
+        "/xrpc/com.atproto.server.refreshSession" => json_response(
+            200,
+            json!({
+                "did": "did:plc:test123",
+                "handle": "test.example.com",
+                "accessJwt": "new-access",
+                "refreshJwt": "new-refresh"
+            }),
+        ),
+        _ => json_response(404, json!({"error": "MethodNotImplemented"})),
+    });
+
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+
+    let output = atp_command_with_home(home.path())
+        .args(["atproto", "server", "get-session"])
+        .output()
+        .expect("Failed to execute get-session");
+
+    assert!(
+        output.status.success(),
+        "Should succeed after refreshing: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Handle: test.example.com"));
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3, "Should request, refresh and retry once");
+    assert_eq!(
+        requests[1].header("authorization"),
+        Some("Bearer old-refresh"),
+        "Should refresh with the refresh token"
+    );
+
+    let config = std::fs::read_to_string(home.path().join(".config/atp/config.toml")).unwrap();
+    assert!(config.contains("new-access"), "Should persist new tokens");
+    assert!(config.contains("new-refresh"), "Should persist new tokens");
+}
+
+#[test]
+fn test_refresh_does_not_persist_command_line_overrides() {
+    let server = MockServer::start(|request| match request.route() {
+        "/xrpc/com.atproto.server.getSession" => {
+            if request.header("authorization") == Some("Bearer new-access") {
+                json_response(
+                    200,
+                    json!({"did": "did:plc:test123", "handle": "test.example.com"}),
+                )
+            } else {
+                json_response(400, json!({"error": "ExpiredToken"}))
+            }
+        }
+        "/xrpc/com.atproto.server.refreshSession" => json_response(
+            200,
+            json!({
+                "did": "did:plc:test123",
+                "handle": "test.example.com",
+                "accessJwt": "new-access",
+                "refreshJwt": "new-refresh"
+            }),
+        ),
+        _ => json_response(404, json!({"error": "MethodNotImplemented"})),
+    });
+
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+
+    let output = atp_command_with_home(home.path())
+        .args([
+            "--plc-directory",
+            "http://127.0.0.1:1",
+            "--output",
+            "json",
+            "atproto",
+            "server",
+            "get-session",
+        ])
+        .output()
+        .expect("Failed to execute get-session");
+
+    assert!(
+        output.status.success(),
+        "Should succeed after refreshing: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let config = std::fs::read_to_string(home.path().join(".config/atp/config.toml")).unwrap();
+    assert!(config.contains("new-refresh"), "Should persist new tokens");
+    assert!(
+        !config.contains("127.0.0.1:1"),
+        "Should not persist --plc-directory: {config}"
+    );
+}
+
+#[test]
+fn test_other_errors_are_not_retried() {
+    let server = MockServer::start(|_| {
+        json_response(
+            400,
+            json!({"error": "InvalidRequest", "message": "Record not found"}),
+        )
+    });
+
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+
+    let output = atp_command_with_home(home.path())
+        .args([
+            "atproto",
+            "repo",
+            "delete-record",
+            "--repo",
+            "did:plc:test123",
+            "--collection",
+            "app.bsky.feed.post",
+            "--rkey",
+            "3k2a4b5c6d7e8",
+        ])
+        .output()
+        .expect("Failed to execute delete-record");
+
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Failed to delete record"));
+    assert_eq!(server.requests().len(), 1, "Should not retry");
+}
+
+fn delete_record_against(server: &MockServer) -> std::process::Output {
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+
+    atp_command_with_home(home.path())
+        .args([
+            "atproto",
+            "repo",
+            "delete-record",
+            "--repo",
+            "did:plc:test123",
+            "--collection",
+            "app.bsky.feed.post",
+            "--rkey",
+            "3k2a4b5c6d7e8",
+        ])
+        .output()
+        .expect("Failed to execute delete-record")
+}
+
+#[test]
+fn test_xrpc_error_is_decoded_into_message() {
+    let server = MockServer::start(|_| {
+        json_response(
+            400,
+            json!({"error": "InvalidSwap", "message": "Record was at bafyreiabc"}),
+        )
+    });
+
+    let output = delete_record_against(&server);
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("InvalidSwap: Record was at bafyreiabc"));
+    assert!(stderr.contains("400 Bad Request"));
+}
+
+#[test]
+fn test_xrpc_errors_map_to_exit_codes() {
+    let cases = [
+        (401, "AuthRequired", 3),
+        (400, "RecordNotFound", 4),
+        (400, "InvalidRequest", 5),
+        (429, "RateLimitExceeded", 6),
+        (400, "InvalidSwap", 7),
+        (500, "InternalServerError", 8),
+        (400, "SomethingElse", 9),
+    ];
+
+    for (status, error, code) in cases {
+        let server = MockServer::start(move |_| json_response(status, json!({"error": error})));
+
+        let output = delete_record_against(&server);
+
+        assert_eq!(output.status.code(), Some(code), "Exit code for {error}");
+    }
+}
+
+#[test]
+fn test_error_without_xrpc_body_uses_status() {
+    let server = MockServer::start(|_| (429, "text/plain", b"slow down".to_vec()));
+
+    let output = delete_record_against(&server);
+
+    assert_eq!(output.status.code(), Some(6));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("RateLimitExceeded: slow down"));
+}
+
+// =============================================================================
+// RAW XRPC COMMAND TESTS - atp xrpc <nsid>
+// =============================================================================
+
+#[test]
+fn test_xrpc_command_query_with_params() {
+    let server = MockServer::start(|request| {
+        json_response(200, json!({"path": request.path, "method": request.method}))
+    });
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+
+    let output = atp_command_with_home(home.path())
+        .args([
+            "xrpc",
+            "com.atproto.repo.describeRepo",
+            "--param",
+            "repo=did:plc:test123",
+        ])
+        .output()
+        .expect("Failed to execute xrpc");
+
+    assert!(output.status.success(), "Command should succeed");
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(value["method"], "GET");
+    assert_eq!(
+        value["path"],
+        "/xrpc/com.atproto.repo.describeRepo?repo=did%3Aplc%3Atest123"
+    );
+    assert_eq!(
+        server.requests()[0].header("authorization"),
+        Some("Bearer old-access"),
+        "Should attach the stored session"
+    );
+}
+
+#[test]
+fn test_xrpc_command_procedure_body_from_stdin() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let server = MockServer::start(|request| {
+        let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        json_response(200, json!({"echo": body, "method": request.method}))
+    });
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+
+    let mut child = atp_command_with_home(home.path())
+        .args([
+            "xrpc",
+            "com.atproto.repo.createRecord",
+            "--body",
+            "-",
+            "--output",
+            "jsonl",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start atp");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(br#"{"repo": "did:plc:test123"}"#)
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success(), "Command should succeed");
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(value["method"], "POST");
+    assert_eq!(value["echo"]["repo"], "did:plc:test123");
+}
+
+#[test]
+fn test_xrpc_command_falls_back_to_procedure() {
+    let server = MockServer::start(|request| {
+        if request.method == "GET" {
+            json_response(
+                400,
+                json!({"error": "InvalidRequest", "message": "Incorrect HTTP method (GET) expected POST"}),
+            )
+        } else {
+            json_response(200, json!({"ok": true}))
+        }
+    });
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &session_config(&server.url));
+
+    let output = atp_command_with_home(home.path())
+        .args(["xrpc", "com.atproto.server.activateAccount"])
+        .output()
+        .expect("Failed to execute xrpc");
+
+    assert!(output.status.success(), "Command should succeed");
+    let methods: Vec<_> = server.requests().into_iter().map(|r| r.method).collect();
+    assert_eq!(methods, ["GET", "POST"]);
+}
+
+#[test]
+fn test_xrpc_command_invalid_param() {
+    let output = common::atp_command()
+        .args(["xrpc", "com.atproto.repo.describeRepo", "--param", "repo"])
+        .output()
+        .expect("Failed to execute xrpc");
+
+    assert!(!output.status.success(), "Command should fail");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("expected key=value"));
+}